@@ -0,0 +1,293 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use crate::db::{self, settings, DbConnection};
+use crate::utils::time::{format_sqlite_time, now_utc};
+use super::{create_archive, read_manifest, BackupManifest};
+
+pub const SETTINGS_KEY: &str = "auto_backup";
+pub const BACKUPS_DIR_NAME: &str = "backups";
+
+const AUTO_PREFIX: &str = "auto-";
+// Snapshots taken when the app starts, kept apart from the schedule
+const STARTUP_PREFIX: &str = "startup-";
+const KEEP_STARTUP: usize = 3;
+const ID_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoBackupSettings {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    // Oldest scheduled backup of each of the last N days / ISO weeks is kept,
+    // plus the newest one
+    pub keep_daily: u32,
+    pub keep_weekly: u32,
+}
+
+impl Default for AutoBackupSettings {
+    fn default() -> Self {
+        AutoBackupSettings {
+            enabled: true,
+            interval_hours: 24,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+impl AutoBackupSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_hours == 0 {
+            return Err("Backup interval must be at least 1 hour".to_string());
+        }
+        if self.keep_daily == 0 && self.keep_weekly == 0 {
+            return Err("At least one daily or weekly backup must be kept".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub path: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+    pub schema_version: Option<i32>,
+    pub automatic: bool,
+}
+
+pub fn backups_dir(app_dir: &Path) -> PathBuf {
+    app_dir.join(BACKUPS_DIR_NAME)
+}
+
+// Ids are archive file stems inside the backups directory
+pub fn backup_path_for_id(app_dir: &Path, id: &str) -> Result<PathBuf, String> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err(format!("Invalid backup id: {}", id));
+    }
+
+    let path = backups_dir(app_dir).join(format!("{}.zip", id));
+    if !path.is_file() {
+        return Err(format!("Backup '{}' not found", id));
+    }
+    Ok(path)
+}
+
+// Time of a scheduled backup
+fn auto_backup_time(id: &str) -> Option<NaiveDateTime> {
+    id.strip_prefix(AUTO_PREFIX)
+        .and_then(|stamp| NaiveDateTime::parse_from_str(stamp, ID_TIME_FORMAT).ok())
+}
+
+fn startup_backup_time(id: &str) -> Option<NaiveDateTime> {
+    id.strip_prefix(STARTUP_PREFIX)
+        .and_then(|stamp| NaiveDateTime::parse_from_str(stamp, ID_TIME_FORMAT).ok())
+}
+
+// Newest first
+pub fn list_backups(app_dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    let dir = backups_dir(app_dir);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("zip") {
+            continue;
+        }
+        let id = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => stem.to_string(),
+            None => continue,
+        };
+
+        let size_bytes = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        // A damaged archive is still listed so it can be seen and removed
+        let manifest = read_manifest(&path).ok();
+
+        let automatic_time = auto_backup_time(&id).or_else(|| startup_backup_time(&id));
+        backups.push(BackupInfo {
+            automatic: automatic_time.is_some(),
+            created_at: manifest.as_ref()
+                .map(|m| m.created_at.clone())
+                .or_else(|| automatic_time.map(|t| format_sqlite_time(&t)))
+                .unwrap_or_default(),
+            schema_version: manifest.map(|m| m.schema_version),
+            path: path.to_string_lossy().to_string(),
+            size_bytes,
+            id,
+        });
+    }
+
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+pub fn run_auto_backup(
+    db: &DbConnection,
+    app_dir: &Path,
+    settings: &AutoBackupSettings,
+    startup: bool,
+) -> Result<BackupManifest, String> {
+    let prefix = if startup { STARTUP_PREFIX } else { AUTO_PREFIX };
+    let id = format!("{}{}", prefix, now_utc().format(ID_TIME_FORMAT));
+    let dest = backups_dir(app_dir).join(format!("{}.zip", id));
+
+    let manifest = create_archive(db, app_dir, &dest)?;
+    let removed = rotate(app_dir, settings)?;
+
+    println!("💾 Automatic backup {} ({} old backups rotated out)", id, removed.len());
+    Ok(manifest)
+}
+
+// Scheduled backups to keep: the oldest of each of the last `keep_daily`
+// days and `keep_weekly` ISO weeks, and the newest one so the schedule can
+// tell when the last backup ran
+fn retained<'a>(autos: &'a [(NaiveDateTime, String)], settings: &AutoBackupSettings) -> HashSet<&'a str> {
+    let mut by_time: Vec<&(NaiveDateTime, String)> = autos.iter().collect();
+    by_time.sort_by_key(|(time, _)| *time);
+
+    let mut first_of_day: BTreeMap<NaiveDate, &str> = BTreeMap::new();
+    let mut first_of_week: BTreeMap<(i32, u32), &str> = BTreeMap::new();
+    for (time, id) in &by_time {
+        first_of_day.entry(time.date()).or_insert(id);
+        first_of_week.entry((time.iso_week().year(), time.iso_week().week())).or_insert(id);
+    }
+
+    first_of_day.values().rev().take(settings.keep_daily as usize)
+        .chain(first_of_week.values().rev().take(settings.keep_weekly as usize))
+        .copied()
+        .chain(by_time.last().map(|(_, id)| id.as_str()))
+        .collect()
+}
+
+// Delete automatic backups that fall outside the daily/weekly retention, and
+// all but the last few startup snapshots. Backups created manually in the
+// directory are never touched.
+pub fn rotate(app_dir: &Path, settings: &AutoBackupSettings) -> Result<Vec<String>, String> {
+    let backups = list_backups(app_dir)?;
+    let autos: Vec<(NaiveDateTime, String)> = backups.iter()
+        .filter_map(|b| auto_backup_time(&b.id).map(|t| (t, b.id.clone())))
+        .collect();
+    let mut startups: Vec<(NaiveDateTime, String)> = backups.iter()
+        .filter_map(|b| startup_backup_time(&b.id).map(|t| (t, b.id.clone())))
+        .collect();
+    startups.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+
+    let keep = retained(&autos, settings);
+    let expired = autos.iter()
+        .filter(|(_, id)| !keep.contains(id.as_str()))
+        .chain(startups.iter().skip(KEEP_STARTUP));
+
+    let mut removed = Vec::new();
+    for (_, id) in expired {
+        fs::remove_file(backups_dir(app_dir).join(format!("{}.zip", id)))
+            .map_err(|e| e.to_string())?;
+        removed.push(id.clone());
+    }
+
+    Ok(removed)
+}
+
+fn latest_auto_backup(app_dir: &Path) -> Result<Option<NaiveDateTime>, String> {
+    Ok(list_backups(app_dir)?
+        .iter()
+        .filter_map(|b| auto_backup_time(&b.id))
+        .max())
+}
+
+fn run_if_due(db: &DbConnection, app_dir: &Path, startup: bool) -> Result<(), String> {
+    let settings: AutoBackupSettings = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        settings::load(&conn, SETTINGS_KEY)?
+    };
+
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let due = match latest_auto_backup(app_dir)? {
+        Some(last) => now_utc() - last >= Duration::hours(settings.interval_hours as i64),
+        None => true,
+    };
+
+    // A scheduled backup that is due at startup doubles as the startup snapshot
+    if due || startup {
+        run_auto_backup(db, app_dir, &settings, !due)?;
+    }
+    Ok(())
+}
+
+// Snapshot once at startup, then every `interval_hours` for as long as the
+// app runs. Startup snapshots don't count toward the schedule or retention.
+pub fn start(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let app_dir = match db::app_data_dir(&app_handle) {
+            Ok(dir) => dir,
+            Err(e) => {
+                println!("⚠️ Automatic backups disabled: {}", e);
+                return;
+            }
+        };
+
+        let mut startup = true;
+        loop {
+            let db = app_handle.state::<DbConnection>();
+            if let Err(e) = run_if_due(&db, &app_dir, startup) {
+                println!("⚠️ Automatic backup failed: {}", e);
+            }
+            startup = false;
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup(stamp: &str) -> (NaiveDateTime, String) {
+        let time = NaiveDateTime::parse_from_str(stamp, ID_TIME_FORMAT).unwrap();
+        (time, format!("{}{}", AUTO_PREFIX, stamp))
+    }
+
+    #[test]
+    fn keeps_the_first_backup_of_each_day_and_the_newest() {
+        let autos = vec![
+            backup("20260105-090000"),
+            backup("20260105-150000"),
+            backup("20260106-080000"),
+            backup("20260106-200000"),
+            backup("20260107-100000"),
+            backup("20260107-180000"),
+        ];
+        let settings = AutoBackupSettings { keep_daily: 2, keep_weekly: 0, ..Default::default() };
+
+        let mut keep: Vec<&str> = retained(&autos, &settings).into_iter().collect();
+        keep.sort();
+        assert_eq!(keep, vec!["auto-20260106-080000", "auto-20260107-100000", "auto-20260107-180000"]);
+    }
+
+    #[test]
+    fn weekly_retention_keeps_the_first_backup_of_each_week() {
+        // 2026-01-04 is a Sunday, the 5th starts a new ISO week
+        let autos = vec![
+            backup("20260103-120000"),
+            backup("20260104-120000"),
+            backup("20260105-120000"),
+            backup("20260106-120000"),
+        ];
+        let settings = AutoBackupSettings { keep_daily: 0, keep_weekly: 2, ..Default::default() };
+
+        let mut keep: Vec<&str> = retained(&autos, &settings).into_iter().collect();
+        keep.sort();
+        assert_eq!(keep, vec!["auto-20260103-120000", "auto-20260105-120000", "auto-20260106-120000"]);
+    }
+}
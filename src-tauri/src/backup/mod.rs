@@ -0,0 +1,327 @@
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use crate::db::{migrations, DbConnection, DB_FILE_NAME, IMAGES_DIR_NAME};
+
+pub mod auto;
+
+// A backup is a zip archive holding a snapshot of the database, every file
+// in `problem_images/` and a manifest with checksums for all of them.
+pub const FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub schema_version: i32,
+    pub created_at: String,
+    pub table_counts: BTreeMap<String, i64>,
+    pub files: Vec<BackupFile>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn timestamp_suffix() -> String {
+    chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Consistent copy of the live database using SQLite's online backup API.
+// The connection lock is only held while copying.
+pub fn snapshot_database(db: &DbConnection, dest: &Path) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.backup(DatabaseName::Main, dest, None)
+        .map_err(|e| format!("Database snapshot failed: {}", e))
+}
+
+fn table_counts(conn: &Connection) -> Result<BTreeMap<String, i64>, String> {
+    let tables: Vec<String> = conn.prepare(
+        "SELECT name FROM sqlite_master t
+         WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+           -- Full-text indexes and their shadow tables are derived data
+           AND sql NOT LIKE 'CREATE VIRTUAL TABLE%'
+           AND NOT EXISTS (
+               SELECT 1 FROM sqlite_master v
+               WHERE v.sql LIKE 'CREATE VIRTUAL TABLE%'
+                 AND t.name LIKE v.name || '\\_%' ESCAPE '\\'
+           )
+         ORDER BY name"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map([], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut counts = BTreeMap::new();
+    for table in tables {
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM \"{}\"", table),
+            [],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+        counts.insert(table, count);
+    }
+
+    Ok(counts)
+}
+
+// Snapshot the database and write it with all problem images to `dest`
+pub fn create_archive(db: &DbConnection, app_dir: &Path, dest: &Path) -> Result<BackupManifest, String> {
+    let snapshot_path = app_dir.join(format!("backup-{}.db.tmp", timestamp_suffix()));
+    snapshot_database(db, &snapshot_path)?;
+
+    let result = write_archive(&snapshot_path, &app_dir.join(IMAGES_DIR_NAME), dest);
+    let _ = fs::remove_file(&snapshot_path);
+
+    result
+}
+
+fn write_archive(snapshot_path: &Path, images_dir: &Path, dest: &Path) -> Result<BackupManifest, String> {
+    let (schema_version, table_counts) = {
+        let conn = Connection::open_with_flags(snapshot_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| e.to_string())?;
+        (migrations::current_version(&conn)?, table_counts(&conn)?)
+    };
+
+    let mut entries = vec![(DB_FILE_NAME.to_string(), snapshot_path.to_path_buf())];
+    if images_dir.is_dir() {
+        let mut images = Vec::new();
+        for entry in fs::read_dir(images_dir).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.is_file() {
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    images.push((format!("{}/{}", IMAGES_DIR_NAME, name), path.clone()));
+                }
+            }
+        }
+        images.sort();
+        entries.extend(images);
+    }
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    // Write next to the destination and rename, so a failed backup never
+    // leaves a truncated archive under the real name
+    let partial_path = with_suffix(dest, ".partial");
+    let mut zip = ZipWriter::new(File::create(&partial_path).map_err(|e| e.to_string())?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut files = Vec::with_capacity(entries.len());
+    for (name, path) in entries {
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        files.push(BackupFile {
+            path: name.clone(),
+            size: bytes.len() as u64,
+            sha256: sha256_hex(&bytes),
+        });
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(&bytes).map_err(|e| e.to_string())?;
+    }
+
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        table_counts,
+        files,
+    };
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST_NAME, options).map_err(|e| e.to_string())?;
+    zip.write_all(&manifest_json).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    fs::rename(&partial_path, dest).map_err(|e| e.to_string())?;
+
+    Ok(manifest)
+}
+
+pub fn read_manifest(archive_path: &Path) -> Result<BackupManifest, String> {
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Not a valid backup archive: {}", e))?;
+
+    let mut json = String::new();
+    archive.by_name(MANIFEST_NAME)
+        .map_err(|_| "Backup archive has no manifest".to_string())?
+        .read_to_string(&mut json)
+        .map_err(|e| e.to_string())?;
+
+    serde_json::from_str(&json).map_err(|e| format!("Invalid backup manifest: {}", e))
+}
+
+// Restore a backup archive over the live database and images. Everything is
+// extracted, verified (checksums, SQLite integrity check) and migrated to the
+// current schema before anything is replaced; the previous database is kept
+// as a `.pre-restore` copy.
+pub fn restore_archive(db: &DbConnection, app_dir: &Path, archive_path: &Path) -> Result<BackupManifest, String> {
+    let manifest = read_manifest(archive_path)?;
+
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "Backup format version {} is newer than this app supports",
+            manifest.format_version
+        ));
+    }
+    if manifest.schema_version > migrations::latest_version() {
+        return Err(format!(
+            "Backup schema version {} is newer than this app supports (version {})",
+            manifest.schema_version,
+            migrations::latest_version()
+        ));
+    }
+
+    let suffix = timestamp_suffix();
+    let staging_dir = app_dir.join(format!("restore-staging-{}", suffix));
+    fs::create_dir_all(staging_dir.join(IMAGES_DIR_NAME)).map_err(|e| e.to_string())?;
+
+    let staged_db = staging_dir.join(DB_FILE_NAME);
+    let result = extract_verified(archive_path, &manifest, &staging_dir)
+        .and_then(|_| verify_database(&staged_db))
+        .and_then(|_| migrate_staged(&staged_db))
+        .and_then(|_| verify_database(&staged_db))
+        .and_then(|_| swap_in(db, app_dir, &staging_dir, &suffix));
+
+    let _ = fs::remove_dir_all(&staging_dir);
+
+    result.map(|_| manifest)
+}
+
+fn is_allowed_entry(path: &str) -> bool {
+    if path == DB_FILE_NAME {
+        return true;
+    }
+    match path.strip_prefix(IMAGES_DIR_NAME).and_then(|rest| rest.strip_prefix('/')) {
+        Some(name) => !name.is_empty() && !name.contains(['/', '\\']) && name != ".." && name != ".",
+        None => false,
+    }
+}
+
+fn extract_verified(archive_path: &Path, manifest: &BackupManifest, staging_dir: &Path) -> Result<(), String> {
+    if !manifest.files.iter().any(|f| f.path == DB_FILE_NAME) {
+        return Err("Backup does not contain a database".to_string());
+    }
+
+    let file = File::open(archive_path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    for entry in &manifest.files {
+        if !is_allowed_entry(&entry.path) {
+            return Err(format!("Backup contains an unexpected file: {}", entry.path));
+        }
+
+        let mut bytes = Vec::new();
+        archive.by_name(&entry.path)
+            .map_err(|_| format!("Backup is missing {}", entry.path))?
+            .read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())?;
+
+        if bytes.len() as u64 != entry.size || sha256_hex(&bytes) != entry.sha256 {
+            return Err(format!("Checksum mismatch for {}, the backup is corrupted", entry.path));
+        }
+
+        fs::write(staging_dir.join(&entry.path), bytes).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn verify_database(db_path: &Path) -> Result<(), String> {
+    // Read-write because FTS5 tables need it for their part of the integrity
+    // check. This is the extracted staging copy, never the live database.
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .map_err(|e| e.to_string())?;
+
+    let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if integrity != "ok" {
+        return Err(format!("Backup database failed integrity check: {}", integrity));
+    }
+
+    let violations: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_foreign_key_check",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if violations > 0 {
+        return Err(format!("Backup database has {} foreign key violations", violations));
+    }
+
+    Ok(())
+}
+
+// Backups from older versions are brought up to the current schema in the
+// staging directory, so a failed migration leaves the live database alone
+fn migrate_staged(db_path: &Path) -> Result<(), String> {
+    let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    conn.execute("PRAGMA foreign_keys = ON", [])
+        .map_err(|e| e.to_string())?;
+    migrations::run_migrations(&mut conn, db_path)
+        .map_err(|e| format!("Failed to upgrade the backup database: {}", e))
+}
+
+fn swap_in(db: &DbConnection, app_dir: &Path, staging_dir: &Path, suffix: &str) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let safety_copy = app_dir.join(format!("{}.pre-restore-{}.bak", DB_FILE_NAME, suffix));
+    conn.backup(DatabaseName::Main, &safety_copy, None)
+        .map_err(|e| format!("Failed to save current database before restoring: {}", e))?;
+
+    // Images go first: moving folders is easy to undo, a restored database
+    // isn't
+    let images_dir = app_dir.join(IMAGES_DIR_NAME);
+    let previous_images = app_dir.join(format!("{}.pre-restore-{}", IMAGES_DIR_NAME, suffix));
+    let had_images = images_dir.exists();
+    let put_back_images = || {
+        let _ = fs::remove_dir_all(&images_dir);
+        if had_images {
+            let _ = fs::rename(&previous_images, &images_dir);
+        }
+    };
+
+    if had_images {
+        fs::rename(&images_dir, &previous_images).map_err(|e| e.to_string())?;
+    }
+    if let Err(e) = fs::rename(staging_dir.join(IMAGES_DIR_NAME), &images_dir) {
+        put_back_images();
+        return Err(format!("Failed to restore images: {}", e));
+    }
+
+    // Copies the staged database into the open connection in one transaction
+    if let Err(e) = conn.restore(DatabaseName::Main, staging_dir.join(DB_FILE_NAME), None::<fn(Progress)>) {
+        let _ = conn.restore(DatabaseName::Main, &safety_copy, None::<fn(Progress)>);
+        put_back_images();
+        return Err(format!("Database restore failed: {}", e));
+    }
+
+    // Both are in place; only now is the old images folder no longer needed
+    let _ = fs::remove_dir_all(&previous_images);
+
+    println!("♻️ Restored database (previous copy kept at {:?})", safety_copy);
+    Ok(())
+}
@@ -0,0 +1,921 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use crate::mastery::{self, RecomputeSummary};
+use crate::scheduling;
+use crate::segmentation;
+use crate::utils::time::{format_sqlite_time, parse_sqlite_time};
+use crate::utils::{generate_problem_id, problem_id_prefix};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subject {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub commentary: Option<String>,
+    // Prefix of the generated IDs of new problems, e.g. "MATH"
+    pub id_prefix: String,
+    pub material_count: usize,
+    pub problem_count: usize,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Material {
+    pub id: i64,
+    pub name_en: String,
+    pub name_ru: Option<String>,
+    pub commentary: Option<String>,
+    pub subjects: Vec<String>,
+    pub problem_count: usize,
+    pub drill_count: usize,
+    pub vocabulary_count: usize,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdChange {
+    pub problem_id: i64,
+    pub old_id: String,
+    pub new_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubjectUpdate {
+    pub subject: Subject,
+    // Problem IDs rewritten to the new prefix
+    pub regenerated: Vec<IdChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MaterialMerge {
+    pub material: Material,
+    pub problems_moved: usize,
+    // Problems with the same title in both, merged into one
+    pub problems_merged: usize,
+    pub drills_moved: usize,
+    pub vocabulary_moved: usize,
+    pub terms_moved: usize,
+    // The kept material may now fall under other subjects' mastery policies
+    pub solved_status: RecomputeSummary,
+}
+
+// What a delete would take with it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeletionPreview {
+    // Materials deleted along with it; for a subject, those linked to no
+    // other subject
+    pub materials: Vec<String>,
+    // Materials of a subject that stay because another subject uses them
+    pub kept_materials: Vec<String>,
+    pub problems: usize,
+    pub batches: usize,
+    pub attempts: usize,
+    pub drills: usize,
+    pub terms: usize,
+    // Vocabulary is kept but loses its material
+    pub vocabulary_unlinked: usize,
+    pub mastery_policy: bool,
+    // Problem images left unused in the images folder
+    pub image_files: Vec<String>,
+}
+
+fn clean(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+fn subject_from_row(row: &rusqlite::Row) -> rusqlite::Result<Subject> {
+    let name: String = row.get(1)?;
+    Ok(Subject {
+        id: row.get(0)?,
+        id_prefix: problem_id_prefix(&name),
+        name,
+        description: row.get(2)?,
+        commentary: row.get(3)?,
+        material_count: row.get(4)?,
+        problem_count: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+const SUBJECT_COLUMNS: &str =
+    "s.id, s.name, s.description, s.commentary,
+     (SELECT COUNT(*) FROM SubjectMaterials sm WHERE sm.subject_id = s.id),
+     (SELECT COUNT(*) FROM Problems p JOIN SubjectMaterials sm ON p.material_id = sm.material_id
+      WHERE sm.subject_id = s.id),
+     s.created_at, s.updated_at";
+
+pub fn list_subjects(conn: &Connection) -> Result<Vec<Subject>, String> {
+    conn.prepare(&format!("SELECT {} FROM Subjects s ORDER BY s.name", SUBJECT_COLUMNS))
+        .map_err(|e| e.to_string())?
+        .query_map([], subject_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_subject(conn: &Connection, subject_id: i64) -> Result<Subject, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM Subjects s WHERE s.id = ?1", SUBJECT_COLUMNS),
+        params![subject_id],
+        subject_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Subject {} not found", subject_id))
+}
+
+// Materials of a subject, or all materials when `subject_name` is None
+pub fn list_materials(conn: &Connection, subject_name: Option<&str>) -> Result<Vec<Material>, String> {
+    let material_ids: Vec<i64> = conn.prepare(
+        "SELECT m.id FROM Materials m
+         WHERE ?1 IS NULL OR m.id IN (
+             SELECT sm.material_id FROM SubjectMaterials sm
+             JOIN Subjects s ON sm.subject_id = s.id
+             WHERE s.name = ?1
+         )
+         ORDER BY m.name_en"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![subject_name], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    material_ids.into_iter().map(|id| load_material(conn, id)).collect()
+}
+
+pub fn load_material(conn: &Connection, material_id: i64) -> Result<Material, String> {
+    let mut material = conn.query_row(
+        "SELECT m.id, m.name_en, m.name_ru, m.commentary,
+                (SELECT COUNT(*) FROM Problems p WHERE p.material_id = m.id),
+                (SELECT COUNT(*) FROM RussianDrillAttempts d WHERE d.material_id = m.id),
+                (SELECT COUNT(*) FROM RussianVocabulary v WHERE v.material_id = m.id),
+                m.created_at, m.updated_at
+         FROM Materials m WHERE m.id = ?1",
+        params![material_id],
+        |row| Ok(Material {
+            id: row.get(0)?,
+            name_en: row.get(1)?,
+            name_ru: row.get(2)?,
+            commentary: row.get(3)?,
+            subjects: Vec::new(),
+            problem_count: row.get(4)?,
+            drill_count: row.get(5)?,
+            vocabulary_count: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Material {} not found", material_id))?;
+
+    material.subjects = conn.prepare(
+        "SELECT s.name FROM SubjectMaterials sm
+         JOIN Subjects s ON sm.subject_id = s.id
+         WHERE sm.material_id = ?1
+         ORDER BY s.name"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(material)
+}
+
+fn check_subject_name(conn: &Connection, subject_id: Option<i64>, name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Subject name cannot be empty".to_string());
+    }
+    let taken: Option<i64> = conn.query_row(
+        "SELECT id FROM Subjects WHERE name = ?1",
+        params![name],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    if taken.is_some_and(|id| Some(id) != subject_id) {
+        return Err(format!("A subject named '{}' already exists", name));
+    }
+    Ok(())
+}
+
+fn check_material_name(conn: &Connection, material_id: Option<i64>, name_en: &str) -> Result<(), String> {
+    if name_en.is_empty() {
+        return Err("Material name cannot be empty".to_string());
+    }
+    let taken: Option<i64> = conn.query_row(
+        "SELECT id FROM Materials WHERE name_en = ?1",
+        params![name_en],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    if taken.is_some_and(|id| Some(id) != material_id) {
+        return Err(format!("A material named '{}' already exists; merge them instead", name_en));
+    }
+    Ok(())
+}
+
+pub fn create_subject(
+    conn: &Connection,
+    name: &str,
+    description: Option<&str>,
+    commentary: Option<&str>,
+) -> Result<i64, String> {
+    let name = name.trim();
+    check_subject_name(conn, None, name)?;
+
+    conn.execute(
+        "INSERT INTO Subjects (name, description, commentary) VALUES (?1, ?2, ?3)",
+        params![name, clean(description), clean(commentary)],
+    ).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Problems of a subject's materials that carry its ID prefix
+fn prefixed_problems(conn: &Connection, subject_id: i64, prefix: &str) -> Result<Vec<(i64, String)>, String> {
+    let problems: Vec<(i64, String)> = conn.prepare(
+        "SELECT DISTINCT p.id, p.generated_id FROM Problems p
+         JOIN SubjectMaterials sm ON p.material_id = sm.material_id
+         WHERE sm.subject_id = ?1
+         ORDER BY p.id"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![subject_id], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(problems.into_iter()
+        .filter(|(_, generated_id)| {
+            generated_id.rsplit_once('_').is_some_and(|(head, _)| head == prefix)
+        })
+        .collect())
+}
+
+// Edit a subject. A rename that changes the ID prefix is refused while
+// problems still carry the old prefix, unless `regenerate_ids` rewrites
+// their IDs to the new one.
+pub fn update_subject(
+    conn: &Connection,
+    subject_id: i64,
+    name: &str,
+    description: Option<&str>,
+    commentary: Option<&str>,
+    regenerate_ids: bool,
+) -> Result<Vec<IdChange>, String> {
+    let subject = load_subject(conn, subject_id)?;
+    let name = name.trim();
+    check_subject_name(conn, Some(subject_id), name)?;
+
+    let new_prefix = problem_id_prefix(name);
+    let affected = if new_prefix == subject.id_prefix {
+        Vec::new()
+    } else {
+        prefixed_problems(conn, subject_id, &subject.id_prefix)?
+    };
+    if !affected.is_empty() && !regenerate_ids {
+        return Err(format!(
+            "Renaming '{}' to '{}' changes the problem ID prefix from {} to {}, but {} problems use {}. Regenerate their IDs or keep the prefix.",
+            subject.name, name, subject.id_prefix, new_prefix, affected.len(), subject.id_prefix
+        ));
+    }
+
+    conn.execute(
+        "UPDATE Subjects SET name = ?1, description = ?2, commentary = ?3, updated_at = datetime('now')
+         WHERE id = ?4",
+        params![name, clean(description), clean(commentary), subject_id],
+    ).map_err(|e| e.to_string())?;
+
+    // One at a time so each new ID sees the ones before it
+    let mut regenerated = Vec::new();
+    for (problem_id, old_id) in affected {
+        let new_id = generate_problem_id(conn, name)?;
+        conn.execute(
+            "UPDATE Problems SET generated_id = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![new_id, problem_id],
+        ).map_err(|e| e.to_string())?;
+        regenerated.push(IdChange { problem_id, old_id, new_id });
+    }
+
+    Ok(regenerated)
+}
+
+pub fn create_material(
+    conn: &Connection,
+    subject_id: i64,
+    name_en: &str,
+    name_ru: Option<&str>,
+    commentary: Option<&str>,
+) -> Result<i64, String> {
+    load_subject(conn, subject_id)?;
+    let name_en = name_en.trim();
+    check_material_name(conn, None, name_en)?;
+
+    conn.execute(
+        "INSERT INTO Materials (name_en, name_ru, commentary) VALUES (?1, ?2, ?3)",
+        params![name_en, clean(name_ru), clean(commentary)],
+    ).map_err(|e| e.to_string())?;
+    let material_id = conn.last_insert_rowid();
+
+    conn.execute(
+        "INSERT INTO SubjectMaterials (subject_id, material_id) VALUES (?1, ?2)",
+        params![subject_id, material_id],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(material_id)
+}
+
+pub fn update_material(
+    conn: &Connection,
+    material_id: i64,
+    name_en: &str,
+    name_ru: Option<&str>,
+    commentary: Option<&str>,
+) -> Result<(), String> {
+    load_material(conn, material_id)?;
+    let name_en = name_en.trim();
+    check_material_name(conn, Some(material_id), name_en)?;
+
+    conn.execute(
+        "UPDATE Materials SET name_en = ?1, name_ru = ?2, commentary = ?3, updated_at = datetime('now')
+         WHERE id = ?4",
+        params![name_en, clean(name_ru), clean(commentary), material_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Re-evaluate a material's problems, e.g. after its subjects (and so its
+// mastery policy) changed
+fn recompute_material(conn: &Connection, material_id: i64) -> Result<RecomputeSummary, String> {
+    let problem_ids: Vec<i64> = conn.prepare("SELECT id FROM Problems WHERE material_id = ?1")
+        .map_err(|e| e.to_string())?
+        .query_map(params![material_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut summary = RecomputeSummary::default();
+    for problem_id in problem_ids {
+        let (was_solved, is_solved) = mastery::update_solved_status(conn, problem_id)?;
+        summary.evaluated += 1;
+        match (was_solved, is_solved) {
+            (false, true) => summary.newly_solved += 1,
+            (true, false) => summary.newly_unsolved += 1,
+            _ => {}
+        }
+    }
+    Ok(summary)
+}
+
+pub fn link_material(conn: &Connection, subject_id: i64, material_id: i64) -> Result<RecomputeSummary, String> {
+    load_subject(conn, subject_id)?;
+    load_material(conn, material_id)?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO SubjectMaterials (subject_id, material_id) VALUES (?1, ?2)",
+        params![subject_id, material_id],
+    ).map_err(|e| e.to_string())?;
+
+    recompute_material(conn, material_id)
+}
+
+// A material keeps at least one subject; delete it instead
+pub fn unlink_material(conn: &Connection, subject_id: i64, material_id: i64) -> Result<RecomputeSummary, String> {
+    let material = load_material(conn, material_id)?;
+    let subject = load_subject(conn, subject_id)?;
+    if !material.subjects.contains(&subject.name) {
+        return Err(format!("'{}' is not part of '{}'", material.name_en, subject.name));
+    }
+    if material.subjects.len() == 1 {
+        return Err(format!(
+            "'{}' is only in '{}'; link it to another subject first or delete it",
+            material.name_en, subject.name
+        ));
+    }
+
+    conn.execute(
+        "DELETE FROM SubjectMaterials WHERE subject_id = ?1 AND material_id = ?2",
+        params![subject_id, material_id],
+    ).map_err(|e| e.to_string())?;
+
+    recompute_material(conn, material_id)
+}
+
+// Fold `merge_id` into `keep_id`: problems, drills, vocabulary, glossary
+// terms and subject links move over, then `merge_id` is deleted. Problems
+// titled the same in both are merged.
+pub fn merge_materials(conn: &Connection, keep_id: i64, merge_id: i64) -> Result<MaterialMerge, String> {
+    if keep_id == merge_id {
+        return Err("Cannot merge a material into itself".to_string());
+    }
+    let kept = load_material(conn, keep_id)?;
+    let merged = load_material(conn, merge_id)?;
+
+    // Problems with the same title in both are the same problem
+    let clashes: Vec<(i64, i64)> = conn.prepare(
+        "SELECT k.id, p.id FROM Problems p
+         JOIN Problems k ON k.material_id = ?1 AND k.title = p.title
+         WHERE p.material_id = ?2"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![keep_id, merge_id], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+    for (keep_problem, merge_problem) in &clashes {
+        merge_problems(conn, *keep_problem, *merge_problem)?;
+    }
+
+    let problems_moved = conn.execute(
+        "UPDATE Problems SET material_id = ?1, updated_at = datetime('now') WHERE material_id = ?2",
+        params![keep_id, merge_id],
+    ).map_err(|e| e.to_string())?;
+    let drills_moved = conn.execute(
+        "UPDATE RussianDrillAttempts SET material_id = ?1 WHERE material_id = ?2",
+        params![keep_id, merge_id],
+    ).map_err(|e| e.to_string())?;
+    let vocabulary_moved = conn.execute(
+        "UPDATE RussianVocabulary SET material_id = ?1 WHERE material_id = ?2",
+        params![keep_id, merge_id],
+    ).map_err(|e| e.to_string())?;
+
+    // Terms in both glossaries keep the kept one, filled in from the other
+    conn.execute(
+        "UPDATE MaterialTerms AS k
+         SET translation_en = COALESCE(k.translation_en, m.translation_en),
+             notes = COALESCE(k.notes, m.notes)
+         FROM MaterialTerms AS m
+         WHERE k.material_id = ?1 AND m.material_id = ?2 AND m.term_ru = k.term_ru",
+        params![keep_id, merge_id],
+    ).map_err(|e| e.to_string())?;
+    let terms_moved = conn.execute(
+        "UPDATE OR IGNORE MaterialTerms SET material_id = ?1 WHERE material_id = ?2",
+        params![keep_id, merge_id],
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO SubjectMaterials (subject_id, material_id)
+         SELECT subject_id, ?1 FROM SubjectMaterials WHERE material_id = ?2",
+        params![keep_id, merge_id],
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE Materials SET name_ru = ?1, commentary = ?2, updated_at = datetime('now') WHERE id = ?3",
+        params![
+            kept.name_ru.or(merged.name_ru),
+            kept.commentary.or(merged.commentary),
+            keep_id,
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM Materials WHERE id = ?1", params![merge_id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(MaterialMerge {
+        solved_status: recompute_material(conn, keep_id)?,
+        material: load_material(conn, keep_id)?,
+        problems_moved,
+        problems_merged: clashes.len(),
+        drills_moved,
+        vocabulary_moved,
+        terms_moved,
+    })
+}
+
+fn count(conn: &Connection, sql: &str, material_id: i64) -> Result<usize, String> {
+    conn.query_row(sql, params![material_id], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+// Add what deleting a material takes with it
+fn add_material_effects(conn: &Connection, preview: &mut DeletionPreview, material_id: i64) -> Result<(), String> {
+    let material = load_material(conn, material_id)?;
+    preview.materials.push(material.name_en);
+    preview.problems += material.problem_count;
+    preview.drills += material.drill_count;
+    preview.vocabulary_unlinked += material.vocabulary_count;
+    preview.batches += count(conn,
+        "SELECT COUNT(*) FROM Batches b JOIN Problems p ON b.problem_id = p.id WHERE p.material_id = ?1",
+        material_id)?;
+    preview.attempts += count(conn,
+        "SELECT COUNT(*) FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         JOIN Problems p ON b.problem_id = p.id
+         WHERE p.material_id = ?1",
+        material_id)?;
+    preview.terms += count(conn, "SELECT COUNT(*) FROM MaterialTerms WHERE material_id = ?1", material_id)?;
+
+    let images: Vec<String> = conn.prepare(
+        "SELECT image_filename FROM Problems WHERE material_id = ?1 AND image_filename IS NOT NULL"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+    preview.image_files.extend(images);
+
+    Ok(())
+}
+
+pub fn material_deletion_preview(conn: &Connection, material_id: i64) -> Result<DeletionPreview, String> {
+    let mut preview = DeletionPreview::default();
+    add_material_effects(conn, &mut preview, material_id)?;
+    Ok(preview)
+}
+
+pub fn delete_material(conn: &Connection, material_id: i64) -> Result<DeletionPreview, String> {
+    let preview = material_deletion_preview(conn, material_id)?;
+    conn.execute("DELETE FROM Materials WHERE id = ?1", params![material_id])
+        .map_err(|e| e.to_string())?;
+    Ok(preview)
+}
+
+// Materials of a subject split into (only in this subject, shared)
+fn subject_materials(conn: &Connection, subject_id: i64) -> Result<(Vec<i64>, Vec<i64>), String> {
+    let materials: Vec<(i64, usize)> = conn.prepare(
+        "SELECT sm.material_id,
+                (SELECT COUNT(*) FROM SubjectMaterials other WHERE other.material_id = sm.material_id)
+         FROM SubjectMaterials sm
+         JOIN Materials m ON sm.material_id = m.id
+         WHERE sm.subject_id = ?1
+         ORDER BY m.name_en"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![subject_id], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let (own, shared): (Vec<_>, Vec<_>) = materials.into_iter().partition(|(_, subjects)| *subjects == 1);
+    Ok((
+        own.into_iter().map(|(id, _)| id).collect(),
+        shared.into_iter().map(|(id, _)| id).collect(),
+    ))
+}
+
+pub fn subject_deletion_preview(conn: &Connection, subject_id: i64) -> Result<DeletionPreview, String> {
+    load_subject(conn, subject_id)?;
+    let (own, shared) = subject_materials(conn, subject_id)?;
+
+    let mut preview = DeletionPreview::default();
+    for material_id in own {
+        add_material_effects(conn, &mut preview, material_id)?;
+    }
+    for material_id in shared {
+        preview.kept_materials.push(load_material(conn, material_id)?.name_en);
+    }
+    preview.mastery_policy = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM MasteryPolicies WHERE subject_id = ?1)",
+        params![subject_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    Ok(preview)
+}
+
+// Deletes the subject and the materials no other subject uses. Shared
+// materials stay, and their problems are re-evaluated without its policy.
+pub fn delete_subject(conn: &Connection, subject_id: i64) -> Result<DeletionPreview, String> {
+    let preview = subject_deletion_preview(conn, subject_id)?;
+    let (own, shared) = subject_materials(conn, subject_id)?;
+
+    for material_id in own {
+        conn.execute("DELETE FROM Materials WHERE id = ?1", params![material_id])
+            .map_err(|e| e.to_string())?;
+    }
+    conn.execute("DELETE FROM Subjects WHERE id = ?1", params![subject_id])
+        .map_err(|e| e.to_string())?;
+    for material_id in shared {
+        recompute_material(conn, material_id)?;
+    }
+
+    Ok(preview)
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProblemMove {
+    pub problem_id: i64,
+    pub generated_id: String,
+    // Set when the ID was regenerated for the new subject
+    pub old_generated_id: Option<String>,
+    pub material_name: String,
+    pub is_solved: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProblemMerge {
+    pub problem_id: i64,
+    pub generated_id: String,
+    // ID of the problem merged away
+    pub merged_generated_id: String,
+    pub batches: usize,
+    pub attempts: usize,
+    pub is_solved: bool,
+    // Images of the merged problem the kept one doesn't show
+    pub unused_images: Vec<String>,
+}
+
+struct ProblemRow {
+    generated_id: String,
+    title: String,
+    description: Option<String>,
+    image_filename: Option<String>,
+}
+
+fn load_problem(conn: &Connection, problem_id: i64) -> Result<ProblemRow, String> {
+    conn.query_row(
+        "SELECT generated_id, title, description, image_filename FROM Problems WHERE id = ?1",
+        params![problem_id],
+        |row| Ok(ProblemRow {
+            generated_id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            image_filename: row.get(3)?,
+        })
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Problem {} not found", problem_id))
+}
+
+// Move a problem to another material. Its ID keeps its prefix if one of the
+// material's subjects uses it; otherwise `regenerate_id` must allow a new
+// ID for the material's first subject.
+pub fn move_problem(
+    conn: &Connection,
+    problem_id: i64,
+    material_name: &str,
+    regenerate_id: bool,
+) -> Result<ProblemMove, String> {
+    let problem = load_problem(conn, problem_id)?;
+    let material_id: i64 = conn.query_row(
+        "SELECT id FROM Materials WHERE name_en = ?1",
+        params![material_name.trim()],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Material '{}' not found", material_name.trim()))?;
+    let material = load_material(conn, material_id)?;
+
+    let clash: Option<i64> = conn.query_row(
+        "SELECT id FROM Problems WHERE material_id = ?1 AND title = ?2 AND id != ?3",
+        params![material_id, problem.title, problem_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    if let Some(other) = clash {
+        return Err(format!(
+            "'{}' already has a problem titled '{}' (#{}); merge the problems instead",
+            material.name_en, problem.title, other
+        ));
+    }
+
+    let prefix = problem.generated_id.rsplit_once('_').map(|(head, _)| head).unwrap_or("");
+    let prefix_fits = material.subjects.iter().any(|subject| problem_id_prefix(subject) == prefix);
+    let mut old_generated_id = None;
+    let mut generated_id = problem.generated_id.clone();
+    if !prefix_fits {
+        let Some(subject) = material.subjects.first() else {
+            return Err(format!("'{}' is not part of any subject", material.name_en));
+        };
+        if !regenerate_id {
+            return Err(format!(
+                "No subject of '{}' uses the ID prefix {}; regenerate the ID to move {}",
+                material.name_en, prefix, problem.generated_id
+            ));
+        }
+        generated_id = generate_problem_id(conn, subject)?;
+        old_generated_id = Some(problem.generated_id);
+    }
+
+    conn.execute(
+        "UPDATE Problems SET material_id = ?1, generated_id = ?2, updated_at = datetime('now') WHERE id = ?3",
+        params![material_id, generated_id, problem_id],
+    ).map_err(|e| e.to_string())?;
+
+    // The new material may fall under another subject's mastery policy
+    let (_, is_solved) = mastery::update_solved_status(conn, problem_id)?;
+
+    Ok(ProblemMove {
+        problem_id,
+        generated_id,
+        old_generated_id,
+        material_name: material.name_en,
+        is_solved,
+    })
+}
+
+// Close every open batch of a problem except the latest, the way logging
+// closes a batch when a new one starts
+fn close_stale_batches(conn: &Connection, problem_id: i64) -> Result<(), String> {
+    let open: Vec<(i64, String, Option<String>)> = conn.prepare(
+        "SELECT b.id, b.started_at, (SELECT MAX(a.timestamp) FROM Attempts a WHERE a.batch_id = b.id)
+         FROM Batches b
+         WHERE b.problem_id = ?1 AND b.ended_at IS NULL
+         ORDER BY b.started_at DESC, b.id DESC"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![problem_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let settings = segmentation::load_settings(conn)?;
+    for (batch_id, started_at, last_attempt) in open.into_iter().skip(1) {
+        let started = parse_sqlite_time(&started_at)?;
+        let last = last_attempt.as_deref().map(parse_sqlite_time).transpose()?.unwrap_or(started);
+        conn.execute(
+            "UPDATE Batches SET ended_at = ?1 WHERE id = ?2",
+            params![format_sqlite_time(&segmentation::close_time(&settings, &started, &last)), batch_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// Fold `merge_id` into `keep_id`: batches (with their attempts, resources
+// and error tags) join its history in chronological order, the description
+// and image fill in what it lacks, and solved status and schedule are
+// recomputed from the combined history
+pub fn merge_problems(conn: &Connection, keep_id: i64, merge_id: i64) -> Result<ProblemMerge, String> {
+    if keep_id == merge_id {
+        return Err("Cannot merge a problem into itself".to_string());
+    }
+    let kept = load_problem(conn, keep_id)?;
+    let merged = load_problem(conn, merge_id)?;
+
+    // Park the moved batches above the kept ones until renumbering
+    let offset: i32 = conn.query_row(
+        "SELECT COALESCE(MAX(batch_number), 0) FROM Batches WHERE problem_id = ?1",
+        params![keep_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE Batches SET problem_id = ?1, batch_number = batch_number + ?2 WHERE problem_id = ?3",
+        params![keep_id, offset, merge_id],
+    ).map_err(|e| e.to_string())?;
+
+    close_stale_batches(conn, keep_id)?;
+    segmentation::renumber_problem(conn, keep_id)?;
+
+    let description = match (kept.description, merged.description) {
+        (Some(k), Some(m)) if !m.trim().is_empty() && k.trim() != m.trim() => Some(format!("{}\n\n{}", k, m)),
+        (k, m) => k.filter(|k| !k.trim().is_empty()).or(m),
+    };
+    let mut unused_images = Vec::new();
+    let image_filename = match (kept.image_filename, merged.image_filename) {
+        (Some(k), Some(m)) => {
+            if k != m {
+                unused_images.push(m);
+            }
+            Some(k)
+        }
+        (k, m) => k.or(m),
+    };
+
+    conn.execute("DELETE FROM Problems WHERE id = ?1", params![merge_id])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE Problems SET description = ?1, image_filename = ?2,
+         content_type = CASE
+            WHEN ?2 IS NULL THEN 'text'
+            WHEN ?1 IS NOT NULL AND ?1 != '' THEN 'both'
+            ELSE 'image'
+         END,
+         updated_at = datetime('now')
+         WHERE id = ?3",
+        params![description, image_filename, keep_id],
+    ).map_err(|e| e.to_string())?;
+
+    let (_, is_solved) = mastery::update_solved_status(conn, keep_id)?;
+    scheduling::reschedule_problem(conn, keep_id)?;
+
+    let (batches, attempts): (usize, usize) = conn.query_row(
+        "SELECT COUNT(DISTINCT b.id), COUNT(a.id) FROM Batches b
+         LEFT JOIN Attempts a ON a.batch_id = b.id
+         WHERE b.problem_id = ?1",
+        params![keep_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| e.to_string())?;
+
+    Ok(ProblemMerge {
+        problem_id: keep_id,
+        generated_id: kept.generated_id,
+        merged_generated_id: merged.generated_id,
+        batches,
+        attempts,
+        is_solved,
+        unused_images,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    // Algorithms covers Graphs and Graph theory, which share a "BFS" problem;
+    // Maths covers Calculus
+    fn catalog() -> Connection {
+        let conn = db::open_in_memory();
+        conn.execute_batch(
+            "INSERT INTO Subjects (id, name) VALUES (1, 'Algorithms'), (2, 'Maths');
+             INSERT INTO Materials (id, name_en) VALUES (1, 'Graphs'), (2, 'Graph theory'), (3, 'Calculus');
+             INSERT INTO SubjectMaterials (subject_id, material_id) VALUES (1, 1), (1, 2), (2, 3);
+             INSERT INTO Problems (id, generated_id, material_id, title, description) VALUES
+                 (1, 'ALGO_001', 1, 'BFS', 'Shortest paths'),
+                 (2, 'ALGO_002', 2, 'BFS', 'Level order'),
+                 (3, 'ALGO_003', 2, 'DFS', NULL);
+             INSERT INTO Batches (id, problem_id, batch_number, started_at, ended_at) VALUES
+                 (1, 1, 1, '2024-01-10 09:00:00', '2024-01-10 12:00:00'),
+                 (2, 2, 1, '2024-01-05 09:00:00', '2024-01-05 12:00:00');
+             INSERT INTO Attempts (batch_id, attempt_number, successful, timestamp) VALUES
+                 (1, 1, 1, '2024-01-10 10:00:00'),
+                 (2, 1, 0, '2024-01-05 10:00:00');",
+        ).unwrap();
+        conn
+    }
+
+    #[test]
+    fn merging_materials_moves_content_and_merges_same_titled_problems() {
+        let conn = catalog();
+        conn.execute_batch(
+            "INSERT INTO MaterialTerms (material_id, term_ru, translation_en) VALUES
+                 (1, 'граф', NULL), (2, 'граф', 'graph'), (2, 'вершина', 'vertex');
+             INSERT INTO SubjectMaterials (subject_id, material_id) VALUES (2, 2);",
+        ).unwrap();
+
+        let merge = merge_materials(&conn, 1, 2).unwrap();
+        assert_eq!((merge.problems_moved, merge.problems_merged, merge.terms_moved), (1, 1, 1));
+        assert_eq!(merge.material.subjects, vec!["Algorithms", "Maths"]);
+        assert_eq!(merge.material.problem_count, 2);
+        assert!(load_material(&conn, 2).is_err());
+
+        let graph: Option<String> = conn.query_row(
+            "SELECT translation_en FROM MaterialTerms WHERE material_id = 1 AND term_ru = 'граф'",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(graph.as_deref(), Some("graph"));
+
+        let batches: i64 = conn.query_row("SELECT COUNT(*) FROM Batches WHERE problem_id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(batches, 2);
+    }
+
+    #[test]
+    fn a_material_cannot_be_merged_into_itself() {
+        let conn = catalog();
+        assert!(merge_materials(&conn, 1, 1).is_err());
+        assert_eq!(load_material(&conn, 1).unwrap().problem_count, 1);
+    }
+
+    #[test]
+    fn deletion_preview_counts_what_goes_with_a_material() {
+        let conn = catalog();
+        let preview = material_deletion_preview(&conn, 2).unwrap();
+        assert_eq!(preview.materials, vec!["Graph theory"]);
+        assert_eq!((preview.problems, preview.batches, preview.attempts), (2, 1, 1));
+    }
+
+    #[test]
+    fn moving_keeps_an_id_whose_prefix_still_fits() {
+        let conn = catalog();
+        let moved = move_problem(&conn, 3, "Graphs", false).unwrap();
+        assert_eq!((moved.generated_id.as_str(), moved.old_generated_id), ("ALGO_003", None));
+
+        // Graphs already has a BFS
+        assert!(move_problem(&conn, 2, "Graphs", true).is_err());
+    }
+
+    #[test]
+    fn moving_to_another_subject_needs_a_new_id() {
+        let conn = catalog();
+        assert!(move_problem(&conn, 3, "Calculus", false).is_err());
+
+        let moved = move_problem(&conn, 3, "Calculus", true).unwrap();
+        assert_eq!(moved.generated_id, "MATH_001");
+        assert_eq!(moved.old_generated_id.as_deref(), Some("ALGO_003"));
+        assert_eq!(load_material(&conn, 3).unwrap().problem_count, 1);
+    }
+
+    #[test]
+    fn merged_problems_share_one_chronological_history() {
+        let conn = catalog();
+        conn.execute("UPDATE Problems SET image_filename = 'bfs.png' WHERE id = 2", []).unwrap();
+
+        let merge = merge_problems(&conn, 1, 2).unwrap();
+        assert_eq!((merge.batches, merge.attempts), (2, 2));
+        assert_eq!(merge.merged_generated_id, "ALGO_002");
+        assert!(merge.unused_images.is_empty());
+
+        // The merged problem's older batch comes first
+        let first: i64 = conn.query_row(
+            "SELECT id FROM Batches WHERE problem_id = 1 AND batch_number = 1",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(first, 2);
+
+        let problem = load_problem(&conn, 1).unwrap();
+        assert_eq!(problem.description.as_deref(), Some("Shortest paths\n\nLevel order"));
+        assert_eq!(problem.image_filename.as_deref(), Some("bfs.png"));
+        assert!(load_problem(&conn, 2).is_err());
+        assert!(merge_problems(&conn, 1, 1).is_err());
+    }
+}
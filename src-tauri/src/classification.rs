@@ -0,0 +1,733 @@
+use regex::{Regex, RegexBuilder};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::utils::time::parse_sqlite_time;
+
+// Error taxonomy for attempts. Categories form a user-editable hierarchy;
+// each category has keyword or regex rules that are matched against
+// Attempts.errors to tag attempts automatically. Tags set by hand override
+// the rules for that attempt.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    // Whole words or phrases, case-insensitive
+    Keyword,
+    // Case-insensitive regular expression
+    Regex,
+}
+
+impl RuleKind {
+    pub const ALL: [RuleKind; 2] = [RuleKind::Keyword, RuleKind::Regex];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleKind::Keyword => "keyword",
+            RuleKind::Regex => "regex",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorRule {
+    pub id: i64,
+    pub kind: RuleKind,
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorCategory {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub description: Option<String>,
+    pub rules: Vec<ErrorRule>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReclassifySummary {
+    pub evaluated: usize,
+    pub tagged: usize,
+    // Attempts with manual tags, left alone
+    pub manual: usize,
+}
+
+struct DefaultCategory {
+    name: &'static str,
+    description: &'static str,
+    rules: &'static [(RuleKind, &'static str)],
+}
+
+// Seeded on a new database; the rules cover what the review screen used to
+// match in the browser
+const DEFAULT_CATEGORIES: &[DefaultCategory] = &[
+    DefaultCategory {
+        name: "Syntax",
+        description: "Syntax, type and compile errors",
+        rules: &[
+            (RuleKind::Keyword, "syntax"),
+            (RuleKind::Keyword, "type"),
+            (RuleKind::Keyword, "typo"),
+            (RuleKind::Regex, r"\bcompil(e|er|ation)\b"),
+        ],
+    },
+    DefaultCategory {
+        name: "Logic",
+        description: "Wrong approach, algorithm or reasoning",
+        rules: &[
+            (RuleKind::Keyword, "logic"),
+            (RuleKind::Regex, r"\balgorithm(s|ic)?\b"),
+            (RuleKind::Keyword, "wrong approach"),
+            (RuleKind::Keyword, "misunderstood"),
+        ],
+    },
+    DefaultCategory {
+        name: "Boundary",
+        description: "Off-by-one errors and edge cases",
+        rules: &[
+            (RuleKind::Regex, r"\boff[- ]by[- ]one\b"),
+            (RuleKind::Keyword, "boundary"),
+            (RuleKind::Regex, r"\bedge[- ]cases?\b"),
+            (RuleKind::Keyword, "out of bounds"),
+        ],
+    },
+    DefaultCategory {
+        name: "Performance",
+        description: "Too slow or over the time limit",
+        rules: &[
+            (RuleKind::Regex, r"\btime(d)?[- ]?out\b"),
+            (RuleKind::Keyword, "time limit"),
+            (RuleKind::Keyword, "too slow"),
+            (RuleKind::Keyword, "TLE"),
+        ],
+    },
+    DefaultCategory {
+        name: "Recall",
+        description: "Forgot a fact, formula or step",
+        rules: &[
+            (RuleKind::Regex, r"\bforg[eo]t"),
+            (RuleKind::Keyword, "missed"),
+            (RuleKind::Regex, r"\b(didn't|did not|couldn't|could not) (remember|recall)\b"),
+        ],
+    },
+];
+
+// Add the default categories and rules that are missing, by name
+pub fn seed_defaults(conn: &Connection) -> Result<(), String> {
+    for category in DEFAULT_CATEGORIES {
+        let category_id: i64 = conn.query_row(
+            "INSERT INTO ErrorCategories (name, description) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET name = name
+             RETURNING id",
+            params![category.name, category.description],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+
+        for (kind, pattern) in category.rules {
+            conn.execute(
+                "INSERT OR IGNORE INTO ErrorCategoryRules (category_id, kind, pattern) VALUES (?1, ?2, ?3)",
+                params![category_id, kind.as_str(), pattern],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn compile_rule(kind: RuleKind, pattern: &str) -> Result<Regex, String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("Rule pattern cannot be empty".to_string());
+    }
+
+    let source = match kind {
+        RuleKind::Regex => pattern.to_string(),
+        RuleKind::Keyword => {
+            // Word boundaries only where the keyword starts or ends with a
+            // word character, so "c++" still matches
+            let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            format!(
+                "{}{}{}",
+                if is_word(pattern.chars().next()) { r"\b" } else { "" },
+                regex::escape(pattern),
+                if is_word(pattern.chars().last()) { r"\b" } else { "" },
+            )
+        }
+    };
+
+    RegexBuilder::new(&source)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))
+}
+
+// Every rule, compiled once
+pub struct RuleSet {
+    rules: Vec<(i64, Regex)>,
+}
+
+impl RuleSet {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let rows: Vec<(i64, String, String)> = conn.prepare(
+            "SELECT category_id, kind, pattern FROM ErrorCategoryRules ORDER BY id"
+        ).map_err(|e| e.to_string())?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+        let mut rules = Vec::with_capacity(rows.len());
+        for (category_id, kind, pattern) in rows {
+            let kind = RuleKind::parse(&kind).unwrap_or(RuleKind::Keyword);
+            rules.push((category_id, compile_rule(kind, &pattern)?));
+        }
+        Ok(RuleSet { rules })
+    }
+
+    // Categories whose rules match `text`, ascending
+    pub fn classify(&self, text: &str) -> Vec<i64> {
+        let matched: BTreeSet<i64> = self.rules.iter()
+            .filter(|(_, regex)| regex.is_match(text))
+            .map(|(category_id, _)| *category_id)
+            .collect();
+        matched.into_iter().collect()
+    }
+}
+
+fn has_manual_tags(conn: &Connection, attempt_id: i64) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM AttemptErrorOverrides WHERE attempt_id = ?1)",
+        params![attempt_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())
+}
+
+// Re-run the rules for one attempt, replacing its rule tags. Attempts tagged
+// by hand keep their tags. Returns the attempt's categories.
+pub fn tag_attempt(conn: &Connection, rules: &RuleSet, attempt_id: i64) -> Result<Vec<i64>, String> {
+    if !has_manual_tags(conn, attempt_id)? {
+        let errors: Option<String> = conn.query_row(
+            "SELECT errors FROM Attempts WHERE id = ?1",
+            params![attempt_id],
+            |row| row.get(0)
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Attempt {} not found", attempt_id))?;
+
+        conn.execute(
+            "DELETE FROM AttemptErrorCategories WHERE attempt_id = ?1 AND source = 'rule'",
+            params![attempt_id],
+        ).map_err(|e| e.to_string())?;
+
+        for category_id in rules.classify(errors.as_deref().unwrap_or("")) {
+            conn.execute(
+                "INSERT OR IGNORE INTO AttemptErrorCategories (attempt_id, category_id, source)
+                 VALUES (?1, ?2, 'rule')",
+                params![attempt_id, category_id],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+
+    attempt_categories(conn, attempt_id)
+}
+
+// Used after an attempt is logged or edited
+pub fn classify_attempt(conn: &Connection, attempt_id: i64) -> Result<Vec<i64>, String> {
+    let rules = RuleSet::load(conn)?;
+    tag_attempt(conn, &rules, attempt_id)
+}
+
+pub fn reclassify_all(conn: &Connection) -> Result<ReclassifySummary, String> {
+    let rules = RuleSet::load(conn)?;
+    let attempt_ids: Vec<i64> = conn.prepare("SELECT id FROM Attempts ORDER BY id")
+        .map_err(|e| e.to_string())?
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut summary = ReclassifySummary::default();
+    for attempt_id in attempt_ids {
+        summary.evaluated += 1;
+        if has_manual_tags(conn, attempt_id)? {
+            summary.manual += 1;
+            continue;
+        }
+        if !tag_attempt(conn, &rules, attempt_id)?.is_empty() {
+            summary.tagged += 1;
+        }
+    }
+    Ok(summary)
+}
+
+pub fn attempt_categories(conn: &Connection, attempt_id: i64) -> Result<Vec<i64>, String> {
+    conn.prepare(
+        "SELECT category_id FROM AttemptErrorCategories WHERE attempt_id = ?1 ORDER BY category_id"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![attempt_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())
+}
+
+pub fn attempt_category_names(conn: &Connection, attempt_id: i64) -> Result<Vec<String>, String> {
+    conn.prepare(
+        "SELECT c.name FROM AttemptErrorCategories ac
+         JOIN ErrorCategories c ON ac.category_id = c.id
+         WHERE ac.attempt_id = ?1
+         ORDER BY c.name"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![attempt_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())
+}
+
+pub fn category_names(conn: &Connection, category_ids: &[i64]) -> Result<Vec<String>, String> {
+    let categories = list_categories(conn)?;
+    Ok(category_ids.iter()
+        .filter_map(|id| categories.iter().find(|c| c.id == *id).map(|c| c.name.clone()))
+        .collect())
+}
+
+// Tag an attempt by hand, overriding the rules; an empty list means no
+// categories at all. None hands it back to the rules.
+pub fn set_manual_tags(conn: &Connection, attempt_id: i64, category_ids: Option<&[i64]>) -> Result<Vec<i64>, String> {
+    conn.execute("DELETE FROM AttemptErrorCategories WHERE attempt_id = ?1", params![attempt_id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM AttemptErrorOverrides WHERE attempt_id = ?1", params![attempt_id])
+        .map_err(|e| e.to_string())?;
+
+    match category_ids {
+        Some(category_ids) => {
+            conn.execute("INSERT INTO AttemptErrorOverrides (attempt_id) VALUES (?1)", params![attempt_id])
+                .map_err(|e| format!("Cannot tag attempt {}: {}", attempt_id, e))?;
+            for category_id in category_ids {
+                conn.execute(
+                    "INSERT OR IGNORE INTO AttemptErrorCategories (attempt_id, category_id, source)
+                     VALUES (?1, ?2, 'manual')",
+                    params![attempt_id, category_id],
+                ).map_err(|e| format!("Cannot tag attempt {}: {}", attempt_id, e))?;
+            }
+            attempt_categories(conn, attempt_id)
+        }
+        None => classify_attempt(conn, attempt_id),
+    }
+}
+
+pub fn list_categories(conn: &Connection) -> Result<Vec<ErrorCategory>, String> {
+    let mut categories: Vec<ErrorCategory> = conn.prepare(
+        "SELECT id, name, parent_id, description FROM ErrorCategories ORDER BY name"
+    ).map_err(|e| e.to_string())?
+    .query_map([], |row| Ok(ErrorCategory {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        parent_id: row.get(2)?,
+        description: row.get(3)?,
+        rules: Vec::new(),
+    }))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let rules: Vec<(i64, ErrorRule)> = conn.prepare(
+        "SELECT category_id, id, kind, pattern FROM ErrorCategoryRules ORDER BY id"
+    ).map_err(|e| e.to_string())?
+    .query_map([], |row| {
+        let kind: String = row.get(2)?;
+        Ok((row.get(0)?, ErrorRule {
+            id: row.get(1)?,
+            kind: RuleKind::parse(&kind).unwrap_or(RuleKind::Keyword),
+            pattern: row.get(3)?,
+        }))
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    for (category_id, rule) in rules {
+        if let Some(category) = categories.iter_mut().find(|c| c.id == category_id) {
+            category.rules.push(rule);
+        }
+    }
+    Ok(categories)
+}
+
+pub fn load_category(conn: &Connection, category_id: i64) -> Result<ErrorCategory, String> {
+    list_categories(conn)?
+        .into_iter()
+        .find(|c| c.id == category_id)
+        .ok_or_else(|| format!("Error category {} not found", category_id))
+}
+
+// A category can't be moved under itself or one of its descendants
+fn check_parent(conn: &Connection, category_id: Option<i64>, parent_id: Option<i64>) -> Result<(), String> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    let parents: HashMap<i64, Option<i64>> = list_categories(conn)?
+        .into_iter()
+        .map(|c| (c.id, c.parent_id))
+        .collect();
+
+    if !parents.contains_key(&parent_id) {
+        return Err(format!("Error category {} not found", parent_id));
+    }
+
+    let mut current = Some(parent_id);
+    let mut seen = HashSet::new();
+    while let Some(id) = current {
+        if Some(id) == category_id || !seen.insert(id) {
+            return Err("A category cannot be nested under itself".to_string());
+        }
+        current = parents.get(&id).copied().flatten();
+    }
+    Ok(())
+}
+
+pub fn save_category(
+    conn: &Connection,
+    category_id: Option<i64>,
+    name: &str,
+    parent_id: Option<i64>,
+    description: Option<&str>,
+) -> Result<i64, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Category name cannot be empty".to_string());
+    }
+    check_parent(conn, category_id, parent_id)?;
+
+    let duplicate = |e: rusqlite::Error| match e {
+        rusqlite::Error::SqliteFailure(ref err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("Error category '{}' already exists", name)
+        }
+        e => e.to_string(),
+    };
+
+    match category_id {
+        Some(id) => {
+            let changed = conn.execute(
+                "UPDATE ErrorCategories SET name = ?1, parent_id = ?2, description = ?3 WHERE id = ?4",
+                params![name, parent_id, description, id],
+            ).map_err(duplicate)?;
+            if changed == 0 {
+                return Err(format!("Error category {} not found", id));
+            }
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO ErrorCategories (name, parent_id, description) VALUES (?1, ?2, ?3)",
+                params![name, parent_id, description],
+            ).map_err(duplicate)?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+// Subcategories move up to the deleted category's parent; its tags and rules
+// go with it
+pub fn delete_category(conn: &Connection, category_id: i64) -> Result<(), String> {
+    let parent_id = load_category(conn, category_id)?.parent_id;
+
+    conn.execute(
+        "UPDATE ErrorCategories SET parent_id = ?1 WHERE parent_id = ?2",
+        params![parent_id, category_id],
+    ).map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM ErrorCategories WHERE id = ?1", params![category_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn add_rule(conn: &Connection, category_id: i64, kind: RuleKind, pattern: &str) -> Result<i64, String> {
+    load_category(conn, category_id)?;
+    compile_rule(kind, pattern)?;
+
+    conn.query_row(
+        "INSERT INTO ErrorCategoryRules (category_id, kind, pattern) VALUES (?1, ?2, ?3)
+         ON CONFLICT(category_id, kind, pattern) DO UPDATE SET pattern = pattern
+         RETURNING id",
+        params![category_id, kind.as_str(), pattern.trim()],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())
+}
+
+pub fn delete_rule(conn: &Connection, rule_id: i64) -> Result<(), String> {
+    let deleted = conn.execute("DELETE FROM ErrorCategoryRules WHERE id = ?1", params![rule_id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Rule {} not found", rule_id));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendPeriod {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl TrendPeriod {
+    // Weeks are ISO weeks, labelled with their ISO year (2025-W01 can start
+    // in December 2024). SQLite's strftime only has these from 3.46, so the
+    // labels are formatted here.
+    fn label(&self, timestamp: &str) -> Result<String, String> {
+        let format = match self {
+            TrendPeriod::Day => "%Y-%m-%d",
+            TrendPeriod::Week => "%G-W%V",
+            TrendPeriod::Month => "%Y-%m",
+        };
+        Ok(parse_sqlite_time(timestamp)?.format(format).to_string())
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorBreakdownFilter {
+    pub subject_name: Option<String>,
+    pub material_name: Option<String>,
+    // Dates (YYYY-MM-DD), inclusive
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub failed_only: bool,
+    pub period: TrendPeriod,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub period: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryBreakdown {
+    pub category_id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    // Attempts tagged with this category itself
+    pub count: usize,
+    // Attempts tagged with it or any subcategory
+    pub total: usize,
+    // `total` as a share of the categorized attempts
+    pub share: f64,
+    pub last_seen: Option<String>,
+    // `total` per period, over every period with a matching attempt
+    pub trend: Vec<TrendPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBreakdown {
+    pub attempts: usize,
+    pub categorized: usize,
+    // Attempts with errors written down that no category matched
+    pub uncategorized: usize,
+    pub categories: Vec<CategoryBreakdown>,
+}
+
+struct TaggedAttempt {
+    id: i64,
+    timestamp: String,
+    period: String,
+    has_errors: bool,
+    categories: Vec<i64>,
+}
+
+pub fn breakdown(conn: &Connection, filter: &ErrorBreakdownFilter) -> Result<ErrorBreakdown, String> {
+    let mut attempts: Vec<TaggedAttempt> = conn.prepare(
+        "SELECT a.id, a.timestamp, TRIM(COALESCE(a.errors, '')) <> ''
+         FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         JOIN Problems p ON b.problem_id = p.id
+         JOIN Materials m ON p.material_id = m.id
+         WHERE (?1 IS NULL OR EXISTS(
+                SELECT 1 FROM SubjectMaterials sm JOIN Subjects s ON sm.subject_id = s.id
+                WHERE sm.material_id = m.id AND s.name = ?1))
+           AND (?2 IS NULL OR m.name_en = ?2)
+           AND (?3 IS NULL OR date(a.timestamp) >= date(?3))
+           AND (?4 IS NULL OR date(a.timestamp) <= date(?4))
+           AND (?5 = 0 OR a.successful = 0)
+         ORDER BY a.timestamp ASC, a.id ASC"
+    ).map_err(|e| e.to_string())?
+    .query_map(
+        params![
+            filter.subject_name,
+            filter.material_name,
+            filter.since,
+            filter.until,
+            filter.failed_only,
+        ],
+        |row| Ok(TaggedAttempt {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            period: String::new(),
+            has_errors: row.get(2)?,
+            categories: Vec::new(),
+        })
+    )
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    for attempt in &mut attempts {
+        attempt.period = filter.period.label(&attempt.timestamp)?;
+        attempt.categories = attempt_categories(conn, attempt.id)?;
+    }
+
+    let categories = list_categories(conn)?;
+    let parents: HashMap<i64, Option<i64>> = categories.iter().map(|c| (c.id, c.parent_id)).collect();
+    // A category and all its ancestors
+    let lineage = |id: i64| {
+        let mut ids = vec![id];
+        let mut current = parents.get(&id).copied().flatten();
+        while let Some(parent) = current {
+            if ids.contains(&parent) {
+                break;
+            }
+            ids.push(parent);
+            current = parents.get(&parent).copied().flatten();
+        }
+        ids
+    };
+
+    let mut periods: Vec<String> = Vec::new();
+    for attempt in &attempts {
+        if !periods.contains(&attempt.period) {
+            periods.push(attempt.period.clone());
+        }
+    }
+
+    let categorized = attempts.iter().filter(|a| !a.categories.is_empty()).count();
+    let uncategorized = attempts.iter().filter(|a| a.categories.is_empty() && a.has_errors).count();
+
+    let mut rows: Vec<CategoryBreakdown> = categories.iter().map(|c| CategoryBreakdown {
+        category_id: c.id,
+        name: c.name.clone(),
+        parent_id: c.parent_id,
+        count: 0,
+        total: 0,
+        share: 0.0,
+        last_seen: None,
+        trend: periods.iter().map(|p| TrendPoint { period: p.clone(), count: 0 }).collect(),
+    }).collect();
+
+    for attempt in &attempts {
+        let mut rolled_up = BTreeSet::new();
+        for category_id in &attempt.categories {
+            if let Some(row) = rows.iter_mut().find(|r| r.category_id == *category_id) {
+                row.count += 1;
+            }
+            rolled_up.extend(lineage(*category_id));
+        }
+
+        for category_id in rolled_up {
+            let Some(row) = rows.iter_mut().find(|r| r.category_id == category_id) else {
+                continue;
+            };
+            row.total += 1;
+            row.last_seen = Some(attempt.timestamp.clone());
+            if let Some(point) = row.trend.iter_mut().find(|p| p.period == attempt.period) {
+                point.count += 1;
+            }
+        }
+    }
+
+    for row in &mut rows {
+        if categorized > 0 {
+            row.share = row.total as f64 / categorized as f64;
+        }
+    }
+    rows.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(ErrorBreakdown {
+        attempts: attempts.len(),
+        categorized,
+        uncategorized,
+        categories: rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn category_id(conn: &Connection, name: &str) -> i64 {
+        conn.query_row("SELECT id FROM ErrorCategories WHERE name = ?1", params![name], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn log(conn: &Connection, errors: &str, timestamp: &str) -> i64 {
+        conn.execute_batch(
+            "INSERT OR IGNORE INTO Materials (id, name_en) VALUES (1, 'Graphs');
+             INSERT OR IGNORE INTO Problems (id, generated_id, material_id, title) VALUES (1, 'ALG-1', 1, 'BFS');
+             INSERT OR IGNORE INTO Batches (id, problem_id, batch_number, started_at) VALUES (1, 1, 1, '2025-01-01 00:00:00');",
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO Attempts (batch_id, attempt_number, errors, timestamp) VALUES (1, 1, ?1, ?2)",
+            params![errors, timestamp],
+        ).unwrap();
+        conn.last_insert_rowid()
+    }
+
+    #[test]
+    fn keywords_match_whole_words_only() {
+        let regex = compile_rule(RuleKind::Keyword, "type").unwrap();
+        assert!(regex.is_match("Wrong TYPE for the index"));
+        assert!(!regex.is_match("prototype chain"));
+
+        let regex = compile_rule(RuleKind::Keyword, "c++").unwrap();
+        assert!(regex.is_match("old c++ habits"));
+
+        assert!(compile_rule(RuleKind::Regex, "(").is_err());
+        assert!(compile_rule(RuleKind::Keyword, "  ").is_err());
+    }
+
+    #[test]
+    fn rules_tag_attempts_with_every_matching_category() {
+        let conn = db::open_in_memory();
+        let attempt_id = log(&conn, "Forgot the edge case, off by one", "2025-01-01 10:00:00");
+
+        let tagged = classify_attempt(&conn, attempt_id).unwrap();
+        let mut expected = vec![category_id(&conn, "Boundary"), category_id(&conn, "Recall")];
+        expected.sort();
+        assert_eq!(tagged, expected);
+    }
+
+    #[test]
+    fn no_categories_by_hand_survives_reclassification() {
+        let conn = db::open_in_memory();
+        let attempt_id = log(&conn, "Forgot the base case", "2025-01-01 10:00:00");
+
+        assert!(set_manual_tags(&conn, attempt_id, Some(&[])).unwrap().is_empty());
+        let summary = reclassify_all(&conn).unwrap();
+        assert_eq!(summary.manual, 1);
+        assert!(attempt_categories(&conn, attempt_id).unwrap().is_empty());
+
+        let tagged = set_manual_tags(&conn, attempt_id, None).unwrap();
+        assert_eq!(tagged, vec![category_id(&conn, "Recall")]);
+    }
+
+    #[test]
+    fn weeks_are_iso_weeks() {
+        assert_eq!(TrendPeriod::Week.label("2024-12-30 09:00:00").unwrap(), "2025-W01");
+        assert_eq!(TrendPeriod::Week.label("2025-01-05 23:00:00").unwrap(), "2025-W01");
+        assert_eq!(TrendPeriod::Week.label("2021-01-01 12:00:00").unwrap(), "2020-W53");
+
+        let conn = db::open_in_memory();
+        let attempt_id = log(&conn, "Forgot the base case", "2024-12-31 10:00:00");
+        classify_attempt(&conn, attempt_id).unwrap();
+        let breakdown = breakdown(&conn, &ErrorBreakdownFilter::default()).unwrap();
+        let recall = breakdown.categories.iter().find(|c| c.name == "Recall").unwrap();
+        assert_eq!(recall.trend[0].period, "2025-W01");
+    }
+}
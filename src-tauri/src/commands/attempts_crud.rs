@@ -1,6 +1,9 @@
 use tauri::State;
 use rusqlite::params;
 use crate::db::{DbConnection, models::AttemptInput};
+use crate::scheduling;
+use crate::mastery;
+use crate::classification;
 
 #[tauri::command]
 pub fn update_attempt(
@@ -8,9 +11,10 @@ pub fn update_attempt(
     attempt_id: i64,
     attempt_data: AttemptInput,
 ) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
     
-    conn.execute(
+    tx.execute(
         "UPDATE Attempts 
          SET successful = ?1, time_spent_minutes = ?2, difficulty_rating = ?3,
              errors = ?4, resolution = ?5, commentary = ?6, status_tag = ?7
@@ -27,6 +31,13 @@ pub fn update_attempt(
         ],
     ).map_err(|e| e.to_string())?;
     
+    let problem_id = problem_id_for_attempt(&tx, attempt_id)?;
+    classification::classify_attempt(&tx, attempt_id)?;
+    mastery::update_solved_status(&tx, problem_id)?;
+    scheduling::reschedule_problem(&tx, problem_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    
     println!("✏️ Updated attempt #{}", attempt_id);
     Ok(())
 }
@@ -36,13 +47,21 @@ pub fn delete_attempt(
     db: State<DbConnection>,
     attempt_id: i64,
 ) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
     
-    conn.execute(
+    let problem_id = problem_id_for_attempt(&tx, attempt_id)?;
+    
+    tx.execute(
         "DELETE FROM Attempts WHERE id = ?1",
         params![attempt_id],
     ).map_err(|e| e.to_string())?;
     
+    mastery::update_solved_status(&tx, problem_id)?;
+    scheduling::reschedule_problem(&tx, problem_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    
     println!("🗑️ Deleted attempt #{}", attempt_id);
     Ok(())
 }
@@ -63,3 +82,17 @@ pub fn update_problem(
     
     Ok(())
 }
+
+// Helper: Find the problem an attempt belongs to
+fn problem_id_for_attempt(
+    conn: &rusqlite::Connection,
+    attempt_id: i64,
+) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT b.problem_id FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         WHERE a.id = ?1",
+        params![attempt_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())
+}
@@ -0,0 +1,79 @@
+use tauri::State;
+use std::path::PathBuf;
+use crate::backup::{self, BackupManifest};
+use crate::backup::auto::{self, AutoBackupSettings, BackupInfo};
+use crate::db::{self, settings, DbConnection};
+
+#[tauri::command]
+pub async fn create_backup(
+    app_handle: tauri::AppHandle,
+    db: State<'_, DbConnection>,
+    path: String,
+) -> Result<BackupManifest, String> {
+    let app_dir = db::app_data_dir(&app_handle)?;
+    let manifest = backup::create_archive(&db, &app_dir, &PathBuf::from(&path))?;
+    
+    println!("💾 Created backup {} ({} files)", path, manifest.files.len());
+    Ok(manifest)
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    app_handle: tauri::AppHandle,
+    db: State<'_, DbConnection>,
+    path: String,
+) -> Result<BackupManifest, String> {
+    let app_dir = db::app_data_dir(&app_handle)?;
+    let manifest = backup::restore_archive(&db, &app_dir, &PathBuf::from(&path))?;
+    
+    println!("♻️ Restored backup {} from {}", path, manifest.created_at);
+    Ok(manifest)
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<BackupInfo>, String> {
+    let app_dir = db::app_data_dir(&app_handle)?;
+    auto::list_backups(&app_dir)
+}
+
+#[tauri::command]
+pub async fn restore_backup_by_id(
+    app_handle: tauri::AppHandle,
+    db: State<'_, DbConnection>,
+    id: String,
+) -> Result<BackupManifest, String> {
+    let app_dir = db::app_data_dir(&app_handle)?;
+    let path = auto::backup_path_for_id(&app_dir, &id)?;
+    let manifest = backup::restore_archive(&db, &app_dir, &path)?;
+    
+    println!("♻️ Restored backup {} from {}", id, manifest.created_at);
+    Ok(manifest)
+}
+
+#[tauri::command]
+pub fn get_auto_backup_settings(
+    db: State<DbConnection>,
+) -> Result<AutoBackupSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    settings::load(&conn, auto::SETTINGS_KEY)
+}
+
+// Saving applies the new retention to existing automatic backups right away
+#[tauri::command]
+pub async fn update_auto_backup_settings(
+    app_handle: tauri::AppHandle,
+    db: State<'_, DbConnection>,
+    settings: AutoBackupSettings,
+) -> Result<Vec<String>, String> {
+    settings.validate()?;
+    
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        settings::save(&conn, auto::SETTINGS_KEY, &settings)?;
+    }
+    
+    let app_dir = db::app_data_dir(&app_handle)?;
+    auto::rotate(&app_dir, &settings)
+}
@@ -0,0 +1,106 @@
+use tauri::State;
+use rusqlite::params;
+use crate::db::{DbConnection, settings};
+use crate::segmentation::{self, ResegmentSummary, SegmentationSettings};
+use crate::{mastery, scheduling};
+use crate::utils::time::{format_sqlite_time, now_utc, parse_sqlite_time};
+
+#[tauri::command]
+pub fn get_segmentation_settings(
+    db: State<DbConnection>,
+) -> Result<SegmentationSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    segmentation::load_settings(&conn)
+}
+
+// New rules apply to future attempts; use resegment_problem to rebuild history
+#[tauri::command]
+pub fn update_segmentation_settings(
+    db: State<DbConnection>,
+    settings: SegmentationSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    settings::save(&conn, segmentation::SETTINGS_KEY, &settings)?;
+
+    println!("⚙️ Updated batch segmentation: {}h gap", settings.gap_hours);
+    Ok(())
+}
+
+// End the problem's open batch now so the next attempt starts a new one.
+// Returns false if there was no open batch.
+#[tauri::command]
+pub fn close_batch(
+    db: State<DbConnection>,
+    problem_id: i64,
+) -> Result<bool, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let open_batch: Option<(i64, String)> = conn.query_row(
+        "SELECT id, started_at FROM Batches
+         WHERE problem_id = ?1 AND ended_at IS NULL
+         ORDER BY batch_number DESC LIMIT 1",
+        params![problem_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).ok();
+
+    let (batch_id, started_at) = match open_batch {
+        Some(batch) => batch,
+        None => return Ok(false),
+    };
+
+    let last_attempt_time: Option<String> = conn.query_row(
+        "SELECT MAX(timestamp) FROM Attempts WHERE batch_id = ?1",
+        params![batch_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    // Don't stretch the batch past where the rules would have ended it
+    let now = now_utc();
+    let ended_at = match last_attempt_time {
+        Some(last_time) => {
+            let settings = segmentation::load_settings(&conn)?;
+            let close_time = segmentation::close_time(
+                &settings,
+                &parse_sqlite_time(&started_at)?,
+                &parse_sqlite_time(&last_time)?,
+            );
+            close_time.min(now)
+        }
+        None => now,
+    };
+
+    conn.execute(
+        "UPDATE Batches SET ended_at = ?1 WHERE id = ?2",
+        params![format_sqlite_time(&ended_at), batch_id],
+    ).map_err(|e| e.to_string())?;
+
+    println!("⏹️ Closed batch #{} for problem {}", batch_id, problem_id);
+    Ok(true)
+}
+
+// Rebuild a problem's batches from its attempt timestamps under the current rules
+#[tauri::command]
+pub fn resegment_problem(
+    db: State<DbConnection>,
+    problem_id: i64,
+) -> Result<ResegmentSummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let settings = segmentation::load_settings(&tx)?;
+    let summary = segmentation::rebuild_batches(&tx, problem_id, &settings)?;
+
+    // Batch boundaries feed both mastery and review scheduling
+    mastery::update_solved_status(&tx, problem_id)?;
+    scheduling::reschedule_problem(&tx, problem_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!(
+        "🔀 Re-segmented problem {}: {} -> {} batches",
+        problem_id, summary.batches_before, summary.batches_after
+    );
+    Ok(summary)
+}
@@ -0,0 +1,244 @@
+use tauri::State;
+use crate::db::DbConnection;
+use crate::catalog::{
+    self, DeletionPreview, Material, MaterialMerge, ProblemMerge, ProblemMove, Subject, SubjectUpdate,
+};
+use crate::mastery::RecomputeSummary;
+
+#[tauri::command]
+pub fn get_subject_details(
+    db: State<DbConnection>,
+) -> Result<Vec<Subject>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    catalog::list_subjects(&conn)
+}
+
+// Materials of a subject, or all materials when `subject_name` is None
+#[tauri::command]
+pub fn get_material_details(
+    db: State<DbConnection>,
+    subject_name: Option<String>,
+) -> Result<Vec<Material>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    catalog::list_materials(&conn, subject_name.as_deref())
+}
+
+#[tauri::command]
+pub fn create_subject(
+    db: State<DbConnection>,
+    name: String,
+    description: Option<String>,
+    commentary: Option<String>,
+) -> Result<Subject, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let subject_id = catalog::create_subject(&conn, &name, description.as_deref(), commentary.as_deref())?;
+
+    println!("📚 Created subject {}", name.trim());
+    catalog::load_subject(&conn, subject_id)
+}
+
+// Renames that change the problem ID prefix need `regenerate_ids` to
+// rewrite the IDs of the subject's problems
+#[tauri::command]
+pub fn update_subject(
+    db: State<DbConnection>,
+    subject_id: i64,
+    name: String,
+    description: Option<String>,
+    commentary: Option<String>,
+    regenerate_ids: Option<bool>,
+) -> Result<SubjectUpdate, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let regenerated = catalog::update_subject(
+        &tx,
+        subject_id,
+        &name,
+        description.as_deref(),
+        commentary.as_deref(),
+        regenerate_ids.unwrap_or(false),
+    )?;
+    let subject = catalog::load_subject(&tx, subject_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if !regenerated.is_empty() {
+        println!("✏️ Renamed subject to {}: {} problem IDs regenerated", subject.name, regenerated.len());
+    }
+    Ok(SubjectUpdate { subject, regenerated })
+}
+
+#[tauri::command]
+pub fn preview_subject_deletion(
+    db: State<DbConnection>,
+    subject_id: i64,
+) -> Result<DeletionPreview, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    catalog::subject_deletion_preview(&conn, subject_id)
+}
+
+// Also deletes the materials no other subject uses
+#[tauri::command]
+pub fn delete_subject(
+    db: State<DbConnection>,
+    subject_id: i64,
+) -> Result<DeletionPreview, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let deleted = catalog::delete_subject(&tx, subject_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🗑️ Deleted subject #{} ({} materials, {} problems)", subject_id, deleted.materials.len(), deleted.problems);
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub fn create_material(
+    db: State<DbConnection>,
+    subject_id: i64,
+    name_en: String,
+    name_ru: Option<String>,
+    commentary: Option<String>,
+) -> Result<Material, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let material_id = catalog::create_material(&tx, subject_id, &name_en, name_ru.as_deref(), commentary.as_deref())?;
+    let material = catalog::load_material(&tx, material_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("📚 Created material {}", material.name_en);
+    Ok(material)
+}
+
+#[tauri::command]
+pub fn update_material(
+    db: State<DbConnection>,
+    material_id: i64,
+    name_en: String,
+    name_ru: Option<String>,
+    commentary: Option<String>,
+) -> Result<Material, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    catalog::update_material(&conn, material_id, &name_en, name_ru.as_deref(), commentary.as_deref())?;
+    catalog::load_material(&conn, material_id)
+}
+
+// Fold a duplicate material into `keep_id`
+#[tauri::command]
+pub fn merge_materials(
+    db: State<DbConnection>,
+    keep_id: i64,
+    merge_id: i64,
+) -> Result<MaterialMerge, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let merge = catalog::merge_materials(&tx, keep_id, merge_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🔁 Merged material #{} into {} ({} problems moved)", merge_id, merge.material.name_en, merge.problems_moved);
+    Ok(merge)
+}
+
+// Linking and unlinking re-evaluate solved status, since the material's
+// problems may fall under a different subject's mastery policy
+#[tauri::command]
+pub fn link_material_to_subject(
+    db: State<DbConnection>,
+    subject_id: i64,
+    material_id: i64,
+) -> Result<RecomputeSummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = catalog::link_material(&tx, subject_id, material_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn unlink_material_from_subject(
+    db: State<DbConnection>,
+    subject_id: i64,
+    material_id: i64,
+) -> Result<RecomputeSummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = catalog::unlink_material(&tx, subject_id, material_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn preview_material_deletion(
+    db: State<DbConnection>,
+    material_id: i64,
+) -> Result<DeletionPreview, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    catalog::material_deletion_preview(&conn, material_id)
+}
+
+#[tauri::command]
+pub fn delete_material(
+    db: State<DbConnection>,
+    material_id: i64,
+) -> Result<DeletionPreview, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let deleted = catalog::delete_material(&tx, material_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🗑️ Deleted material #{} ({} problems)", material_id, deleted.problems);
+    Ok(deleted)
+}
+
+// Move a problem logged under the wrong material. `regenerate_id` allows a
+// new ID when the target's subjects use a different prefix.
+#[tauri::command]
+pub fn move_problem(
+    db: State<DbConnection>,
+    problem_id: i64,
+    material_name: String,
+    regenerate_id: Option<bool>,
+) -> Result<ProblemMove, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let moved = catalog::move_problem(&tx, problem_id, &material_name, regenerate_id.unwrap_or(false))?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🔄 Moved {} to {}", moved.generated_id, moved.material_name);
+    Ok(moved)
+}
+
+// Combine two problems that are really the same one into `keep_id`
+#[tauri::command]
+pub fn merge_problems(
+    db: State<DbConnection>,
+    keep_id: i64,
+    merge_id: i64,
+) -> Result<ProblemMerge, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let merge = catalog::merge_problems(&tx, keep_id, merge_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🔁 Merged {} into {} ({} attempts)", merge.merged_generated_id, merge.generated_id, merge.attempts);
+    Ok(merge)
+}
@@ -0,0 +1,155 @@
+use tauri::State;
+use crate::db::DbConnection;
+use crate::classification::{
+    self, ErrorBreakdown, ErrorBreakdownFilter, ErrorCategory, ReclassifySummary, RuleKind,
+};
+
+#[tauri::command]
+pub fn get_error_categories(
+    db: State<DbConnection>,
+) -> Result<Vec<ErrorCategory>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    classification::list_categories(&conn)
+}
+
+#[tauri::command]
+pub fn create_error_category(
+    db: State<DbConnection>,
+    name: String,
+    parent_id: Option<i64>,
+    description: Option<String>,
+) -> Result<ErrorCategory, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let category_id = classification::save_category(&conn, None, &name, parent_id, description.as_deref())?;
+
+    println!("🏷️ Created error category {}", name.trim());
+    classification::load_category(&conn, category_id)
+}
+
+#[tauri::command]
+pub fn update_error_category(
+    db: State<DbConnection>,
+    category_id: i64,
+    name: String,
+    parent_id: Option<i64>,
+    description: Option<String>,
+) -> Result<ErrorCategory, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    classification::save_category(&conn, Some(category_id), &name, parent_id, description.as_deref())?;
+    classification::load_category(&conn, category_id)
+}
+
+// Subcategories move up to the deleted category's parent
+#[tauri::command]
+pub fn delete_error_category(
+    db: State<DbConnection>,
+    category_id: i64,
+) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    classification::delete_category(&tx, category_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🗑️ Deleted error category #{}", category_id);
+    Ok(())
+}
+
+// Add a keyword or regex rule and re-tag existing attempts with it
+#[tauri::command]
+pub fn add_error_rule(
+    db: State<DbConnection>,
+    category_id: i64,
+    kind: RuleKind,
+    pattern: String,
+) -> Result<ReclassifySummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    classification::add_rule(&tx, category_id, kind, &pattern)?;
+    let summary = classification::reclassify_all(&tx)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🏷️ Added {} rule '{}': {} attempts tagged", kind.as_str(), pattern.trim(), summary.tagged);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn delete_error_rule(
+    db: State<DbConnection>,
+    rule_id: i64,
+) -> Result<ReclassifySummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    classification::delete_rule(&tx, rule_id)?;
+    let summary = classification::reclassify_all(&tx)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+// Tag an attempt by hand. The rules no longer touch it until
+// `category_ids` is None, which hands it back to them.
+#[tauri::command]
+pub fn set_attempt_error_categories(
+    db: State<DbConnection>,
+    attempt_id: i64,
+    category_ids: Option<Vec<i64>>,
+) -> Result<Vec<ErrorCategory>, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let tagged = classification::set_manual_tags(&tx, attempt_id, category_ids.as_deref())?;
+    let categories = classification::list_categories(&tx)?
+        .into_iter()
+        .filter(|c| tagged.contains(&c.id))
+        .collect();
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(categories)
+}
+
+#[tauri::command]
+pub fn get_attempt_error_categories(
+    db: State<DbConnection>,
+    attempt_id: i64,
+) -> Result<Vec<ErrorCategory>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let tagged = classification::attempt_categories(&conn, attempt_id)?;
+    Ok(classification::list_categories(&conn)?
+        .into_iter()
+        .filter(|c| tagged.contains(&c.id))
+        .collect())
+}
+
+#[tauri::command]
+pub fn reclassify_attempts(
+    db: State<DbConnection>,
+) -> Result<ReclassifySummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = classification::reclassify_all(&tx)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🔄 Re-classified {} attempts", summary.evaluated);
+    Ok(summary)
+}
+
+// Attempt counts per error category (subcategories rolled up into their
+// parents) with a trend per day, week or month
+#[tauri::command]
+pub fn get_error_breakdown(
+    db: State<DbConnection>,
+    filters: ErrorBreakdownFilter,
+) -> Result<ErrorBreakdown, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    classification::breakdown(&conn, &filters)
+}
@@ -0,0 +1,143 @@
+use tauri::State;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::db::{self, DbConnection};
+use crate::interchange::anki::{self, AnkiExportFilter, AnkiExportSummary};
+use crate::interchange::attempts_csv::{self, CsvImportOptions, CsvImportSummary};
+use crate::interchange::dataset::{self, Dataset, ImportMode, ImportSummary};
+use crate::interchange::markdown::{self, VaultExportSummary};
+use crate::interchange::vocabulary::{self, VocabularyImportOptions, VocabularyImportSummary};
+
+// Write every table to a versioned JSON file; returns the row count per table
+#[tauri::command]
+pub fn export_dataset(
+    db: State<DbConnection>,
+    path: String,
+) -> Result<BTreeMap<String, usize>, String> {
+    let dataset = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        dataset::export(&conn)?
+    };
+
+    let json = serde_json::to_vec_pretty(&dataset).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    println!("📤 Exported dataset to {}", path);
+    Ok(dataset.table_counts())
+}
+
+// Load a dataset file. All-or-nothing: on any error nothing is changed.
+#[tauri::command]
+pub fn import_dataset(
+    db: State<DbConnection>,
+    path: String,
+    mode: ImportMode,
+) -> Result<ImportSummary, String> {
+    let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let dataset: Dataset = serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid dataset file: {}", e))?;
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = dataset::import(&tx, &dataset, mode)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!(
+        "📥 Imported dataset from {} ({:?}, {} conflicts)",
+        path, mode, summary.conflicts.len()
+    );
+    Ok(summary)
+}
+
+// Import attempt logs from a spreadsheet. Nothing is written on a dry run or
+// when any row fails validation; the summary lists the row errors.
+#[tauri::command]
+pub fn import_attempts_csv(
+    db: State<DbConnection>,
+    path: String,
+    options: CsvImportOptions,
+) -> Result<CsvImportSummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = attempts_csv::import(&tx, Path::new(&path), &options)?;
+
+    if summary.dry_run || !summary.errors.is_empty() {
+        tx.rollback().map_err(|e| e.to_string())?;
+    } else {
+        tx.commit().map_err(|e| e.to_string())?;
+        println!(
+            "📥 Imported {} attempts from {} ({} problems)",
+            summary.imported, path, summary.problems_affected
+        );
+    }
+    Ok(summary)
+}
+
+// Write vocabulary and/or problems as an Anki deck package (.apkg)
+#[tauri::command]
+pub async fn export_anki(
+    app_handle: tauri::AppHandle,
+    db: State<'_, DbConnection>,
+    path: String,
+    filter: AnkiExportFilter,
+) -> Result<AnkiExportSummary, String> {
+    let app_dir = db::app_data_dir(&app_handle)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let summary = anki::export(&conn, &app_dir, Path::new(&path), &filter)?;
+
+    println!(
+        "🃏 Exported {} vocabulary and {} problem notes to {}",
+        summary.vocabulary_notes, summary.problem_notes, path
+    );
+    Ok(summary)
+}
+
+// Bulk-add words from an Anki package (.apkg) or a tab/comma separated word
+// list. All-or-nothing; skipped rows are listed in the summary.
+#[tauri::command]
+pub async fn import_vocabulary(
+    app_handle: tauri::AppHandle,
+    db: State<'_, DbConnection>,
+    path: String,
+    options: VocabularyImportOptions,
+) -> Result<VocabularyImportSummary, String> {
+    let app_dir = db::app_data_dir(&app_handle)?;
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = vocabulary::import(&tx, Path::new(&path), &app_dir, &options)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!(
+        "📚 Imported vocabulary from {}: {} new, {} updated, {} duplicates skipped",
+        path, summary.inserted, summary.updated, summary.skipped_duplicates
+    );
+    Ok(summary)
+}
+
+// Write an Obsidian-style vault: a note per problem with its attempt timeline,
+// index notes per subject and material, and copies of problem images.
+// Re-running updates the same files in place.
+#[tauri::command]
+pub async fn export_markdown_vault(
+    app_handle: tauri::AppHandle,
+    db: State<'_, DbConnection>,
+    dir: String,
+) -> Result<VaultExportSummary, String> {
+    let app_dir = db::app_data_dir(&app_handle)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let summary = markdown::export(&conn, &app_dir, Path::new(&dir))?;
+
+    println!(
+        "🗒️ Exported {} problem notes to {}: {} files written, {} unchanged",
+        summary.problems, dir, summary.written, summary.unchanged
+    );
+    Ok(summary)
+}
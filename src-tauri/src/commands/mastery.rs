@@ -0,0 +1,86 @@
+use tauri::State;
+use rusqlite::params;
+use crate::db::DbConnection;
+use crate::mastery::{self, MasteryPolicy, RecomputeSummary};
+
+#[tauri::command]
+pub fn get_mastery_policies(
+    db: State<DbConnection>,
+) -> Result<Vec<MasteryPolicy>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    mastery::list_policies(&conn)
+}
+
+// Effective policy for a subject (falls back to the global default)
+#[tauri::command]
+pub fn get_mastery_policy(
+    db: State<DbConnection>,
+    subject_name: Option<String>,
+) -> Result<MasteryPolicy, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    mastery::policy_for_subject(&conn, subject_name.as_deref())
+}
+
+// Create or replace a policy and re-evaluate the problems it governs
+#[tauri::command]
+pub fn set_mastery_policy(
+    db: State<DbConnection>,
+    policy: MasteryPolicy,
+) -> Result<RecomputeSummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    mastery::save_policy(&tx, &policy)?;
+    let summary = mastery::recompute(&tx, policy.subject_name.as_deref())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!(
+        "⚙️ Saved mastery policy for {}: {} newly solved, {} newly unsolved",
+        policy.subject_name.as_deref().unwrap_or("all subjects"),
+        summary.newly_solved,
+        summary.newly_unsolved
+    );
+    Ok(summary)
+}
+
+// Remove a subject's policy so it falls back to the global default
+#[tauri::command]
+pub fn delete_mastery_policy(
+    db: State<DbConnection>,
+    subject_name: String,
+) -> Result<RecomputeSummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let deleted = tx.execute(
+        "DELETE FROM MasteryPolicies
+         WHERE subject_id = (SELECT id FROM Subjects WHERE name = ?1)",
+        params![&subject_name],
+    ).map_err(|e| e.to_string())?;
+
+    if deleted == 0 {
+        return Err(format!("Subject '{}' has no mastery policy", subject_name));
+    }
+
+    let summary = mastery::recompute(&tx, Some(&subject_name))?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn recompute_solved_status(
+    db: State<DbConnection>,
+    subject_name: Option<String>,
+) -> Result<RecomputeSummary, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let summary = mastery::recompute(&tx, subject_name.as_deref())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🔄 Re-evaluated {} problems", summary.evaluated);
+    Ok(summary)
+}
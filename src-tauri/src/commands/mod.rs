@@ -7,6 +7,16 @@ pub mod images;
 pub mod attempts_crud;
 pub mod stats;
 pub mod russian;
+pub mod scheduling;
+pub mod mastery;
+pub mod batches;
+pub mod backup;
+pub mod interchange;
+pub mod search;
+pub mod classification;
+pub mod recurrence;
+pub mod resources;
+pub mod catalog;
 
 #[tauri::command]
 pub fn test_database(db: State<DbConnection>) -> Result<String, String> {
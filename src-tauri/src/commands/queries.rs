@@ -3,6 +3,7 @@ use rusqlite::params;
 use crate::db::DbConnection;
 use serde::{Serialize, Deserialize};
 use crate::db::models::{ProblemDetail, AttemptView};
+use crate::classification;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SubjectItem {
@@ -219,7 +220,7 @@ fn get_problem_detail_by_id(
          ORDER BY a.attempt_number ASC"
     ).map_err(|e| e.to_string())?;
     
-    let attempts = stmt.query_map(params![problem_id], |row| {
+    let mut attempts = stmt.query_map(params![problem_id], |row| {
         Ok(AttemptView {
             id: row.get(0)?,
             attempt_number: row.get(1)?,
@@ -232,11 +233,16 @@ fn get_problem_detail_by_id(
             resolution: row.get(8)?,
             commentary: row.get(9)?,
             timestamp: row.get(10)?,
+            error_categories: Vec::new(),
         })
     })
     .map_err(|e| e.to_string())?
     .collect::<Result<Vec<_>, _>>()
     .map_err(|e| e.to_string())?;
+
+    for attempt in &mut attempts {
+        attempt.error_categories = classification::attempt_category_names(conn, attempt.id)?;
+    }
     
     Ok(ProblemDetail {
     id: problem_id,
@@ -0,0 +1,19 @@
+use tauri::State;
+use crate::db::DbConnection;
+use crate::recurrence::{self, RecurringMistake, DEFAULT_MIN_OCCURRENCES};
+
+// Groups of similar error texts across problems and materials that occurred
+// at least `min_occurrences` times (since `since`, YYYY-MM-DD, if given)
+#[tauri::command]
+pub fn get_recurring_mistakes(
+    db: State<DbConnection>,
+    min_occurrences: Option<usize>,
+    since: Option<String>,
+) -> Result<Vec<RecurringMistake>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    recurrence::recurring_mistakes(
+        &conn,
+        min_occurrences.unwrap_or(DEFAULT_MIN_OCCURRENCES),
+        since.as_deref(),
+    )
+}
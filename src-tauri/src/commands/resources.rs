@@ -0,0 +1,130 @@
+use tauri::State;
+use crate::db::DbConnection;
+use crate::resources::{self, AttemptResource, Resource, ResourceEffectiveness, ResourceType};
+
+#[tauri::command]
+pub fn get_resources(
+    db: State<DbConnection>,
+) -> Result<Vec<Resource>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    resources::list_resources(&conn)
+}
+
+#[tauri::command]
+pub fn create_resource(
+    db: State<DbConnection>,
+    name: String,
+    resource_type: ResourceType,
+    url: Option<String>,
+) -> Result<Resource, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let resource_id = resources::save_resource(&conn, None, &name, resource_type, url.as_deref())?;
+
+    println!("📚 Created resource {} ({})", name.trim(), resource_type.as_str());
+    resources::load_resource(&conn, resource_id)
+}
+
+#[tauri::command]
+pub fn update_resource(
+    db: State<DbConnection>,
+    resource_id: i64,
+    name: String,
+    resource_type: ResourceType,
+    url: Option<String>,
+) -> Result<Resource, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    resources::save_resource(&conn, Some(resource_id), &name, resource_type, url.as_deref())?;
+    resources::load_resource(&conn, resource_id)
+}
+
+// Also unlinks it from every attempt; returns how many attempts used it
+#[tauri::command]
+pub fn delete_resource(
+    db: State<DbConnection>,
+    resource_id: i64,
+) -> Result<usize, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let unlinked = resources::delete_resource(&tx, resource_id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🗑️ Deleted resource #{} ({} attempts unlinked)", resource_id, unlinked);
+    Ok(unlinked)
+}
+
+// Fold duplicates into `keep_id`, moving their attempt links and notes over
+#[tauri::command]
+pub fn merge_resources(
+    db: State<DbConnection>,
+    keep_id: i64,
+    merge_ids: Vec<i64>,
+) -> Result<Resource, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let resource = resources::merge_resources(&tx, keep_id, &merge_ids)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🔁 Merged {} resources into {}", merge_ids.len(), resource.name);
+    Ok(resource)
+}
+
+// Resources that look like the same thing, to offer for merging
+#[tauri::command]
+pub fn find_duplicate_resources(
+    db: State<DbConnection>,
+) -> Result<Vec<Vec<Resource>>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    resources::find_duplicates(&conn)
+}
+
+#[tauri::command]
+pub fn get_attempt_resources(
+    db: State<DbConnection>,
+    attempt_id: i64,
+) -> Result<Vec<AttemptResource>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    resources::attempt_resources(&conn, attempt_id)
+}
+
+// Link a resource to an attempt, or replace the notes on an existing link
+#[tauri::command]
+pub fn set_attempt_resource(
+    db: State<DbConnection>,
+    attempt_id: i64,
+    resource_id: i64,
+    notes: Option<String>,
+) -> Result<Vec<AttemptResource>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    resources::set_attempt_resource(&conn, attempt_id, resource_id, notes.as_deref())?;
+    resources::attempt_resources(&conn, attempt_id)
+}
+
+#[tauri::command]
+pub fn unlink_attempt_resource(
+    db: State<DbConnection>,
+    attempt_id: i64,
+    resource_id: i64,
+) -> Result<Vec<AttemptResource>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    resources::unlink_attempt_resource(&conn, attempt_id, resource_id)?;
+    resources::attempt_resources(&conn, attempt_id)
+}
+
+// Success on the next attempt and time to mastery with each resource vs.
+// without it, per material (only `material_name` if given)
+#[tauri::command]
+pub fn get_resource_effectiveness(
+    db: State<DbConnection>,
+    material_name: Option<String>,
+) -> Result<Vec<ResourceEffectiveness>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    resources::effectiveness(&conn, material_name.as_deref())
+}
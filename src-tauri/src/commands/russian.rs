@@ -1,6 +1,15 @@
 use tauri::State;
-use rusqlite::params;
+use rusqlite::{params, Connection};
 use crate::db::DbConnection;
+use crate::russian::extraction::{self, LinkedWord, UnknownWord, VocabularyExtraction};
+use crate::russian::grammar::{self, Aspect, Gender, GrammaticalCase, PartOfSpeech, VocabularyGrammar};
+use crate::russian::form_drill::{self, FormAnswerResult, FormDrill, FormDrillKind};
+use crate::russian::glossary::{self, MaterialReadiness, MaterialTerm};
+use crate::russian::morphology;
+use crate::russian::rubric::{self, DrillRubric, RubricInput};
+use crate::russian::transliteration::{self, Script, TransliterationScheme};
+use crate::scheduling::{sm2, due_date, Grade, MemoryState};
+use crate::utils::time::{format_sqlite_time, now_utc};
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,6 +22,71 @@ pub struct VocabularyEntry {
     pub first_seen: String,
     pub last_reviewed: Option<String>,
     pub review_count: i32,
+    pub ease: f64,
+    pub interval_days: f64,
+    pub lapses: i32,
+    pub due_at: Option<String>,
+    // Stem shared by all inflected forms, e.g. "книг" for книга/книги
+    pub lemma: Option<String>,
+    // Inflected forms grouped under this entry
+    pub forms: Vec<String>,
+    pub grammar: VocabularyGrammar,
+}
+
+const VOCABULARY_COLUMNS: &str =
+    "v.id, v.word_ru, v.translation_en, m.name_en, v.example_sentence,
+     v.first_seen, v.last_reviewed, v.review_count,
+     v.ease, v.interval_days, v.lapses, v.due_at, v.lemma,
+     (SELECT GROUP_CONCAT(f.form, '|') FROM VocabularyForms f WHERE f.vocabulary_id = v.id),
+     v.part_of_speech, v.gender, v.animate, v.aspect, v.aspect_partner, v.stress_position,
+     (SELECT json_group_array(json_object('preposition', g.preposition, 'case', g.grammatical_case, 'note', g.note))
+      FROM VocabularyGovernment g WHERE g.vocabulary_id = v.id),
+     (SELECT json_group_object(i.form_key, i.form)
+      FROM VocabularyIrregularForms i WHERE i.vocabulary_id = v.id)";
+
+fn vocabulary_from_row(row: &rusqlite::Row) -> rusqlite::Result<VocabularyEntry> {
+    Ok(VocabularyEntry {
+        id: row.get(0)?,
+        word_ru: row.get(1)?,
+        translation_en: row.get(2)?,
+        material_name: row.get(3)?,
+        example_sentence: row.get(4)?,
+        first_seen: row.get(5)?,
+        last_reviewed: row.get(6)?,
+        review_count: row.get(7)?,
+        ease: row.get(8)?,
+        interval_days: row.get(9)?,
+        lapses: row.get(10)?,
+        due_at: row.get(11)?,
+        lemma: row.get(12)?,
+        forms: row.get::<_, Option<String>>(13)?
+            .map(|forms| forms.split('|').map(str::to_string).collect())
+            .unwrap_or_default(),
+        grammar: VocabularyGrammar {
+            part_of_speech: row.get::<_, Option<String>>(14)?.as_deref().and_then(PartOfSpeech::parse),
+            gender: row.get::<_, Option<String>>(15)?.as_deref().and_then(Gender::parse),
+            animate: row.get(16)?,
+            aspect: row.get::<_, Option<String>>(17)?.as_deref().and_then(Aspect::parse),
+            aspect_partner: row.get(18)?,
+            stress_position: row.get(19)?,
+            government: serde_json::from_str(&row.get::<_, String>(20)?).unwrap_or_default(),
+            irregular_forms: serde_json::from_str(&row.get::<_, String>(21)?).unwrap_or_default(),
+        },
+    })
+}
+
+fn fetch_vocabulary_entry(conn: &Connection, word_id: i64) -> Result<VocabularyEntry, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {}
+             FROM RussianVocabulary v
+             LEFT JOIN Materials m ON v.material_id = m.id
+             WHERE v.id = ?1",
+            VOCABULARY_COLUMNS
+        ),
+        params![word_id],
+        vocabulary_from_row,
+    ).map_err(|_| format!("Vocabulary entry {} not found", word_id))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,8 +99,16 @@ pub struct DrillAttempt {
     pub errors_ru: Option<String>,
     pub resolution_ru: Option<String>,
     pub timestamp: String,
+    // None until the drill has been scored
+    pub rubric: Option<DrillRubric>,
 }
 
+// Adding a form the grammar of an existing entry produces (e.g. "книги"
+// when "книга" is a noun) records it as a form of that entry and returns
+// the existing id, unless `keep_separate` is set. Words that only share a
+// stem are grouped when `group_with` names the entry, as confirmed through
+// find_vocabulary_group. A grouped word's translation is added to the
+// entry's.
 #[tauri::command]
 pub fn add_vocabulary(
     db: State<DbConnection>,
@@ -34,6 +116,8 @@ pub fn add_vocabulary(
     translation_en: String,
     material_name: Option<String>,
     example_sentence: Option<String>,
+    keep_separate: Option<bool>,
+    group_with: Option<i64>,
 ) -> Result<i64, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     
@@ -48,44 +132,164 @@ pub fn add_vocabulary(
         None
     };
     
-    conn.execute(
-        "INSERT INTO RussianVocabulary (word_ru, translation_en, material_id, example_sentence)
-         VALUES (?1, ?2, ?3, ?4)",
-        params![word_ru, translation_en, material_id, example_sentence],
-    ).map_err(|e| e.to_string())?;
-    
-    let vocab_id = conn.last_insert_rowid();
-    println!("📚 Added vocabulary: {} = {}", word_ru, translation_en);
+    let (vocab_id, grouped) = morphology::insert_vocabulary(
+        &conn,
+        &word_ru,
+        &translation_en,
+        material_id,
+        example_sentence.as_deref(),
+        keep_separate.unwrap_or(false),
+        group_with,
+    )?;
     
+    if grouped {
+        println!("📚 Grouped {} under existing entry #{}", word_ru, vocab_id);
+    } else {
+        println!("📚 Added vocabulary: {} = {}", word_ru, translation_en);
+    }
     Ok(vocab_id)
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewVocabularyWord {
+    pub word_ru: String,
+    pub translation_en: String,
+    #[serde(default)]
+    pub example_sentence: Option<String>,
+    #[serde(default)]
+    pub keep_separate: bool,
+    // Entry to group the word under, confirmed through find_vocabulary_group
+    #[serde(default)]
+    pub group_with: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkippedWord {
+    pub word_ru: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VocabularyBatchResult {
+    // One id per added or grouped word, in input order
+    pub vocabulary_ids: Vec<i64>,
+    pub added: usize,
+    pub grouped: usize,
+    pub skipped: Vec<SkippedWord>,
+}
+
+// Add several words at once, typically the unknown words returned by
+// log_drill_attempt. Words already in the vocabulary or missing a
+// translation are skipped rather than failing the batch. With `drill_id`,
+// the words are also linked to that drill and default to its material.
+#[tauri::command]
+pub fn add_vocabulary_batch(
+    db: State<DbConnection>,
+    words: Vec<NewVocabularyWord>,
+    material_name: Option<String>,
+    drill_id: Option<i64>,
+) -> Result<VocabularyBatchResult, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let drill_material: Option<i64> = match drill_id {
+        Some(id) => Some(tx.query_row(
+            "SELECT material_id FROM RussianDrillAttempts WHERE id = ?1",
+            params![id],
+            |row| row.get(0)
+        ).map_err(|_| format!("Drill {} not found", id))?),
+        None => None,
+    };
+    
+    let material_id: Option<i64> = match material_name {
+        Some(ref mat_name) => Some(tx.query_row(
+            "SELECT id FROM Materials WHERE name_en = ?1",
+            params![mat_name],
+            |row| row.get(0)
+        ).map_err(|_| format!("Material '{}' not found", mat_name))?),
+        None => drill_material,
+    };
+    
+    let mut result = VocabularyBatchResult::default();
+    for word in words {
+        let word_ru = word.word_ru.trim();
+        let translation_en = word.translation_en.trim();
+        
+        if word_ru.is_empty() || translation_en.is_empty() {
+            result.skipped.push(SkippedWord {
+                word_ru: word_ru.to_string(),
+                reason: "Word and translation are required".to_string(),
+            });
+            continue;
+        }
+        
+        let inserted = morphology::insert_vocabulary(
+            &tx,
+            word_ru,
+            translation_en,
+            material_id,
+            word.example_sentence.as_deref(),
+            word.keep_separate,
+            word.group_with,
+        );
+        
+        let (vocab_id, grouped) = match inserted {
+            Ok(inserted) => inserted,
+            Err(reason) => {
+                result.skipped.push(SkippedWord { word_ru: word_ru.to_string(), reason });
+                continue;
+            }
+        };
+        
+        if let Some(drill_id) = drill_id {
+            tx.execute(
+                "INSERT OR IGNORE INTO DrillVocabulary (drill_id, vocabulary_id) VALUES (?1, ?2)",
+                params![drill_id, vocab_id],
+            ).map_err(|e| e.to_string())?;
+        }
+        
+        if grouped { result.grouped += 1 } else { result.added += 1 }
+        result.vocabulary_ids.push(vocab_id);
+    }
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("📚 Batch added {} words ({} grouped, {} skipped)", result.added, result.grouped, result.skipped.len());
+    
+    Ok(result)
+}
+
+// The existing entry a word may be a form of: one whose grammar produces
+// it, or else the oldest entry sharing its stem. Pass its id as `group_with`
+// to add_vocabulary once the user confirms.
+#[tauri::command]
+pub fn find_vocabulary_group(
+    db: State<DbConnection>,
+    word_ru: String,
+) -> Result<Option<VocabularyEntry>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    match morphology::find_group_candidate(&conn, &word_ru)? {
+        Some((vocab_id, _)) => fetch_vocabulary_entry(&conn, vocab_id).map(Some),
+        None => Ok(None),
+    }
+}
+
 #[tauri::command]
 pub fn get_all_vocabulary(
     db: State<DbConnection>,
 ) -> Result<Vec<VocabularyEntry>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     
-    let mut stmt = conn.prepare(
-        "SELECT v.id, v.word_ru, v.translation_en, m.name_en, v.example_sentence,
-                v.first_seen, v.last_reviewed, v.review_count
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM RussianVocabulary v
          LEFT JOIN Materials m ON v.material_id = m.id
-         ORDER BY v.last_reviewed DESC, v.first_seen DESC"
-    ).map_err(|e| e.to_string())?;
+         ORDER BY v.last_reviewed DESC, v.first_seen DESC",
+        VOCABULARY_COLUMNS
+    )).map_err(|e| e.to_string())?;
     
-    let entries = stmt.query_map([], |row| {
-        Ok(VocabularyEntry {
-            id: row.get(0)?,
-            word_ru: row.get(1)?,
-            translation_en: row.get(2)?,
-            material_name: row.get(3)?,
-            example_sentence: row.get(4)?,
-            first_seen: row.get(5)?,
-            last_reviewed: row.get(6)?,
-            review_count: row.get(7)?,
-        })
-    })
+    let entries = stmt.query_map([], vocabulary_from_row)
     .map_err(|e| e.to_string())?
     .collect::<Result<Vec<_>, _>>()
     .map_err(|e| e.to_string())?;
@@ -100,50 +304,70 @@ pub fn search_vocabulary(
 ) -> Result<Vec<VocabularyEntry>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     
-    let term = format!("%{}%", search_term);
-    
-    let mut stmt = conn.prepare(
-        "SELECT v.id, v.word_ru, v.translation_en, m.name_en, v.example_sentence,
-                v.first_seen, v.last_reviewed, v.review_count
+    // Substring match as before, plus any word sharing the term's stem, so
+    // "книги" finds "книга" and "елка" finds "ёлка". Latin input is also
+    // tried as transliterated or wrong-layout Russian ("kniga", "rybuf").
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM RussianVocabulary v
          LEFT JOIN Materials m ON v.material_id = m.id
          WHERE v.word_ru LIKE ?1 OR v.translation_en LIKE ?1
-         ORDER BY v.word_ru"
-    ).map_err(|e| e.to_string())?;
+            OR v.lemma LIKE '%' || ?2 || '%'
+            OR v.id IN (SELECT vocabulary_id FROM VocabularyForms WHERE form LIKE ?1)
+         ORDER BY v.lemma = ?2 DESC, v.word_ru",
+        VOCABULARY_COLUMNS
+    )).map_err(|e| e.to_string())?;
     
-    let entries = stmt.query_map(params![term], |row| {
-        Ok(VocabularyEntry {
-            id: row.get(0)?,
-            word_ru: row.get(1)?,
-            translation_en: row.get(2)?,
-            material_name: row.get(3)?,
-            example_sentence: row.get(4)?,
-            first_seen: row.get(5)?,
-            last_reviewed: row.get(6)?,
-            review_count: row.get(7)?,
-        })
-    })
-    .map_err(|e| e.to_string())?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| e.to_string())?;
+    let mut entries: Vec<VocabularyEntry> = Vec::new();
+    for variant in transliteration::search_variants(&search_term) {
+        let term = format!("%{}%", variant);
+        let lemma = morphology::lemma_key(&variant);
+        
+        let matches = stmt.query_map(params![term, lemma], vocabulary_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+        
+        for entry in matches {
+            if !entries.iter().any(|e| e.id == entry.id) {
+                entries.push(entry);
+            }
+        }
+    }
     
     Ok(entries)
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogDrillResponse {
+    pub drill_id: i64,
+    pub attempt_number: i32,
+    // Vocabulary linked to the drill, listed or found in its text
+    pub linked_words: Vec<LinkedWord>,
+    // Words in the drill text (or listed) with no vocabulary entry, as
+    // candidates for add_vocabulary_batch
+    pub unknown_words: Vec<UnknownWord>,
+}
+
+// `status` is only a starting point: once the drill is scored with
+// score_drill it is derived from the rubric history.
+// Besides `vocabulary_words`, the drill is linked to every vocabulary entry
+// mentioned in errors_ru, resolution_ru or commentary.
 #[tauri::command]
 pub fn log_drill_attempt(
     db: State<DbConnection>,
     material_name: String,
-    status: String,
+    status: Option<String>,
     errors_ru: Option<String>,
     resolution_ru: Option<String>,
     commentary: Option<String>,
     vocabulary_words: Vec<String>,
-) -> Result<i64, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+) -> Result<LogDrillResponse, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
     
     // Find or create material
-    let material_id: i64 = conn.query_row(
+    let material_id: i64 = tx.query_row(
         "INSERT INTO Materials (name_en) VALUES (?1)
          ON CONFLICT(name_en) DO UPDATE SET name_en=name_en
          RETURNING id",
@@ -152,46 +376,116 @@ pub fn log_drill_attempt(
     ).map_err(|e| e.to_string())?;
     
     // Count existing drill attempts for this material
-    let attempt_number: i32 = conn.query_row(
+    let attempt_number: i32 = tx.query_row(
         "SELECT COUNT(*) + 1 FROM RussianDrillAttempts WHERE material_id = ?1",
         params![material_id],
         |row| row.get(0)
     ).map_err(|e| e.to_string())?;
     
     // Insert drill attempt
-    conn.execute(
+    tx.execute(
         "INSERT INTO RussianDrillAttempts (material_id, attempt_number, status, commentary, errors_ru, resolution_ru)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![material_id, attempt_number, status, commentary, errors_ru, resolution_ru],
+        params![material_id, attempt_number, status.as_deref().unwrap_or("learning"), commentary, errors_ru, resolution_ru],
     ).map_err(|e| e.to_string())?;
     
-    let drill_id = conn.last_insert_rowid();
+    let drill_id = tx.last_insert_rowid();
     
-    // Link vocabulary words
+    // Link vocabulary words; inflected forms link their base entry. Review
+    // stats are only updated by graded flashcard reviews (review_vocabulary),
+    // not by being mentioned in a drill.
+    let mut linked_words = Vec::new();
+    let mut unknown_words = Vec::new();
     for word in vocabulary_words {
-        if let Ok(vocab_id) = conn.query_row::<i64, _, _>(
-            "SELECT id FROM RussianVocabulary WHERE word_ru = ?1",
-            params![&word],
-            |row| row.get(0)
-        ) {
-            conn.execute(
-                "INSERT OR IGNORE INTO DrillVocabulary (drill_id, vocabulary_id) VALUES (?1, ?2)",
-                params![drill_id, vocab_id],
-            ).map_err(|e| e.to_string())?;
-            
-            // Update review stats
-            conn.execute(
-                "UPDATE RussianVocabulary 
-                 SET last_reviewed = datetime('now'), review_count = review_count + 1
-                 WHERE id = ?1",
-                params![vocab_id],
-            ).map_err(|e| e.to_string())?;
+        match morphology::find_entry(&tx, &word, None)? {
+            Some((vocab_id, word_ru)) => {
+                tx.execute(
+                    "INSERT OR IGNORE INTO DrillVocabulary (drill_id, vocabulary_id) VALUES (?1, ?2)",
+                    params![drill_id, vocab_id],
+                ).map_err(|e| e.to_string())?;
+                linked_words.push(LinkedWord { vocabulary_id: vocab_id, word_ru });
+            }
+            None if !word.trim().is_empty() => {
+                let word = word.trim().to_string();
+                unknown_words.push(UnknownWord { word: word.clone(), forms: vec![word], occurrences: 1 });
+            }
+            None => {}
         }
     }
     
-    println!("📝 Logged Russian drill attempt #{}", attempt_number);
+    let extraction = extraction::link_drill(&tx, drill_id)?;
+    for word in extraction.linked {
+        if !linked_words.iter().any(|w| w.vocabulary_id == word.vocabulary_id) {
+            linked_words.push(word);
+        }
+    }
+    for word in extraction.unknown {
+        let lemma = morphology::lemma_key(&word.word);
+        if !unknown_words.iter().any(|w| morphology::lemma_key(&w.word) == lemma) {
+            unknown_words.push(word);
+        }
+    }
     
-    Ok(drill_id)
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("📝 Logged Russian drill attempt #{} ({} words linked, {} unknown)",
+        attempt_number, linked_words.len(), unknown_words.len());
+    
+    Ok(LogDrillResponse { drill_id, attempt_number, linked_words, unknown_words })
+}
+
+// Re-run vocabulary extraction on a logged drill, e.g. after adding words.
+// Links newly known words and returns what is still unknown.
+#[tauri::command]
+pub fn extract_drill_vocabulary(
+    db: State<DbConnection>,
+    drill_id: i64,
+) -> Result<VocabularyExtraction, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    extraction::link_drill(&conn, drill_id)
+}
+
+// Columns read by drill_from_row; the rubric is LEFT JOINed as `r`
+fn drill_columns() -> String {
+    format!(
+        "d.id, m.name_en, d.attempt_number, d.status, d.commentary, d.errors_ru, d.resolution_ru,
+         d.timestamp, r.drill_id, {}",
+        rubric::RUBRIC_COLUMNS
+    )
+}
+
+fn drill_from_row(row: &rusqlite::Row) -> rusqlite::Result<DrillAttempt> {
+    let scored: Option<i64> = row.get(8)?;
+    Ok(DrillAttempt {
+        id: row.get(0)?,
+        material_name: row.get(1)?,
+        attempt_number: row.get(2)?,
+        status: row.get(3)?,
+        commentary: row.get(4)?,
+        errors_ru: row.get(5)?,
+        resolution_ru: row.get(6)?,
+        timestamp: row.get(7)?,
+        rubric: match scored {
+            Some(_) => Some(rubric::rubric_from_row(row, 9)?),
+            None => None,
+        },
+    })
+}
+
+fn fetch_drill(conn: &Connection, drill_id: i64) -> Result<DrillAttempt, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {}
+             FROM RussianDrillAttempts d
+             JOIN Materials m ON d.material_id = m.id
+             LEFT JOIN DrillRubricScores r ON r.drill_id = d.id
+             WHERE d.id = ?1",
+            drill_columns()
+        ),
+        params![drill_id],
+        drill_from_row
+    ).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -201,30 +495,319 @@ pub fn get_drill_history(
 ) -> Result<Vec<DrillAttempt>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     
-    let mut stmt = conn.prepare(
-        "SELECT d.id, m.name_en, d.attempt_number, d.status, d.commentary,
-                d.errors_ru, d.resolution_ru, d.timestamp
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM RussianDrillAttempts d
          JOIN Materials m ON d.material_id = m.id
+         LEFT JOIN DrillRubricScores r ON r.drill_id = d.id
          ORDER BY d.timestamp DESC
-         LIMIT ?1"
-    ).map_err(|e| e.to_string())?;
+         LIMIT ?1",
+        drill_columns()
+    )).map_err(|e| e.to_string())?;
     
-    let drills = stmt.query_map(params![limit], |row| {
-        Ok(DrillAttempt {
-            id: row.get(0)?,
-            material_name: row.get(1)?,
-            attempt_number: row.get(2)?,
-            status: row.get(3)?,
-            commentary: row.get(4)?,
-            errors_ru: row.get(5)?,
-            resolution_ru: row.get(6)?,
-            timestamp: row.get(7)?,
-        })
-    })
+    let drills = stmt.query_map(params![limit], drill_from_row)
     .map_err(|e| e.to_string())?
     .collect::<Result<Vec<_>, _>>()
     .map_err(|e| e.to_string())?;
     
     Ok(drills)
 }
+
+// Score a drill on the rubric (fluency, accuracy and completeness 1-5;
+// terminology coverage is computed from resolution_ru) and re-derive the
+// material's drill statuses
+#[tauri::command]
+pub fn score_drill(
+    db: State<DbConnection>,
+    drill_id: i64,
+    rubric: RubricInput,
+) -> Result<DrillAttempt, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let scored = rubric::score_drill(&tx, drill_id, &rubric)?;
+    let drill = fetch_drill(&tx, drill_id)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("📝 Scored drill #{}: {:.2} ({})", drill.attempt_number, scored.overall, drill.status);
+    Ok(drill)
+}
+
+#[tauri::command]
+pub fn review_vocabulary(
+    db: State<DbConnection>,
+    word_id: i64,
+    grade: Grade,
+) -> Result<VocabularyEntry, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let state: MemoryState = tx.query_row(
+        "SELECT ease, interval_days, repetitions, lapses FROM RussianVocabulary WHERE id = ?1",
+        params![word_id],
+        |row| Ok(MemoryState {
+            stability: row.get(1)?,
+            difficulty: None,
+            ease: row.get(0)?,
+            interval_days: row.get(1)?,
+            repetitions: row.get(2)?,
+            lapses: row.get(3)?,
+        })
+    ).map_err(|_| format!("Vocabulary entry {} not found", word_id))?;
+    
+    let next = sm2::next_state(&state, grade);
+    let now = now_utc();
+    let due_at = due_date(&now, next.interval_days);
+    
+    tx.execute(
+        "UPDATE RussianVocabulary
+         SET ease = ?1, interval_days = ?2, repetitions = ?3, lapses = ?4, due_at = ?5,
+             last_reviewed = ?6, review_count = review_count + 1
+         WHERE id = ?7",
+        params![
+            next.ease,
+            next.interval_days,
+            next.repetitions,
+            next.lapses,
+            format_sqlite_time(&due_at),
+            format_sqlite_time(&now),
+            word_id,
+        ],
+    ).map_err(|e| e.to_string())?;
+    
+    tx.execute(
+        "INSERT INTO VocabularyReviews (vocabulary_id, grade, interval_days, ease, reviewed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![word_id, grade.as_str(), next.interval_days, next.ease, format_sqlite_time(&now)],
+    ).map_err(|e| e.to_string())?;
+    
+    let entry = fetch_vocabulary_entry(&tx, word_id)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("🔁 Reviewed {} ({}), next in {} days", entry.word_ru, grade.as_str(), next.interval_days);
+    Ok(entry)
+}
+
+// Flashcard queue: overdue words first (oldest due date first), then words
+// that have never been reviewed
+#[tauri::command]
+pub fn get_due_vocabulary(
+    db: State<DbConnection>,
+    limit: i32,
+) -> Result<Vec<VocabularyEntry>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM RussianVocabulary v
+         LEFT JOIN Materials m ON v.material_id = m.id
+         WHERE v.due_at IS NULL OR v.due_at <= ?1
+         ORDER BY v.due_at IS NULL, v.due_at ASC, v.first_seen ASC
+         LIMIT ?2",
+        VOCABULARY_COLUMNS
+    )).map_err(|e| e.to_string())?;
+    
+    let entries = stmt.query_map(params![format_sqlite_time(&now_utc()), limit], vocabulary_from_row)
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+    
+    Ok(entries)
+}
+
+// Replace a word's grammar metadata (part of speech, gender, aspect pair,
+// government, irregular forms, stress)
+#[tauri::command]
+pub fn update_vocabulary_grammar(
+    db: State<DbConnection>,
+    word_id: i64,
+    grammar: VocabularyGrammar,
+) -> Result<VocabularyEntry, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    grammar::save_grammar(&tx, word_id, &grammar)?;
+    let entry = fetch_vocabulary_entry(&tx, word_id)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("📝 Updated grammar for {}", entry.word_ru);
+    Ok(entry)
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VocabularyFilter {
+    pub material_name: Option<String>,
+    pub part_of_speech: Option<PartOfSpeech>,
+    pub gender: Option<Gender>,
+    pub aspect: Option<Aspect>,
+    pub animate: Option<bool>,
+    // Words governing this case, optionally through this preposition
+    pub governs_case: Option<GrammaticalCase>,
+    pub preposition: Option<String>,
+    pub has_irregular_forms: Option<bool>,
+}
+
+// Vocabulary matching every given grammar filter, e.g. all perfective verbs
+// in a material
+#[tauri::command]
+pub fn query_vocabulary(
+    db: State<DbConnection>,
+    filter: VocabularyFilter,
+) -> Result<Vec<VocabularyEntry>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM RussianVocabulary v
+         LEFT JOIN Materials m ON v.material_id = m.id
+         WHERE (?1 IS NULL OR m.name_en = ?1)
+           AND (?2 IS NULL OR v.part_of_speech = ?2)
+           AND (?3 IS NULL OR v.gender = ?3)
+           AND (?4 IS NULL OR v.aspect = ?4)
+           AND (?5 IS NULL OR v.animate = ?5)
+           AND (?6 IS NULL OR EXISTS(
+                SELECT 1 FROM VocabularyGovernment g
+                WHERE g.vocabulary_id = v.id AND g.grammatical_case = ?6
+                  AND (?7 IS NULL OR g.preposition = ?7)))
+           AND (?8 IS NULL OR EXISTS(
+                SELECT 1 FROM VocabularyIrregularForms i WHERE i.vocabulary_id = v.id) = ?8)
+         ORDER BY v.word_ru",
+        VOCABULARY_COLUMNS
+    )).map_err(|e| e.to_string())?;
+    
+    let entries = stmt.query_map(
+        params![
+            filter.material_name,
+            filter.part_of_speech.map(|p| p.as_str()),
+            filter.gender.map(|g| g.as_str()),
+            filter.aspect.map(|a| a.as_str()),
+            filter.animate,
+            filter.governs_case.map(|c| c.as_str()),
+            filter.preposition,
+            filter.has_irregular_forms,
+        ],
+        vocabulary_from_row,
+    )
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+    
+    Ok(entries)
+}
+
+// Start a drill of grammatical forms (cases, conjugations, past tense,
+// imperatives) for a material's vocabulary; all kinds when `kinds` is empty
+#[tauri::command]
+pub fn generate_form_drill(
+    db: State<DbConnection>,
+    material_name: String,
+    count: Option<u32>,
+    kinds: Vec<FormDrillKind>,
+) -> Result<FormDrill, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let drill = form_drill::generate(&tx, &material_name, count.unwrap_or(form_drill::DEFAULT_COUNT), &kinds)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    println!("📝 Started form drill #{} with {} exercises", drill.attempt_number, drill.exercises.len());
+    Ok(drill)
+}
+
+// Grade a free-text answer to one form drill exercise; results are recorded
+// on the drill attempt
+#[tauri::command]
+pub fn check_form_answer(
+    db: State<DbConnection>,
+    exercise_id: i64,
+    answer: String,
+) -> Result<FormAnswerResult, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let result = form_drill::check(&tx, exercise_id, &answer)?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn get_form_drill(
+    db: State<DbConnection>,
+    drill_id: i64,
+) -> Result<FormDrill, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    form_drill::load_drill(&conn, drill_id)
+}
+
+// Convert text between Cyrillic and Latin, or retype it on the other
+// keyboard layout, for input helpers
+#[tauri::command]
+pub fn convert_script(
+    text: String,
+    target: Script,
+    scheme: Option<TransliterationScheme>,
+) -> Result<String, String> {
+    Ok(transliteration::convert(&text, target, scheme.unwrap_or(TransliterationScheme::Informal)))
+}
+
+// Define a required Russian term for a material; re-adding an existing term
+// updates its translation and notes
+#[tauri::command]
+pub fn add_material_term(
+    db: State<DbConnection>,
+    material_name: String,
+    term_ru: String,
+    translation_en: Option<String>,
+    notes: Option<String>,
+) -> Result<MaterialTerm, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    let term_id = glossary::add_term(&conn, &material_name, &term_ru, translation_en.as_deref(), notes.as_deref())?;
+    
+    println!("📚 Added term {} to {}", term_ru.trim(), material_name);
+    
+    glossary::list_terms(&conn, &material_name)?
+        .into_iter()
+        .find(|t| t.id == term_id)
+        .ok_or_else(|| format!("Term {} not found", term_id))
+}
+
+#[tauri::command]
+pub fn remove_material_term(
+    db: State<DbConnection>,
+    term_id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    glossary::remove_term(&conn, term_id)
+}
+
+// The material's glossary with how often each term appeared in its drills
+#[tauri::command]
+pub fn get_material_terms(
+    db: State<DbConnection>,
+    material_name: String,
+) -> Result<Vec<MaterialTerm>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    glossary::list_terms(&conn, &material_name)
+}
+
+// Whether a material is ready for Phase 4 (Russian) drilling: enough of its
+// problems solved in English and enough of its terms already seen in drills
+#[tauri::command]
+pub fn get_material_russian_readiness(
+    db: State<DbConnection>,
+    material_name: String,
+) -> Result<MaterialReadiness, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    
+    glossary::readiness(&conn, &material_name)
+}
@@ -0,0 +1,140 @@
+use tauri::State;
+use rusqlite::params;
+use crate::db::{DbConnection, settings};
+use crate::scheduling::{self, fsrs, SchedulerSettings};
+use crate::utils::time::{days_between, format_sqlite_time, now_utc, parse_sqlite_time};
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DueProblem {
+    pub problem_id: i64,
+    pub generated_id: String,
+    pub title: String,
+    pub material_name: String,
+    pub subject_name: Option<String>,
+    pub is_solved: bool,
+    pub algorithm: String,
+    pub stability: f64,
+    pub difficulty: Option<f64>,
+    pub interval_days: f64,
+    pub lapses: i32,
+    pub last_reviewed_at: String,
+    pub due_at: String,
+    pub overdue_days: f64,
+    pub retrievability: f64,
+}
+
+#[tauri::command]
+pub fn get_scheduler_settings(
+    db: State<DbConnection>,
+) -> Result<SchedulerSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    scheduling::load_settings(&conn)
+}
+
+// Saving settings reschedules every problem so the queue reflects the
+// chosen algorithm immediately
+#[tauri::command]
+pub fn update_scheduler_settings(
+    db: State<DbConnection>,
+    settings: SchedulerSettings,
+) -> Result<usize, String> {
+    if !(0.5..=0.99).contains(&settings.desired_retention) {
+        return Err("Desired retention must be between 0.5 and 0.99".to_string());
+    }
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    settings::save(&tx, scheduling::SETTINGS_KEY, &settings)?;
+    let count = scheduling::reschedule_all(&tx)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("🗓️ Scheduler set to {}, rescheduled {} problems", settings.algorithm.as_str(), count);
+    Ok(count)
+}
+
+#[tauri::command]
+pub fn recompute_schedules(
+    db: State<DbConnection>,
+) -> Result<usize, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let count = scheduling::reschedule_all(&tx)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+// Review queue: every problem whose due date has passed, solved or not,
+// weakest memory (lowest estimated recall) first
+#[tauri::command]
+pub fn get_due_problems(
+    db: State<DbConnection>,
+    limit: i32,
+) -> Result<Vec<DueProblem>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let now = now_utc();
+
+    let mut stmt = conn.prepare(
+        "SELECT p.id, p.generated_id, p.title, m.name_en,
+                (SELECT s.name FROM SubjectMaterials sm
+                 JOIN Subjects s ON sm.subject_id = s.id
+                 WHERE sm.material_id = m.id
+                 ORDER BY s.id LIMIT 1),
+                p.is_solved, ps.algorithm, ps.stability, ps.difficulty,
+                ps.interval_days, ps.lapses, ps.last_reviewed_at, ps.due_at
+         FROM ProblemSchedules ps
+         JOIN Problems p ON ps.problem_id = p.id
+         JOIN Materials m ON p.material_id = m.id
+         WHERE ps.due_at <= ?1"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![format_sqlite_time(&now)], |row| {
+        Ok(DueProblem {
+            problem_id: row.get(0)?,
+            generated_id: row.get(1)?,
+            title: row.get(2)?,
+            material_name: row.get(3)?,
+            subject_name: row.get(4)?,
+            is_solved: row.get(5)?,
+            algorithm: row.get(6)?,
+            stability: row.get(7)?,
+            difficulty: row.get(8)?,
+            interval_days: row.get(9)?,
+            lapses: row.get(10)?,
+            last_reviewed_at: row.get(11)?,
+            due_at: row.get(12)?,
+            overdue_days: 0.0,
+            retrievability: 0.0,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut due = Vec::with_capacity(rows.len());
+    for mut problem in rows {
+        let last_reviewed = parse_sqlite_time(&problem.last_reviewed_at)?;
+        let due_at = parse_sqlite_time(&problem.due_at)?;
+
+        problem.overdue_days = days_between(&due_at, &now);
+        problem.retrievability = fsrs::retrievability(
+            problem.stability,
+            days_between(&last_reviewed, &now),
+        );
+        due.push(problem);
+    }
+
+    due.sort_by(|a, b| {
+        a.retrievability
+            .partial_cmp(&b.retrievability)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.due_at.cmp(&b.due_at))
+    });
+    due.truncate(limit.max(0) as usize);
+
+    Ok(due)
+}
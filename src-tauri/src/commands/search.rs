@@ -0,0 +1,22 @@
+use tauri::State;
+use crate::db::DbConnection;
+use crate::search::{self, SearchResults, SearchScope, DEFAULT_LIMIT};
+
+// Ranked full-text search; an empty `scopes` list searches everything
+#[tauri::command]
+pub fn global_search(
+    db: State<DbConnection>,
+    query: String,
+    scopes: Vec<SearchScope>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<SearchResults, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    search::global_search(
+        &conn,
+        &query,
+        &scopes,
+        limit.unwrap_or(DEFAULT_LIMIT),
+        offset.unwrap_or(0),
+    )
+}
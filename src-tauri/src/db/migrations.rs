@@ -0,0 +1,220 @@
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use super::schema;
+
+pub type Backfill = fn(&Connection) -> Result<(), String>;
+
+// Forward-only schema change. Versions must be strictly increasing and an
+// entry must never be edited after it has shipped.
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub sql: &'static str,
+    // Data that has to be computed in Rust. Backfills run after every pending
+    // `sql` step (they use today's code, which expects the latest schema) but
+    // still inside the upgrade transaction.
+    pub backfill: Option<Backfill>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: schema::INIT_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 2,
+        description: "settings and problem review schedules",
+        sql: schema::SCHEDULING_SQL,
+        backfill: Some(backfill_problem_schedules),
+    },
+    Migration {
+        version: 3,
+        description: "vocabulary review scheduling",
+        sql: schema::VOCABULARY_SRS_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 4,
+        description: "mastery policies",
+        sql: schema::MASTERY_POLICY_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 5,
+        description: "full-text search indexes",
+        sql: schema::FULL_TEXT_SEARCH_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 6,
+        description: "vocabulary lemmas and grouped forms",
+        sql: schema::VOCABULARY_LEMMA_SQL,
+        backfill: Some(backfill_vocabulary_lemmas),
+    },
+    Migration {
+        version: 7,
+        description: "vocabulary grammar metadata",
+        sql: schema::VOCABULARY_GRAMMAR_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 8,
+        description: "grammatical form drills",
+        sql: schema::FORM_DRILL_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 9,
+        description: "drill rubric scores",
+        sql: schema::DRILL_RUBRIC_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 10,
+        description: "material term glossaries",
+        sql: schema::MATERIAL_TERMS_SQL,
+        backfill: None,
+    },
+    Migration {
+        version: 11,
+        description: "error categories and attempt classification",
+        sql: schema::ERROR_CATEGORIES_SQL,
+        backfill: Some(backfill_error_categories),
+    },
+];
+
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> Result<i32, String> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+// Bring the database up to `latest_version()`. The version is tracked in
+// `PRAGMA user_version`; databases created before migrations existed report 0
+// and simply re-run the (idempotent) initial schema.
+pub fn run_migrations(conn: &mut Connection, db_path: &Path) -> Result<(), String> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(format!(
+            "Database schema version {} is newer than this app supports (version {}). Please update the app.",
+            current, latest
+        ));
+    }
+
+    if current == latest {
+        println!("✅ Database schema up to date (v{})", current);
+        return Ok(());
+    }
+
+    // Only existing databases need a safety copy
+    if has_user_tables(conn)? {
+        let backup_path = backup_before_upgrade(conn, db_path, current)?;
+        println!("💾 Backed up database to {:?}", backup_path);
+    }
+
+    // Table rebuilds (e.g. widening a CHECK constraint) need foreign keys off,
+    // and the pragma is a no-op inside a transaction
+    conn.execute_batch("PRAGMA foreign_keys = OFF")
+        .map_err(|e| e.to_string())?;
+
+    let result = apply_pending(conn, current, latest);
+
+    conn.execute_batch("PRAGMA foreign_keys = ON")
+        .map_err(|e| e.to_string())?;
+
+    result
+}
+
+fn apply_pending(conn: &mut Connection, current: i32, latest: i32) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter()
+        .filter(|m| m.version > current)
+        .collect();
+
+    for migration in &pending {
+        tx.execute_batch(migration.sql).map_err(|e| {
+            format!("Migration v{} ({}) failed: {}", migration.version, migration.description, e)
+        })?;
+        println!("🔧 Applied migration v{}: {}", migration.version, migration.description);
+    }
+
+    for migration in &pending {
+        if let Some(backfill) = migration.backfill {
+            backfill(&tx).map_err(|e| {
+                format!("Backfill for v{} ({}) failed: {}", migration.version, migration.description, e)
+            })?;
+        }
+    }
+
+    // Don't commit a schema that leaves dangling references behind
+    let violations: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM pragma_foreign_key_check",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    if violations > 0 {
+        return Err(format!(
+            "Migration to v{} aborted: {} foreign key violations",
+            latest, violations
+        ));
+    }
+
+    tx.pragma_update(None, "user_version", latest)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    println!("✅ Database schema migrated from v{} to v{}", current, latest);
+    Ok(())
+}
+
+fn backfill_problem_schedules(conn: &Connection) -> Result<(), String> {
+    crate::scheduling::reschedule_all(conn).map(|_| ())
+}
+
+fn backfill_vocabulary_lemmas(conn: &Connection) -> Result<(), String> {
+    crate::russian::morphology::backfill_lemmas(conn).map(|_| ())
+}
+
+fn backfill_error_categories(conn: &Connection) -> Result<(), String> {
+    crate::classification::seed_defaults(conn)?;
+    crate::classification::reclassify_all(conn).map(|_| ())
+}
+
+fn has_user_tables(conn: &Connection) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%')",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())
+}
+
+// Writes a consistent copy next to the database, e.g. `mastery.db.v1-20250101-120000.bak`
+fn backup_before_upgrade(conn: &Connection, db_path: &Path, version: i32) -> Result<PathBuf, String> {
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mastery.db".to_string());
+
+    let backup_path = db_path.with_file_name(format!(
+        "{}.v{}-{}.bak",
+        file_name,
+        version,
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    conn.execute(
+        "VACUUM INTO ?1",
+        [backup_path.to_string_lossy().to_string()],
+    ).map_err(|e| format!("Failed to back up database before migrating: {}", e))?;
+
+    Ok(backup_path)
+}
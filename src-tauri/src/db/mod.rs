@@ -1,29 +1,48 @@
 use rusqlite::Connection;
+use std::path::PathBuf;
 use std::sync::Mutex;
 
 pub mod schema;
 pub mod models;
+pub mod migrations;
+pub mod settings;
 
 pub struct DbConnection(pub Mutex<Connection>);
 
-pub fn init_database(app_handle: &tauri::AppHandle) -> Result<DbConnection, String> {
-    let app_dir = app_handle.path_resolver()
+pub const DB_FILE_NAME: &str = "mastery.db";
+pub const IMAGES_DIR_NAME: &str = "problem_images";
+
+pub fn app_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle.path_resolver()
         .app_data_dir()
-        .ok_or("Failed to get app data dir")?;
+        .ok_or_else(|| "Failed to get app data dir".to_string())
+}
+
+pub fn init_database(app_handle: &tauri::AppHandle) -> Result<DbConnection, String> {
+    let app_dir = app_data_dir(app_handle)?;
     
     std::fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
     
-    let db_path = app_dir.join("mastery.db");
+    let db_path = app_dir.join(DB_FILE_NAME);
     println!("📂 Database path: {:?}", db_path);
     
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let mut conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
     
     // Enable foreign keys
     conn.execute("PRAGMA foreign_keys = ON", [])
         .map_err(|e| e.to_string())?;
     
-    // Initialize schema
-    schema::initialize_database(&conn).map_err(|e| e.to_string())?;
+    // Create or upgrade the schema
+    migrations::run_migrations(&mut conn, &db_path)?;
     
     Ok(DbConnection(Mutex::new(conn)))
 }
+
+// Fresh, fully migrated database for unit tests
+#[cfg(test)]
+pub fn open_in_memory() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+    migrations::run_migrations(&mut conn, std::path::Path::new(":memory:")).unwrap();
+    conn
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::recurrence::RecurringMistakeWarning;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AttemptView {
@@ -13,6 +14,8 @@ pub struct AttemptView {
     pub resolution: Option<String>,
     pub commentary: Option<String>,
     pub timestamp: String,
+    // Names of its error categories, by rule or by hand
+    pub error_categories: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -48,4 +51,8 @@ pub struct LogAttemptResponse {
     pub batch_number: i32,
     pub attempt_number: i32,
     pub batch_closed: bool,
+    // Error categories the attempt was tagged with
+    pub error_categories: Vec<String>,
+    // Set when the error text matches earlier mistakes
+    pub recurring_mistake: Option<RecurringMistakeWarning>,
 }
@@ -1,3 +1,5 @@
+// Version 1 of the schema. This must never change once shipped - schema
+// changes go into a new entry in `migrations::MIGRATIONS` instead.
 pub const INIT_SQL: &str = r#"
 -- Subjects & Materials
 CREATE TABLE IF NOT EXISTS Subjects (
@@ -133,8 +135,356 @@ CREATE INDEX IF NOT EXISTS idx_vocab_material ON RussianVocabulary(material_id);
 CREATE INDEX IF NOT EXISTS idx_vocab_reviewed ON RussianVocabulary(last_reviewed);
 "#;
 
-pub fn initialize_database(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
-    conn.execute_batch(INIT_SQL)?;
-    println!("✅ Database schema initialized");
-    Ok(())
-}
+// Version 2: key/value settings and per-problem spaced-repetition state
+pub const SCHEDULING_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS Settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS ProblemSchedules (
+    problem_id INTEGER PRIMARY KEY,
+    algorithm TEXT NOT NULL CHECK(algorithm IN ('sm2', 'fsrs')),
+    stability REAL NOT NULL,
+    difficulty REAL,
+    ease REAL NOT NULL,
+    interval_days REAL NOT NULL,
+    repetitions INTEGER NOT NULL DEFAULT 0,
+    lapses INTEGER NOT NULL DEFAULT 0,
+    last_reviewed_at TEXT NOT NULL,
+    due_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (problem_id) REFERENCES Problems(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_schedules_due ON ProblemSchedules(due_at);
+"#;
+
+// Version 3: per-word spaced-repetition state and review log
+pub const VOCABULARY_SRS_SQL: &str = r#"
+ALTER TABLE RussianVocabulary ADD COLUMN ease REAL NOT NULL DEFAULT 2.5;
+ALTER TABLE RussianVocabulary ADD COLUMN interval_days REAL NOT NULL DEFAULT 0;
+ALTER TABLE RussianVocabulary ADD COLUMN repetitions INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE RussianVocabulary ADD COLUMN lapses INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE RussianVocabulary ADD COLUMN due_at TEXT;
+
+CREATE TABLE IF NOT EXISTS VocabularyReviews (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    vocabulary_id INTEGER NOT NULL,
+    grade TEXT NOT NULL CHECK(grade IN ('again', 'hard', 'good', 'easy')),
+    interval_days REAL NOT NULL,
+    ease REAL NOT NULL,
+    reviewed_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (vocabulary_id) REFERENCES RussianVocabulary(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_vocab_due ON RussianVocabulary(due_at);
+CREATE INDEX IF NOT EXISTS idx_vocab_reviews_word ON VocabularyReviews(vocabulary_id);
+"#;
+
+// Version 4: configurable mastery rules, per subject with a global default
+pub const MASTERY_POLICY_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS MasteryPolicies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    subject_id INTEGER,
+    required_successes INTEGER NOT NULL DEFAULT 5 CHECK(required_successes >= 1),
+    min_distinct_batches INTEGER NOT NULL DEFAULT 1 CHECK(min_distinct_batches >= 1),
+    min_distinct_days INTEGER NOT NULL DEFAULT 1 CHECK(min_distinct_days >= 1),
+    max_time_minutes REAL,
+    max_difficulty INTEGER CHECK(max_difficulty BETWEEN 1 AND 5),
+    require_fresh_start_success BOOLEAN NOT NULL DEFAULT FALSE,
+    reset_on_failure BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (subject_id) REFERENCES Subjects(id) ON DELETE CASCADE
+);
+
+-- One policy per subject, and a single global default (subject_id NULL)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_mastery_policy_subject ON MasteryPolicies(COALESCE(subject_id, 0));
+
+-- The default reproduces the original rule: last 5 attempts all successful
+INSERT OR IGNORE INTO MasteryPolicies (subject_id) VALUES (NULL);
+"#;
+
+// Version 5: full-text indexes over problems, attempts, drills and vocabulary,
+// kept in sync by triggers. unicode61 case-folds Cyrillic and drops combining
+// stress marks but keeps ё distinct, so the indexes are standalone tables
+// holding text with ё folded to е, and queries are folded the same way.
+// Snippets are mapped back to the original text when searching.
+pub const FULL_TEXT_SEARCH_SQL: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS ProblemsSearch USING fts5(
+    title, description,
+    tokenize='unicode61 remove_diacritics 2', prefix='2 3'
+);
+
+CREATE TRIGGER IF NOT EXISTS problems_search_insert AFTER INSERT ON Problems BEGIN
+    INSERT INTO ProblemsSearch(rowid, title, description)
+    VALUES (new.id,
+            replace(replace(new.title, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.description, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS problems_search_delete AFTER DELETE ON Problems BEGIN
+    DELETE FROM ProblemsSearch WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS problems_search_update AFTER UPDATE OF title, description ON Problems BEGIN
+    DELETE FROM ProblemsSearch WHERE rowid = old.id;
+    INSERT INTO ProblemsSearch(rowid, title, description)
+    VALUES (new.id,
+            replace(replace(new.title, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.description, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+INSERT INTO ProblemsSearch(rowid, title, description)
+SELECT id, replace(replace(title, 'ё', 'е'), 'Ё', 'Е'), replace(replace(description, 'ё', 'е'), 'Ё', 'Е')
+FROM Problems;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS AttemptsSearch USING fts5(
+    errors, resolution, commentary,
+    tokenize='unicode61 remove_diacritics 2', prefix='2 3'
+);
+
+CREATE TRIGGER IF NOT EXISTS attempts_search_insert AFTER INSERT ON Attempts BEGIN
+    INSERT INTO AttemptsSearch(rowid, errors, resolution, commentary)
+    VALUES (new.id,
+            replace(replace(new.errors, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.resolution, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.commentary, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS attempts_search_delete AFTER DELETE ON Attempts BEGIN
+    DELETE FROM AttemptsSearch WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS attempts_search_update AFTER UPDATE OF errors, resolution, commentary ON Attempts BEGIN
+    DELETE FROM AttemptsSearch WHERE rowid = old.id;
+    INSERT INTO AttemptsSearch(rowid, errors, resolution, commentary)
+    VALUES (new.id,
+            replace(replace(new.errors, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.resolution, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.commentary, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+INSERT INTO AttemptsSearch(rowid, errors, resolution, commentary)
+SELECT id, replace(replace(errors, 'ё', 'е'), 'Ё', 'Е'), replace(replace(resolution, 'ё', 'е'), 'Ё', 'Е'), replace(replace(commentary, 'ё', 'е'), 'Ё', 'Е')
+FROM Attempts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS DrillsSearch USING fts5(
+    errors_ru, resolution_ru, commentary,
+    tokenize='unicode61 remove_diacritics 2', prefix='2 3'
+);
+
+CREATE TRIGGER IF NOT EXISTS drills_search_insert AFTER INSERT ON RussianDrillAttempts BEGIN
+    INSERT INTO DrillsSearch(rowid, errors_ru, resolution_ru, commentary)
+    VALUES (new.id,
+            replace(replace(new.errors_ru, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.resolution_ru, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.commentary, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS drills_search_delete AFTER DELETE ON RussianDrillAttempts BEGIN
+    DELETE FROM DrillsSearch WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS drills_search_update AFTER UPDATE OF errors_ru, resolution_ru, commentary ON RussianDrillAttempts BEGIN
+    DELETE FROM DrillsSearch WHERE rowid = old.id;
+    INSERT INTO DrillsSearch(rowid, errors_ru, resolution_ru, commentary)
+    VALUES (new.id,
+            replace(replace(new.errors_ru, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.resolution_ru, 'ё', 'е'), 'Ё', 'Е'),
+            replace(replace(new.commentary, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+INSERT INTO DrillsSearch(rowid, errors_ru, resolution_ru, commentary)
+SELECT id, replace(replace(errors_ru, 'ё', 'е'), 'Ё', 'Е'), replace(replace(resolution_ru, 'ё', 'е'), 'Ё', 'Е'), replace(replace(commentary, 'ё', 'е'), 'Ё', 'Е')
+FROM RussianDrillAttempts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS VocabularySearch USING fts5(
+    word_ru, translation_en, example_sentence,
+    tokenize='unicode61 remove_diacritics 2', prefix='2 3'
+);
+
+CREATE TRIGGER IF NOT EXISTS vocabulary_search_insert AFTER INSERT ON RussianVocabulary BEGIN
+    INSERT INTO VocabularySearch(rowid, word_ru, translation_en, example_sentence)
+    VALUES (new.id,
+            replace(replace(new.word_ru, 'ё', 'е'), 'Ё', 'Е'),
+            new.translation_en,
+            replace(replace(new.example_sentence, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS vocabulary_search_delete AFTER DELETE ON RussianVocabulary BEGIN
+    DELETE FROM VocabularySearch WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS vocabulary_search_update AFTER UPDATE OF word_ru, translation_en, example_sentence ON RussianVocabulary BEGIN
+    DELETE FROM VocabularySearch WHERE rowid = old.id;
+    INSERT INTO VocabularySearch(rowid, word_ru, translation_en, example_sentence)
+    VALUES (new.id,
+            replace(replace(new.word_ru, 'ё', 'е'), 'Ё', 'Е'),
+            new.translation_en,
+            replace(replace(new.example_sentence, 'ё', 'е'), 'Ё', 'Е'));
+END;
+
+INSERT INTO VocabularySearch(rowid, word_ru, translation_en, example_sentence)
+SELECT id, replace(replace(word_ru, 'ё', 'е'), 'Ё', 'Е'), translation_en, replace(replace(example_sentence, 'ё', 'е'), 'Ё', 'Е')
+FROM RussianVocabulary;
+"#;
+
+// Version 6: stem-based lemma per word, and inflected forms grouped under an
+// existing entry instead of being added as separate words
+pub const VOCABULARY_LEMMA_SQL: &str = r#"
+ALTER TABLE RussianVocabulary ADD COLUMN lemma TEXT;
+
+CREATE TABLE IF NOT EXISTS VocabularyForms (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    vocabulary_id INTEGER NOT NULL,
+    form TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (vocabulary_id) REFERENCES RussianVocabulary(id) ON DELETE CASCADE,
+    UNIQUE(vocabulary_id, form)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vocab_lemma ON RussianVocabulary(lemma);
+CREATE INDEX IF NOT EXISTS idx_vocab_forms_form ON VocabularyForms(form);
+"#;
+
+// Version 7: grammar metadata for vocabulary entries
+pub const VOCABULARY_GRAMMAR_SQL: &str = r#"
+ALTER TABLE RussianVocabulary ADD COLUMN part_of_speech TEXT CHECK(part_of_speech IN (
+    'noun', 'verb', 'adjective', 'adverb', 'pronoun', 'numeral', 'preposition',
+    'conjunction', 'particle', 'interjection', 'phrase'));
+ALTER TABLE RussianVocabulary ADD COLUMN gender TEXT CHECK(gender IN ('masculine', 'feminine', 'neuter', 'common'));
+ALTER TABLE RussianVocabulary ADD COLUMN animate BOOLEAN;
+ALTER TABLE RussianVocabulary ADD COLUMN aspect TEXT CHECK(aspect IN ('imperfective', 'perfective', 'biaspectual'));
+ALTER TABLE RussianVocabulary ADD COLUMN aspect_partner TEXT;
+ALTER TABLE RussianVocabulary ADD COLUMN stress_position INTEGER CHECK(stress_position >= 1);
+
+CREATE TABLE IF NOT EXISTS VocabularyGovernment (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    vocabulary_id INTEGER NOT NULL,
+    preposition TEXT,
+    grammatical_case TEXT NOT NULL CHECK(grammatical_case IN (
+        'nominative', 'genitive', 'dative', 'accusative', 'instrumental', 'prepositional')),
+    note TEXT,
+    FOREIGN KEY (vocabulary_id) REFERENCES RussianVocabulary(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS VocabularyIrregularForms (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    vocabulary_id INTEGER NOT NULL,
+    form_key TEXT NOT NULL,
+    form TEXT NOT NULL,
+    FOREIGN KEY (vocabulary_id) REFERENCES RussianVocabulary(id) ON DELETE CASCADE,
+    UNIQUE(vocabulary_id, form_key)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vocab_government_unique
+    ON VocabularyGovernment(vocabulary_id, COALESCE(preposition, ''), grammatical_case);
+CREATE INDEX IF NOT EXISTS idx_vocab_pos ON RussianVocabulary(part_of_speech);
+CREATE INDEX IF NOT EXISTS idx_vocab_aspect ON RussianVocabulary(aspect);
+"#;
+
+// Version 8: exercises of grammatical form drills, recorded on a drill attempt
+pub const FORM_DRILL_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS FormDrillExercises (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    drill_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    vocabulary_id INTEGER,
+    -- Snapshot of the word, so history survives the entry being deleted
+    word_ru TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK(kind IN ('declension', 'conjugation', 'past', 'imperative')),
+    form_key TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    -- JSON array of accepted answers, the preferred one first
+    accepted TEXT NOT NULL,
+    answer TEXT,
+    correct BOOLEAN,
+    answered_at TEXT,
+    FOREIGN KEY (drill_id) REFERENCES RussianDrillAttempts(id) ON DELETE CASCADE,
+    FOREIGN KEY (vocabulary_id) REFERENCES RussianVocabulary(id) ON DELETE SET NULL,
+    UNIQUE(drill_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_form_exercises_vocab ON FormDrillExercises(vocabulary_id);
+"#;
+
+// Version 9: rubric scores of Russian drills, with terminology coverage
+pub const DRILL_RUBRIC_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS DrillRubricScores (
+    drill_id INTEGER PRIMARY KEY,
+    fluency INTEGER NOT NULL CHECK(fluency BETWEEN 1 AND 5),
+    accuracy INTEGER NOT NULL CHECK(accuracy BETWEEN 1 AND 5),
+    completeness INTEGER NOT NULL CHECK(completeness BETWEEN 1 AND 5),
+    -- Share of the material's vocabulary and glossary terms used in
+    -- resolution_ru; NULL when the material has neither
+    terminology_coverage REAL CHECK(terminology_coverage BETWEEN 0 AND 1),
+    -- JSON arrays of the words used and missed
+    terms_used TEXT NOT NULL DEFAULT '[]',
+    terms_missing TEXT NOT NULL DEFAULT '[]',
+    overall REAL NOT NULL,
+    scored_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (drill_id) REFERENCES RussianDrillAttempts(id) ON DELETE CASCADE
+);
+"#;
+
+// Version 10: required Russian terms per material
+pub const MATERIAL_TERMS_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS MaterialTerms (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    material_id INTEGER NOT NULL,
+    term_ru TEXT NOT NULL,
+    translation_en TEXT,
+    notes TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (material_id) REFERENCES Materials(id) ON DELETE CASCADE,
+    UNIQUE(material_id, term_ru)
+);
+
+CREATE INDEX IF NOT EXISTS idx_material_terms_material ON MaterialTerms(material_id);
+"#;
+
+// Version 11: error categories, their matching rules and attempt tags
+pub const ERROR_CATEGORIES_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS ErrorCategories (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT UNIQUE NOT NULL,
+    parent_id INTEGER,
+    description TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (parent_id) REFERENCES ErrorCategories(id) ON DELETE SET NULL
+);
+
+-- Keyword or regex matched against Attempts.errors
+CREATE TABLE IF NOT EXISTS ErrorCategoryRules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    category_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK(kind IN ('keyword', 'regex')),
+    pattern TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (category_id) REFERENCES ErrorCategories(id) ON DELETE CASCADE,
+    UNIQUE(category_id, kind, pattern)
+);
+
+-- 'rule' tags are recomputed whenever the attempt changes; 'manual' tags
+-- replace the rules for attempts in AttemptErrorOverrides
+CREATE TABLE IF NOT EXISTS AttemptErrorCategories (
+    attempt_id INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    source TEXT NOT NULL DEFAULT 'rule' CHECK(source IN ('rule', 'manual')),
+    PRIMARY KEY (attempt_id, category_id),
+    FOREIGN KEY (attempt_id) REFERENCES Attempts(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES ErrorCategories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_attempt_error_categories_category ON AttemptErrorCategories(category_id);
+
+-- Attempts whose categories were set by hand; the rules leave them alone,
+-- even when they were set to no categories at all
+CREATE TABLE IF NOT EXISTS AttemptErrorOverrides (
+    attempt_id INTEGER PRIMARY KEY,
+    FOREIGN KEY (attempt_id) REFERENCES Attempts(id) ON DELETE CASCADE
+);
+"#;
@@ -0,0 +1,32 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+
+// Settings are stored as one JSON document per key. Missing keys fall back to
+// the type's `Default`, so new settings need no migration.
+pub fn load<T: DeserializeOwned + Default>(conn: &Connection, key: &str) -> Result<T, String> {
+    let value: Option<String> = conn.query_row(
+        "SELECT value FROM Settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0)
+    )
+    .optional()
+    .map_err(|e| e.to_string())?;
+
+    match value {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid '{}' settings: {}", key, e)),
+        None => Ok(T::default()),
+    }
+}
+
+pub fn save<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO Settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = datetime('now')",
+        params![key, json],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
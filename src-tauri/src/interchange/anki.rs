@@ -0,0 +1,778 @@
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+use crate::backup::with_suffix;
+use crate::db::IMAGES_DIR_NAME;
+use crate::utils::time::{now_utc, parse_sqlite_time};
+
+// An .apkg is a zip holding `collection.anki2` (an Anki schema 11 SQLite
+// collection), a `media` JSON map and the media files named "0", "1", ...
+// Note GUIDs, note type ids and deck ids are derived from our own ids, so
+// importing a re-export updates the existing cards instead of duplicating them.
+const COLLECTION_NAME: &str = "collection.anki2";
+const MEDIA_NAME: &str = "media";
+
+const VOCABULARY_MODEL_ID: i64 = 1_718_000_000_101;
+const PROBLEM_MODEL_ID: i64 = 1_718_000_000_102;
+const DEFAULT_DECK_NAME: &str = "Mastery Learning";
+
+const GUID_ALPHABET: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$%&()*+,-./:;<=>?@[]^_`{|}~";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnkiExportFilter {
+    pub include_vocabulary: bool,
+    pub include_problems: bool,
+    // Only vocabulary and problems of this material
+    pub material_name: Option<String>,
+    // Only problems of this subject
+    pub subject_name: Option<String>,
+    pub include_solved: bool,
+    // Parent deck; vocabulary and problems go into subdecks
+    pub deck_name: Option<String>,
+}
+
+impl Default for AnkiExportFilter {
+    fn default() -> Self {
+        AnkiExportFilter {
+            include_vocabulary: true,
+            include_problems: false,
+            material_name: None,
+            subject_name: None,
+            include_solved: true,
+            deck_name: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AnkiExportSummary {
+    pub vocabulary_notes: usize,
+    pub problem_notes: usize,
+    pub media_files: usize,
+    // Image files referenced by problems but not found on disk
+    pub missing_images: Vec<String>,
+}
+
+struct AnkiNote {
+    guid: String,
+    model_id: i64,
+    deck_id: i64,
+    fields: Vec<String>,
+    tags: Vec<String>,
+    // Review state carried over from our own scheduling, if any
+    schedule: Option<CardSchedule>,
+}
+
+struct CardSchedule {
+    interval_days: i64,
+    factor: i64,
+    repetitions: i64,
+    lapses: i64,
+    due_day: i64,
+}
+
+// Anki's own GUID style: a 64-bit hash in base 91
+pub fn stable_guid(key: &str) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    let mut value = u64::from_be_bytes(digest[..8].try_into().unwrap_or([0; 8]));
+
+    let mut guid = Vec::new();
+    while value > 0 {
+        guid.push(GUID_ALPHABET[(value % GUID_ALPHABET.len() as u64) as usize]);
+        value /= GUID_ALPHABET.len() as u64;
+    }
+    guid.reverse();
+    String::from_utf8(guid).unwrap_or_default()
+}
+
+fn stable_id(key: &str) -> i64 {
+    let digest = Sha256::digest(key.as_bytes());
+    // Positive and in the range Anki uses for millisecond ids
+    1_500_000_000_000 + (u64::from_be_bytes(digest[..8].try_into().unwrap_or([0; 8])) % 100_000_000_000) as i64
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+fn strip_html(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&amp;", "&")
+}
+
+// Anki tags cannot contain spaces
+fn tag(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+// Duplicate-check value Anki stores with each note: the first 8 hex digits
+// of the SHA-1 of the plain sort field
+fn field_checksum(field: &str) -> i64 {
+    let digest = Sha1::digest(strip_html(field).as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+fn vocabulary_notes(
+    conn: &Connection,
+    filter: &AnkiExportFilter,
+    deck_id: i64,
+    collection_start: i64,
+) -> Result<Vec<AnkiNote>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT v.id, v.word_ru, v.translation_en, v.example_sentence, m.name_en,
+                v.repetitions, v.lapses, v.interval_days, v.ease, v.due_at
+         FROM RussianVocabulary v
+         LEFT JOIN Materials m ON v.material_id = m.id
+         WHERE ?1 IS NULL OR m.name_en = ?1
+         ORDER BY v.first_seen, v.id"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![&filter.material_name], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, i64>(5)?,
+            row.get::<_, i64>(6)?,
+            row.get::<_, f64>(7)?,
+            row.get::<_, f64>(8)?,
+            row.get::<_, Option<String>>(9)?,
+        ))
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut notes = Vec::with_capacity(rows.len());
+    for (id, word_ru, translation_en, example, material, repetitions, lapses, interval_days, ease, due_at) in rows {
+        let schedule = match (&due_at, repetitions > 0) {
+            (Some(due_at), true) => Some(CardSchedule {
+                interval_days: (interval_days.round() as i64).max(1),
+                factor: (ease * 1000.0).round() as i64,
+                repetitions,
+                lapses,
+                due_day: (parse_sqlite_time(due_at)?.and_utc().timestamp() - collection_start).div_euclid(86_400),
+            }),
+            _ => None,
+        };
+
+        notes.push(AnkiNote {
+            guid: stable_guid(&format!("mastery-learning:vocabulary:{}", id)),
+            model_id: VOCABULARY_MODEL_ID,
+            deck_id,
+            fields: vec![
+                escape_html(&word_ru),
+                escape_html(&translation_en),
+                example.as_deref().map(escape_html).unwrap_or_default(),
+            ],
+            tags: material.as_deref().map(tag).into_iter().collect(),
+            schedule,
+        });
+    }
+    Ok(notes)
+}
+
+// Problems with their latest recorded resolution; returns the notes and the
+// image files they reference
+fn problem_notes(
+    conn: &Connection,
+    filter: &AnkiExportFilter,
+    deck_id: i64,
+) -> Result<(Vec<AnkiNote>, Vec<String>), String> {
+    let mut stmt = conn.prepare(
+        "SELECT p.generated_id, p.title, p.description, p.image_filename, p.is_solved, m.name_en,
+                (SELECT GROUP_CONCAT(s.name, '|') FROM SubjectMaterials sm
+                 JOIN Subjects s ON sm.subject_id = s.id WHERE sm.material_id = m.id),
+                (SELECT a.resolution FROM Attempts a JOIN Batches b ON a.batch_id = b.id
+                 WHERE b.problem_id = p.id AND a.resolution IS NOT NULL AND a.resolution != ''
+                 ORDER BY a.timestamp DESC, a.id DESC LIMIT 1)
+         FROM Problems p
+         JOIN Materials m ON p.material_id = m.id
+         WHERE (?1 IS NULL OR m.name_en = ?1)
+           AND (?2 IS NULL OR EXISTS (SELECT 1 FROM SubjectMaterials sm JOIN Subjects s ON sm.subject_id = s.id
+                                      WHERE sm.material_id = m.id AND s.name = ?2))
+           AND (?3 OR NOT COALESCE(p.is_solved, 0))
+         ORDER BY p.generated_id"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(
+        params![&filter.material_name, &filter.subject_name, filter.include_solved],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<bool>>(4)?.unwrap_or(false),
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<String>>(7)?,
+            ))
+        },
+    )
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut notes = Vec::with_capacity(rows.len());
+    let mut images = Vec::new();
+    for (generated_id, title, description, image_filename, is_solved, material, subjects, resolution) in rows {
+        let image_field = match &image_filename {
+            Some(filename) => {
+                images.push(filename.clone());
+                format!("<img src=\"{}\">", escape_html(filename))
+            }
+            None => String::new(),
+        };
+
+        let mut tags: Vec<String> = subjects.as_deref()
+            .map(|s| s.split('|').map(tag).collect())
+            .unwrap_or_default();
+        tags.push(tag(&material));
+        if is_solved {
+            tags.push("solved".to_string());
+        }
+
+        notes.push(AnkiNote {
+            guid: stable_guid(&format!("mastery-learning:problem:{}", generated_id)),
+            model_id: PROBLEM_MODEL_ID,
+            deck_id,
+            fields: vec![
+                escape_html(&title),
+                description.as_deref().map(escape_html).unwrap_or_default(),
+                image_field,
+                resolution.as_deref().map(escape_html).unwrap_or_default(),
+                escape_html(&generated_id),
+            ],
+            tags,
+            schedule: None,
+        });
+    }
+    Ok((notes, images))
+}
+
+fn note_type(id: i64, name: &str, fields: &[&str], front: &str, back: &str, deck_id: i64, now: i64) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": name,
+        "type": 0,
+        "mod": now,
+        "usn": -1,
+        "sortf": 0,
+        "did": deck_id,
+        "tmpls": [{
+            "name": "Card 1",
+            "ord": 0,
+            "qfmt": front,
+            "afmt": back,
+            "did": null,
+            "bqfmt": "",
+            "bafmt": "",
+        }],
+        "flds": fields.iter().enumerate().map(|(ord, field)| json!({
+            "name": field,
+            "ord": ord,
+            "sticky": false,
+            "rtl": false,
+            "font": "Arial",
+            "size": 20,
+            "media": [],
+        })).collect::<Vec<_>>(),
+        "css": ".card { font-family: arial; font-size: 20px; text-align: center; color: black; background-color: white; }",
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "tags": [],
+        "vers": [],
+        "req": [[0, "any", [0]]],
+    })
+}
+
+fn deck(id: i64, name: &str, now: i64) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": name,
+        "mod": now,
+        "usn": -1,
+        "lrnToday": [0, 0],
+        "revToday": [0, 0],
+        "newToday": [0, 0],
+        "timeToday": [0, 0],
+        "collapsed": false,
+        "desc": "",
+        "dyn": 0,
+        "conf": 1,
+        "extendNew": 10,
+        "extendRev": 50,
+    })
+}
+
+const COLLECTION_SCHEMA: &str = r#"
+CREATE TABLE col (
+    id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+    ver integer not null, dty integer not null, usn integer not null, ls integer not null,
+    conf text not null, models text not null, decks text not null, dconf text not null, tags text not null
+);
+CREATE TABLE notes (
+    id integer primary key, guid text not null, mid integer not null, mod integer not null,
+    usn integer not null, tags text not null, flds text not null, sfld integer not null,
+    csum integer not null, flags integer not null, data text not null
+);
+CREATE TABLE cards (
+    id integer primary key, nid integer not null, did integer not null, ord integer not null,
+    mod integer not null, usn integer not null, type integer not null, queue integer not null,
+    due integer not null, ivl integer not null, factor integer not null, reps integer not null,
+    lapses integer not null, left integer not null, odue integer not null, odid integer not null,
+    flags integer not null, data text not null
+);
+CREATE TABLE revlog (
+    id integer primary key, cid integer not null, usn integer not null, ease integer not null,
+    ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null,
+    type integer not null
+);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+"#;
+
+fn write_collection(
+    path: &Path,
+    notes: &[AnkiNote],
+    decks: &[(i64, String)],
+    collection_start: i64,
+) -> Result<(), String> {
+    let now = now_utc().and_utc().timestamp();
+    let now_ms = now * 1000;
+
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    conn.execute_batch(COLLECTION_SCHEMA).map_err(|e| e.to_string())?;
+
+    let parent_deck_id = decks.first().map(|(id, _)| *id).unwrap_or(1);
+    let mut models = serde_json::Map::new();
+    models.insert(VOCABULARY_MODEL_ID.to_string(), note_type(
+        VOCABULARY_MODEL_ID,
+        "Mastery Vocabulary",
+        &["Russian", "English", "Example"],
+        "{{Russian}}",
+        "{{FrontSide}}<hr id=answer>{{English}}<br><i>{{Example}}</i>",
+        parent_deck_id,
+        now,
+    ));
+    models.insert(PROBLEM_MODEL_ID.to_string(), note_type(
+        PROBLEM_MODEL_ID,
+        "Mastery Problem",
+        &["Title", "Description", "Image", "Resolution", "Id"],
+        "<b>{{Title}}</b><br>{{Description}}<br>{{Image}}",
+        "{{FrontSide}}<hr id=answer>{{Resolution}}",
+        parent_deck_id,
+        now,
+    ));
+
+    let mut deck_map = serde_json::Map::new();
+    deck_map.insert("1".to_string(), deck(1, "Default", now));
+    for (id, name) in decks {
+        deck_map.insert(id.to_string(), deck(*id, name, now));
+    }
+
+    let conf = json!({
+        "activeDecks": [1],
+        "curDeck": 1,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": null,
+        "nextPos": notes.len() + 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    });
+    let dconf = json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "mod": 0,
+            "usn": 0,
+            "maxTaken": 60,
+            "autoplay": true,
+            "timer": 0,
+            "replayq": true,
+            "dyn": false,
+            "new": {"delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "separate": true, "order": 1, "perDay": 20, "bury": true},
+            "lapse": {"delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0},
+            "rev": {"perDay": 100, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1, "ivlFct": 1, "maxIvl": 36500, "bury": true},
+        }
+    });
+
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?3, 11, 0, 0, 0, ?4, ?5, ?6, ?7, '{}')",
+        params![
+            collection_start,
+            now_ms,
+            now_ms,
+            conf.to_string(),
+            serde_json::Value::Object(models).to_string(),
+            serde_json::Value::Object(deck_map).to_string(),
+            dconf.to_string(),
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    // Note and card ids only need to be unique; matching on import uses the GUID
+    for (index, note) in notes.iter().enumerate() {
+        let note_id = now_ms + index as i64;
+        let tags = if note.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" {} ", note.tags.join(" "))
+        };
+        let sort_field = note.fields.first().map(|f| strip_html(f)).unwrap_or_default();
+
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                note_id,
+                &note.guid,
+                note.model_id,
+                now,
+                tags,
+                note.fields.join("\u{1f}"),
+                sort_field,
+                field_checksum(note.fields.first().map(String::as_str).unwrap_or_default()),
+            ],
+        ).map_err(|e| e.to_string())?;
+
+        // type/queue 0 = new (due is the position), 2 = review (due is a day number)
+        let (card_type, due, interval, factor, repetitions, lapses) = match &note.schedule {
+            Some(s) => (2, s.due_day, s.interval_days, s.factor, s.repetitions, s.lapses),
+            None => (0, index as i64 + 1, 0, 0, 0, 0),
+        };
+        conn.execute(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, ?5, ?5, ?6, ?7, ?8, ?9, ?10, 0, 0, 0, 0, '')",
+            params![note_id, note_id, note.deck_id, now, card_type, due, interval, factor, repetitions, lapses],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+pub fn export(
+    conn: &Connection,
+    app_dir: &Path,
+    dest: &Path,
+    filter: &AnkiExportFilter,
+) -> Result<AnkiExportSummary, String> {
+    if !filter.include_vocabulary && !filter.include_problems {
+        return Err("Nothing to export: include vocabulary, problems or both".to_string());
+    }
+
+    let parent = filter.deck_name.clone().unwrap_or_else(|| DEFAULT_DECK_NAME.to_string());
+    let vocabulary_deck = (stable_id(&format!("deck:{}::Vocabulary", parent)), format!("{}::Vocabulary", parent));
+    let problems_deck = (stable_id(&format!("deck:{}::Problems", parent)), format!("{}::Problems", parent));
+    let mut decks = vec![(stable_id(&format!("deck:{}", parent)), parent.clone())];
+
+    // Review days are counted from the start of the collection's first day
+    let collection_start = now_utc().date().and_hms_opt(0, 0, 0)
+        .map(|midnight| midnight.and_utc().timestamp())
+        .unwrap_or(0);
+
+    let mut summary = AnkiExportSummary::default();
+    let mut notes = Vec::new();
+    let mut images = Vec::new();
+
+    if filter.include_vocabulary {
+        let vocabulary = vocabulary_notes(conn, filter, vocabulary_deck.0, collection_start)?;
+        summary.vocabulary_notes = vocabulary.len();
+        notes.extend(vocabulary);
+        decks.push(vocabulary_deck);
+    }
+    if filter.include_problems {
+        let (problems, problem_images) = problem_notes(conn, filter, problems_deck.0)?;
+        summary.problem_notes = problems.len();
+        notes.extend(problems);
+        images = problem_images;
+        decks.push(problems_deck);
+    }
+
+    let collection_path = app_dir.join(format!("anki-export-{}.tmp", now_utc().format("%Y%m%d-%H%M%S")));
+    let _ = fs::remove_file(&collection_path);
+    let result = write_collection(&collection_path, &notes, &decks, collection_start)
+        .and_then(|_| fs::read(&collection_path).map_err(|e| e.to_string()));
+    let _ = fs::remove_file(&collection_path);
+    let collection = result?;
+
+    // Write next to the destination and rename, so a failed export never
+    // leaves a truncated package under the real name (or replaces a good one)
+    let partial_path = with_suffix(dest, ".partial");
+    let written = write_package(&partial_path, &collection, &app_dir.join(IMAGES_DIR_NAME), images, &mut summary)
+        .and_then(|_| fs::rename(&partial_path, dest).map_err(|e| e.to_string()));
+    if written.is_err() {
+        let _ = fs::remove_file(&partial_path);
+    }
+    written?;
+
+    Ok(summary)
+}
+
+fn write_package(
+    path: &Path,
+    collection: &[u8],
+    images_dir: &Path,
+    mut images: Vec<String>,
+    summary: &mut AnkiExportSummary,
+) -> Result<(), String> {
+    let mut zip = ZipWriter::new(File::create(path).map_err(|e| format!("Failed to create {:?}: {}", path, e))?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file(COLLECTION_NAME, options).map_err(|e| e.to_string())?;
+    zip.write_all(collection).map_err(|e| e.to_string())?;
+
+    let mut media = BTreeMap::new();
+    images.sort();
+    images.dedup();
+    for filename in images {
+        match fs::read(images_dir.join(&filename)) {
+            Ok(bytes) => {
+                let entry = media.len().to_string();
+                zip.start_file(entry.as_str(), options).map_err(|e| e.to_string())?;
+                zip.write_all(&bytes).map_err(|e| e.to_string())?;
+                media.insert(entry, filename);
+            }
+            Err(_) => summary.missing_images.push(filename),
+        }
+    }
+    summary.media_files = media.len();
+
+    let media_json = serde_json::to_vec(&media).map_err(|e| e.to_string())?;
+    zip.start_file(MEDIA_NAME, options).map_err(|e| e.to_string())?;
+    zip.write_all(&media_json).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// A note read from an .apkg, with the review state of its first card
+#[derive(Debug, Clone)]
+pub struct PackageNote {
+    pub note_id: i64,
+    pub field_names: Vec<String>,
+    // Plain text, HTML removed
+    pub fields: Vec<String>,
+    pub card: Option<PackageCard>,
+    pub reviews: Vec<PackageReview>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageCard {
+    // 0 = new, 1 = learning, 2 = review, 3 = relearning
+    pub card_type: i64,
+    pub interval_days: f64,
+    // Anki ease factor in permille (2500 = 2.5), 0 for new cards
+    pub factor: i64,
+    pub repetitions: i64,
+    pub lapses: i64,
+    pub due_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageReview {
+    pub reviewed_at: NaiveDateTime,
+    // Answer button: 1 = again, 2 = hard, 3 = good, 4 = easy
+    pub button: i64,
+    pub interval_days: f64,
+    pub factor: i64,
+}
+
+// Negative intervals in Anki are learning steps in seconds
+fn anki_interval_days(ivl: i64) -> f64 {
+    if ivl < 0 {
+        -ivl as f64 / 86_400.0
+    } else {
+        ivl as f64
+    }
+}
+
+fn from_epoch_seconds(seconds: i64) -> Option<NaiveDateTime> {
+    chrono::DateTime::from_timestamp(seconds, 0).map(|t| t.naive_utc())
+}
+
+pub fn html_to_text(html: &str) -> String {
+    let with_breaks = html
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</div>", "\n")
+        .replace("&nbsp;", " ");
+
+    // Drop sound references, they have no text equivalent
+    let mut text = strip_html(&with_breaks);
+    while let Some(start) = text.find("[sound:") {
+        match text[start..].find(']') {
+            Some(end) => text.replace_range(start..start + end + 1, ""),
+            None => break,
+        }
+    }
+    text.trim().to_string()
+}
+
+// Read every note of an Anki package. `work_dir` holds the extracted
+// collection while it is read.
+pub fn read_package(path: &Path, work_dir: &Path) -> Result<Vec<PackageNote>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Not a valid Anki package: {}", e))?;
+
+    if archive.by_name("collection.anki21b").is_ok() && archive.by_name("collection.anki21").is_err() {
+        return Err(
+            "This package uses the newest Anki format; export it again with \"Support older Anki versions\" enabled"
+                .to_string(),
+        );
+    }
+
+    // Packages from Anki 2.1 carry the real collection as .anki21 next to a
+    // placeholder .anki2
+    let collection_name = if archive.by_name("collection.anki21").is_ok() {
+        "collection.anki21"
+    } else {
+        COLLECTION_NAME
+    };
+    let mut bytes = Vec::new();
+    archive.by_name(collection_name)
+        .map_err(|_| "Anki package has no collection".to_string())?
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+
+    fs::create_dir_all(work_dir).map_err(|e| e.to_string())?;
+    let collection_path = work_dir.join(format!("anki-import-{}.tmp", now_utc().format("%Y%m%d-%H%M%S")));
+    fs::write(&collection_path, bytes).map_err(|e| e.to_string())?;
+
+    let result = Connection::open_with_flags(&collection_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())
+        .and_then(|conn| read_collection(&conn));
+    let _ = fs::remove_file(&collection_path);
+
+    result
+}
+
+fn read_collection(conn: &Connection) -> Result<Vec<PackageNote>, String> {
+    let (collection_start, models_json): (i64, String) = conn.query_row(
+        "SELECT crt, models FROM col",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| format!("Not a readable Anki collection: {}", e))?;
+
+    // Field names per note type
+    let models: serde_json::Value = serde_json::from_str(&models_json).map_err(|e| e.to_string())?;
+    let mut field_names: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    if let Some(models) = models.as_object() {
+        for (id, model) in models {
+            let mut fields: Vec<(i64, String)> = model["flds"].as_array()
+                .map(|flds| flds.iter().map(|f| (
+                    f["ord"].as_i64().unwrap_or(0),
+                    f["name"].as_str().unwrap_or_default().to_string(),
+                )).collect())
+                .unwrap_or_default();
+            fields.sort();
+            field_names.insert(id.parse().unwrap_or(0), fields.into_iter().map(|(_, name)| name).collect());
+        }
+    }
+
+    let notes: Vec<(i64, i64, String)> = conn.prepare("SELECT id, mid, flds FROM notes ORDER BY id")
+        .map_err(|e| e.to_string())?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut package_notes = Vec::with_capacity(notes.len());
+    for (note_id, model_id, fields) in notes {
+        // The first card (first template) carries the word's review state
+        let card: Option<(i64, i64, i64, i64, i64, i64, i64)> = conn.query_row(
+            "SELECT id, type, ivl, factor, reps, lapses, due FROM cards
+             WHERE nid = ?1 ORDER BY ord LIMIT 1",
+            params![note_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?))
+        ).ok();
+
+        let (card, reviews) = match card {
+            Some((card_id, card_type, ivl, factor, reps, lapses, due)) => {
+                let due_at = match card_type {
+                    // Review cards are due on a day counted from the collection start
+                    2 => from_epoch_seconds(collection_start + due * 86_400),
+                    // Learning cards are due at a timestamp
+                    1 | 3 => from_epoch_seconds(due),
+                    _ => None,
+                };
+
+                // Manual rescheduling entries have no answer button
+                let reviews = conn.prepare(
+                    "SELECT id, ease, ivl, factor FROM revlog WHERE cid = ?1 AND ease > 0 ORDER BY id"
+                )
+                .map_err(|e| e.to_string())?
+                .query_map(params![card_id], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?))
+                })
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter_map(|(id, button, ivl, factor)| Some(PackageReview {
+                    reviewed_at: from_epoch_seconds(id / 1000)?,
+                    button: button.clamp(1, 4),
+                    interval_days: anki_interval_days(ivl),
+                    factor,
+                }))
+                .collect();
+
+                (Some(PackageCard {
+                    card_type,
+                    interval_days: anki_interval_days(ivl),
+                    factor,
+                    repetitions: reps,
+                    lapses,
+                    due_at,
+                }), reviews)
+            }
+            None => (None, Vec::new()),
+        };
+
+        package_notes.push(PackageNote {
+            note_id,
+            field_names: field_names.get(&model_id).cloned().unwrap_or_default(),
+            fields: fields.split('\u{1f}').map(html_to_text).collect(),
+            card,
+            reviews,
+        });
+    }
+
+    Ok(package_notes)
+}
@@ -0,0 +1,433 @@
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use crate::commands::attempts::{
+    find_or_create_material, find_or_create_problem, find_or_create_subject,
+    link_resources, link_subject_material,
+};
+use crate::utils::time::format_sqlite_time;
+use crate::{classification, mastery, scheduling, segmentation};
+
+const STATUS_TAGS: &[&str] = &["stuck", "breakthrough", "review", "first_attempt", "debugging"];
+
+// Spreadsheet formats tried in order when no explicit format is given
+const TIMESTAMP_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+];
+
+// Header names of the columns holding each field. Optional fields may be
+// left unmapped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvColumnMapping {
+    pub subject: String,
+    pub material: String,
+    pub problem_title: String,
+    pub timestamp: String,
+    pub success: String,
+    pub minutes: Option<String>,
+    pub difficulty: Option<String>,
+    pub errors: Option<String>,
+    pub resolution: Option<String>,
+    pub tags: Option<String>,
+    pub resources: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvImportOptions {
+    pub mapping: CsvColumnMapping,
+    // Defaults to ','
+    pub delimiter: Option<char>,
+    // chrono format string; None tries the common spreadsheet formats
+    pub timestamp_format: Option<String>,
+    // Timezone of timestamps without an offset, in minutes east of UTC;
+    // None uses the system timezone
+    pub utc_offset_minutes: Option<i32>,
+    // Validate and report what would happen, but write nothing
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvRowError {
+    // Line in the file, counting the header as line 1
+    pub line: u64,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CsvImportSummary {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub imported: usize,
+    // Rows whose problem already has an attempt at the same time
+    pub skipped_duplicates: usize,
+    pub subjects_created: usize,
+    pub materials_created: usize,
+    pub problems_created: usize,
+    pub problems_affected: usize,
+    // Problems whose existing attempts were renumbered because imported
+    // attempts came before them
+    pub problems_renumbered: usize,
+    pub newly_solved: usize,
+    // Nothing is written while there are errors
+    pub errors: Vec<CsvRowError>,
+}
+
+struct CsvAttempt {
+    subject: String,
+    material: String,
+    problem_title: String,
+    timestamp: NaiveDateTime,
+    successful: bool,
+    minutes: Option<f64>,
+    difficulty: Option<i32>,
+    errors: Option<String>,
+    resolution: Option<String>,
+    status_tag: Option<String>,
+    resources: Vec<String>,
+}
+
+fn parse_success(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "y" | "success" | "ok" | "✓" | "✔" | "+" => Some(true),
+        "0" | "false" | "no" | "n" | "fail" | "failed" | "failure" | "✗" | "x" | "-" => Some(false),
+        _ => None,
+    }
+}
+
+fn local_to_utc(local: NaiveDateTime, utc_offset_minutes: Option<i32>) -> Option<NaiveDateTime> {
+    match utc_offset_minutes {
+        Some(minutes) => Some(local - Duration::minutes(minutes as i64)),
+        None => Local.from_local_datetime(&local).earliest().map(|t| t.naive_utc()),
+    }
+}
+
+fn parse_timestamp(value: &str, options: &CsvImportOptions) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+
+    if let Some(format) = &options.timestamp_format {
+        let local = NaiveDateTime::parse_from_str(value, format)
+            .map_err(|_| format!("'{}' does not match format '{}'", value, format))?;
+        return local_to_utc(local, options.utc_offset_minutes)
+            .ok_or_else(|| format!("'{}' does not exist in the local timezone", value));
+    }
+
+    // An explicit offset wins over the configured timezone
+    if let Ok(with_offset) = DateTime::parse_from_rfc3339(value) {
+        return Ok(with_offset.naive_utc());
+    }
+
+    for format in TIMESTAMP_FORMATS {
+        if let Ok(local) = NaiveDateTime::parse_from_str(value, format) {
+            return local_to_utc(local, options.utc_offset_minutes)
+                .ok_or_else(|| format!("'{}' does not exist in the local timezone", value));
+        }
+    }
+    Err(format!("Unrecognised timestamp '{}'", value))
+}
+
+fn optional_text(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+struct Columns {
+    subject: usize,
+    material: usize,
+    problem_title: usize,
+    timestamp: usize,
+    success: usize,
+    minutes: Option<usize>,
+    difficulty: Option<usize>,
+    errors: Option<usize>,
+    resolution: Option<usize>,
+    tags: Option<usize>,
+    resources: Option<usize>,
+}
+
+fn resolve_columns(headers: &csv::StringRecord, mapping: &CsvColumnMapping) -> Result<Columns, String> {
+    let find = |name: &str| -> Result<usize, String> {
+        headers.iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("Column '{}' not found in the CSV header", name))
+    };
+    let find_optional = |name: &Option<String>| name.as_deref().map(find).transpose();
+
+    Ok(Columns {
+        subject: find(&mapping.subject)?,
+        material: find(&mapping.material)?,
+        problem_title: find(&mapping.problem_title)?,
+        timestamp: find(&mapping.timestamp)?,
+        success: find(&mapping.success)?,
+        minutes: find_optional(&mapping.minutes)?,
+        difficulty: find_optional(&mapping.difficulty)?,
+        errors: find_optional(&mapping.errors)?,
+        resolution: find_optional(&mapping.resolution)?,
+        tags: find_optional(&mapping.tags)?,
+        resources: find_optional(&mapping.resources)?,
+    })
+}
+
+fn parse_row(
+    record: &csv::StringRecord,
+    columns: &Columns,
+    options: &CsvImportOptions,
+    line: u64,
+    errors: &mut Vec<CsvRowError>,
+) -> Option<CsvAttempt> {
+    let mapping = &options.mapping;
+    let before = errors.len();
+    let mut error = |column: &str, message: String| errors.push(CsvRowError {
+        line,
+        column: Some(column.to_string()),
+        message,
+    });
+
+    let get = |index: usize| record.get(index).unwrap_or("").trim();
+    let get_optional = |index: Option<usize>| index.map(get).filter(|v| !v.is_empty());
+
+    let required = |index: usize, column: &str, error: &mut dyn FnMut(&str, String)| {
+        let value = get(index);
+        if value.is_empty() {
+            error(column, "Value is required".to_string());
+        }
+        value.to_string()
+    };
+
+    let subject = required(columns.subject, &mapping.subject, &mut error);
+    let material = required(columns.material, &mapping.material, &mut error);
+    let problem_title = required(columns.problem_title, &mapping.problem_title, &mut error);
+
+    let timestamp = match parse_timestamp(get(columns.timestamp), options) {
+        Ok(timestamp) => Some(timestamp),
+        Err(e) => {
+            error(&mapping.timestamp, e);
+            None
+        }
+    };
+
+    let successful = parse_success(get(columns.success));
+    if successful.is_none() {
+        error(&mapping.success, format!("'{}' is not a success value (yes/no, 1/0, true/false)", get(columns.success)));
+    }
+
+    let minutes = get_optional(columns.minutes).and_then(|value| {
+        match value.replace(',', ".").parse::<f64>() {
+            Ok(minutes) if minutes >= 0.0 => Some(minutes),
+            _ => {
+                error(mapping.minutes.as_deref().unwrap_or_default(), format!("'{}' is not a number of minutes", value));
+                None
+            }
+        }
+    });
+
+    let difficulty = get_optional(columns.difficulty).and_then(|value| {
+        match value.parse::<i32>() {
+            Ok(rating) if (1..=5).contains(&rating) => Some(rating),
+            _ => {
+                error(mapping.difficulty.as_deref().unwrap_or_default(), format!("Difficulty '{}' must be 1-5", value));
+                None
+            }
+        }
+    });
+
+    let status_tag = get_optional(columns.tags).and_then(|value| {
+        let tag = value.to_lowercase().replace(' ', "_");
+        if STATUS_TAGS.contains(&tag.as_str()) {
+            Some(tag)
+        } else {
+            error(mapping.tags.as_deref().unwrap_or_default(), format!(
+                "Unknown tag '{}' (expected one of {})", value, STATUS_TAGS.join(", ")
+            ));
+            None
+        }
+    });
+
+    // Resources are separated by ';' or '|' so ',' stays usable as the delimiter
+    let resources = get_optional(columns.resources)
+        .map(|value| value.split([';', '|']).map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect())
+        .unwrap_or_default();
+
+    if errors.len() > before {
+        return None;
+    }
+
+    Some(CsvAttempt {
+        subject,
+        material,
+        problem_title,
+        timestamp: timestamp?,
+        successful: successful?,
+        minutes,
+        difficulty,
+        errors: optional_text(get_optional(columns.errors)),
+        resolution: optional_text(get_optional(columns.resolution)),
+        status_tag,
+        resources,
+    })
+}
+
+fn count(conn: &Connection, table: &str) -> Result<usize, String> {
+    conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+// Parse and validate the whole file; rows with problems are reported, not skipped
+fn read_attempts(path: &Path, options: &CsvImportOptions) -> Result<(Vec<CsvAttempt>, Vec<CsvRowError>, usize), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter.unwrap_or(',') as u8)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let columns = resolve_columns(&headers, &options.mapping)?;
+
+    let mut attempts = Vec::new();
+    let mut errors = Vec::new();
+    let mut rows = 0;
+
+    for result in reader.records() {
+        rows += 1;
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(CsvRowError {
+                    line: e.position().map(|p| p.line()).unwrap_or(0),
+                    column: None,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        // Blank lines at the end of spreadsheets are common
+        if record.iter().all(|field| field.is_empty()) {
+            rows -= 1;
+            continue;
+        }
+
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        if let Some(attempt) = parse_row(&record, &columns, options, line, &mut errors) {
+            attempts.push(attempt);
+        }
+    }
+
+    Ok((attempts, errors, rows))
+}
+
+// Import attempts from a spreadsheet export. Run inside a transaction; the
+// caller rolls it back for dry runs and when errors are reported.
+pub fn import(conn: &Connection, path: &Path, options: &CsvImportOptions) -> Result<CsvImportSummary, String> {
+    if options.delimiter.is_some_and(|d| !d.is_ascii()) {
+        return Err("Delimiter must be a single ASCII character".to_string());
+    }
+
+    let (mut attempts, errors, rows) = read_attempts(path, options)?;
+    let mut summary = CsvImportSummary {
+        dry_run: options.dry_run,
+        rows,
+        errors,
+        ..Default::default()
+    };
+    if !summary.errors.is_empty() {
+        return Ok(summary);
+    }
+
+    let subjects_before = count(conn, "Subjects")?;
+    let materials_before = count(conn, "Materials")?;
+    let problems_before = count(conn, "Problems")?;
+
+    // Chronological order keeps attempt ids and generated ids in history order
+    attempts.sort_by_key(|a| a.timestamp);
+
+    // Imported attempts go into a holding batch per problem, which is then
+    // split into batches with the current rules. Existing batches are kept.
+    let mut holding_batches: HashMap<i64, i64> = HashMap::new();
+    let rules = classification::RuleSet::load(conn)?;
+    for attempt in &attempts {
+        let subject_id = find_or_create_subject(conn, &attempt.subject)?;
+        let material_id = find_or_create_material(conn, &attempt.material, None)?;
+        link_subject_material(conn, subject_id, material_id)?;
+        let (problem_id, _) = find_or_create_problem(
+            conn, &attempt.subject, material_id, &attempt.problem_title, None, None,
+        )?;
+
+        let timestamp = format_sqlite_time(&attempt.timestamp);
+        let duplicate: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM Attempts a JOIN Batches b ON a.batch_id = b.id
+                           WHERE b.problem_id = ?1 AND a.timestamp = ?2)",
+            params![problem_id, &timestamp],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+        if duplicate {
+            summary.skipped_duplicates += 1;
+            continue;
+        }
+
+        let batch_id = match holding_batches.get(&problem_id) {
+            Some(batch_id) => *batch_id,
+            None => {
+                conn.execute(
+                    "INSERT INTO Batches (problem_id, batch_number, started_at)
+                     VALUES (?1, (SELECT COALESCE(MAX(batch_number), 0) + 1 FROM Batches WHERE problem_id = ?1), ?2)",
+                    params![problem_id, &timestamp],
+                ).map_err(|e| e.to_string())?;
+                let batch_id = conn.last_insert_rowid();
+                holding_batches.insert(problem_id, batch_id);
+                batch_id
+            }
+        };
+
+        // Numbered properly when the batches are rebuilt
+        conn.execute(
+            "INSERT INTO Attempts
+             (batch_id, attempt_number, successful, time_spent_minutes, difficulty_rating,
+              errors, resolution, status_tag, timestamp)
+             VALUES (?1, 0, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                batch_id,
+                attempt.successful,
+                attempt.minutes,
+                attempt.difficulty,
+                &attempt.errors,
+                &attempt.resolution,
+                &attempt.status_tag,
+                &timestamp,
+            ],
+        ).map_err(|e| e.to_string())?;
+        let attempt_id = conn.last_insert_rowid();
+        link_resources(conn, attempt_id, &attempt.resources)?;
+        classification::tag_attempt(conn, &rules, attempt_id)?;
+
+        summary.imported += 1;
+    }
+
+    let settings = segmentation::load_settings(conn)?;
+    let affected: BTreeSet<i64> = holding_batches.keys().copied().collect();
+    for problem_id in &affected {
+        if segmentation::segment_added_attempts(conn, *problem_id, holding_batches[problem_id], &settings)? {
+            summary.problems_renumbered += 1;
+        }
+        if let (false, true) = mastery::update_solved_status(conn, *problem_id)? {
+            summary.newly_solved += 1;
+        }
+        scheduling::reschedule_problem(conn, *problem_id)?;
+    }
+
+    summary.subjects_created = count(conn, "Subjects")? - subjects_before;
+    summary.materials_created = count(conn, "Materials")? - materials_before;
+    summary.problems_created = count(conn, "Problems")? - problems_before;
+    summary.problems_affected = affected.len();
+
+    Ok(summary)
+}
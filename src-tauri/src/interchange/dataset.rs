@@ -0,0 +1,1663 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::db::migrations;
+use crate::mastery::{self, MasteryPolicy};
+use crate::russian::{morphology, rubric};
+use crate::utils::time::{now_utc, format_sqlite_time, parse_sqlite_time};
+use crate::classification::{self, RuleKind};
+use crate::{scheduling, segmentation};
+
+// A dataset file is one JSON document with every user-entered table. Rows
+// keep their database ids so references between tables survive the trip;
+// derived state (review schedules) is rebuilt on import. Bump
+// DATASET_VERSION on any incompatible change and keep reading older ones.
+pub const DATASET_FORMAT: &str = "mastery-learning-dataset";
+pub const DATASET_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubjectRecord {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub commentary: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialRecord {
+    pub id: i64,
+    pub name_en: String,
+    pub name_ru: Option<String>,
+    pub commentary: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubjectMaterialRecord {
+    pub subject_id: i64,
+    pub material_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProblemRecord {
+    pub id: i64,
+    pub generated_id: String,
+    pub material_id: i64,
+    pub title: String,
+    pub content_type: Option<String>,
+    pub description: Option<String>,
+    pub image_filename: Option<String>,
+    pub is_solved: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRecord {
+    pub id: i64,
+    pub problem_id: i64,
+    pub batch_number: i32,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub is_fresh_start: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    pub id: i64,
+    pub batch_id: i64,
+    pub attempt_number: i32,
+    pub successful: bool,
+    pub time_spent_minutes: Option<f64>,
+    pub difficulty_rating: Option<i32>,
+    pub errors: Option<String>,
+    pub resolution: Option<String>,
+    pub commentary: Option<String>,
+    pub status_tag: Option<String>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceRecord {
+    pub id: i64,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub resource_type: Option<String>,
+    pub url: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptResourceRecord {
+    pub attempt_id: i64,
+    pub resource_id: i64,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrillRecord {
+    pub id: i64,
+    pub material_id: i64,
+    pub attempt_number: i32,
+    pub status: Option<String>,
+    pub commentary: Option<String>,
+    pub errors_ru: Option<String>,
+    pub resolution_ru: Option<String>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyRecord {
+    pub id: i64,
+    pub word_ru: String,
+    pub translation_en: String,
+    pub material_id: Option<i64>,
+    pub example_sentence: Option<String>,
+    pub first_seen: String,
+    pub last_reviewed: Option<String>,
+    pub review_count: i32,
+    pub ease: f64,
+    pub interval_days: f64,
+    pub repetitions: i32,
+    pub lapses: i32,
+    pub due_at: Option<String>,
+    // Grammar; missing from files exported before it existed
+    pub part_of_speech: Option<String>,
+    pub gender: Option<String>,
+    pub animate: Option<bool>,
+    pub aspect: Option<String>,
+    pub aspect_partner: Option<String>,
+    pub stress_position: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyGovernmentRecord {
+    pub id: i64,
+    pub vocabulary_id: i64,
+    pub preposition: Option<String>,
+    pub grammatical_case: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyIrregularFormRecord {
+    pub id: i64,
+    pub vocabulary_id: i64,
+    pub form_key: String,
+    pub form: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyReviewRecord {
+    pub id: i64,
+    pub vocabulary_id: i64,
+    pub grade: String,
+    pub interval_days: f64,
+    pub ease: f64,
+    pub reviewed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyFormRecord {
+    pub id: i64,
+    pub vocabulary_id: i64,
+    pub form: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormDrillExerciseRecord {
+    pub id: i64,
+    pub drill_id: i64,
+    pub position: i32,
+    pub vocabulary_id: Option<i64>,
+    pub word_ru: String,
+    pub kind: String,
+    pub form_key: String,
+    pub prompt: String,
+    pub accepted: String,
+    pub answer: Option<String>,
+    pub correct: Option<bool>,
+    pub answered_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrillRubricRecord {
+    pub drill_id: i64,
+    pub fluency: i32,
+    pub accuracy: i32,
+    pub completeness: i32,
+    pub terminology_coverage: Option<f64>,
+    pub terms_used: String,
+    pub terms_missing: String,
+    pub overall: f64,
+    pub scored_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialTermRecord {
+    pub id: i64,
+    pub material_id: i64,
+    pub term_ru: String,
+    pub translation_en: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorCategoryRecord {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub description: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorCategoryRuleRecord {
+    pub id: i64,
+    pub category_id: i64,
+    pub kind: String,
+    pub pattern: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptErrorCategoryRecord {
+    pub attempt_id: i64,
+    pub category_id: i64,
+    pub source: String,
+}
+
+// An attempt whose error categories were set by hand
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptErrorOverrideRecord {
+    pub attempt_id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrillVocabularyRecord {
+    pub drill_id: i64,
+    pub vocabulary_id: i64,
+}
+
+// App settings (scheduler, batch segmentation, auto-backup), by key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingRecord {
+    pub key: String,
+    pub value: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dataset {
+    pub format: String,
+    pub version: u32,
+    // Schema the file was exported from, for diagnostics only
+    pub schema_version: i32,
+    pub exported_at: String,
+    pub subjects: Vec<SubjectRecord>,
+    pub materials: Vec<MaterialRecord>,
+    pub subject_materials: Vec<SubjectMaterialRecord>,
+    pub problems: Vec<ProblemRecord>,
+    pub batches: Vec<BatchRecord>,
+    pub attempts: Vec<AttemptRecord>,
+    pub resources: Vec<ResourceRecord>,
+    pub attempt_resources: Vec<AttemptResourceRecord>,
+    pub russian_drill_attempts: Vec<DrillRecord>,
+    pub russian_vocabulary: Vec<VocabularyRecord>,
+    pub vocabulary_reviews: Vec<VocabularyReviewRecord>,
+    pub drill_vocabulary: Vec<DrillVocabularyRecord>,
+    #[serde(default)]
+    pub mastery_policies: Vec<MasteryPolicy>,
+    #[serde(default)]
+    pub vocabulary_forms: Vec<VocabularyFormRecord>,
+    #[serde(default)]
+    pub vocabulary_government: Vec<VocabularyGovernmentRecord>,
+    #[serde(default)]
+    pub vocabulary_irregular_forms: Vec<VocabularyIrregularFormRecord>,
+    #[serde(default)]
+    pub form_drill_exercises: Vec<FormDrillExerciseRecord>,
+    #[serde(default)]
+    pub drill_rubric_scores: Vec<DrillRubricRecord>,
+    #[serde(default)]
+    pub material_terms: Vec<MaterialTermRecord>,
+    #[serde(default)]
+    pub error_categories: Vec<ErrorCategoryRecord>,
+    #[serde(default)]
+    pub error_category_rules: Vec<ErrorCategoryRuleRecord>,
+    #[serde(default)]
+    pub attempt_error_categories: Vec<AttemptErrorCategoryRecord>,
+    #[serde(default)]
+    pub attempt_error_overrides: Vec<AttemptErrorOverrideRecord>,
+    #[serde(default)]
+    pub settings: Vec<SettingRecord>,
+}
+
+impl Dataset {
+    pub fn table_counts(&self) -> BTreeMap<String, usize> {
+        BTreeMap::from([
+            ("Subjects".to_string(), self.subjects.len()),
+            ("Materials".to_string(), self.materials.len()),
+            ("SubjectMaterials".to_string(), self.subject_materials.len()),
+            ("Problems".to_string(), self.problems.len()),
+            ("Batches".to_string(), self.batches.len()),
+            ("Attempts".to_string(), self.attempts.len()),
+            ("Resources".to_string(), self.resources.len()),
+            ("AttemptResources".to_string(), self.attempt_resources.len()),
+            ("RussianDrillAttempts".to_string(), self.russian_drill_attempts.len()),
+            ("RussianVocabulary".to_string(), self.russian_vocabulary.len()),
+            ("VocabularyReviews".to_string(), self.vocabulary_reviews.len()),
+            ("DrillVocabulary".to_string(), self.drill_vocabulary.len()),
+            ("MasteryPolicies".to_string(), self.mastery_policies.len()),
+            ("VocabularyForms".to_string(), self.vocabulary_forms.len()),
+            ("VocabularyGovernment".to_string(), self.vocabulary_government.len()),
+            ("VocabularyIrregularForms".to_string(), self.vocabulary_irregular_forms.len()),
+            ("FormDrillExercises".to_string(), self.form_drill_exercises.len()),
+            ("DrillRubricScores".to_string(), self.drill_rubric_scores.len()),
+            ("MaterialTerms".to_string(), self.material_terms.len()),
+            ("ErrorCategories".to_string(), self.error_categories.len()),
+            ("ErrorCategoryRules".to_string(), self.error_category_rules.len()),
+            ("AttemptErrorCategories".to_string(), self.attempt_error_categories.len()),
+            ("AttemptErrorOverrides".to_string(), self.attempt_error_overrides.len()),
+            ("Settings".to_string(), self.settings.len()),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    // Add to the existing data, matching rows by their natural keys
+    Merge,
+    // Wipe all user data first and load the file as-is
+    Replace,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TableImportCounts {
+    pub inserted: usize,
+    // Rows that already existed and were reused
+    pub matched: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportConflict {
+    pub table: String,
+    pub key: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub mode: ImportMode,
+    pub tables: BTreeMap<String, TableImportCounts>,
+    pub conflicts: Vec<ImportConflict>,
+    pub problems_rescheduled: usize,
+}
+
+impl ImportSummary {
+    fn inserted(&mut self, table: &str) {
+        self.tables.entry(table.to_string()).or_default().inserted += 1;
+    }
+
+    fn matched(&mut self, table: &str) {
+        self.tables.entry(table.to_string()).or_default().matched += 1;
+    }
+
+    fn conflict(&mut self, table: &str, key: &str, message: String) {
+        self.conflicts.push(ImportConflict {
+            table: table.to_string(),
+            key: key.to_string(),
+            message,
+        });
+    }
+
+    // Existing values win; only report fields where both sides have a value
+    fn compare(&mut self, table: &str, key: &str, fields: &[(&str, Option<String>, Option<String>)]) {
+        for (field, existing, incoming) in fields {
+            if let (Some(existing), Some(incoming)) = (existing, incoming) {
+                if existing != incoming {
+                    self.conflict(table, key, format!(
+                        "{} differs (kept '{}', ignored '{}')", field, existing, incoming
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn query_all<T>(
+    conn: &Connection,
+    sql: &str,
+    map: fn(&Row) -> rusqlite::Result<T>,
+) -> Result<Vec<T>, String> {
+    conn.prepare(sql)
+        .map_err(|e| e.to_string())?
+        .query_map([], map)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn export(conn: &Connection) -> Result<Dataset, String> {
+    Ok(Dataset {
+        format: DATASET_FORMAT.to_string(),
+        version: DATASET_VERSION,
+        schema_version: migrations::current_version(conn)?,
+        exported_at: format_sqlite_time(&now_utc()),
+        subjects: query_all(conn,
+            "SELECT id, name, description, commentary, created_at, updated_at
+             FROM Subjects ORDER BY id",
+            |row| Ok(SubjectRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                commentary: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            }))?,
+        materials: query_all(conn,
+            "SELECT id, name_en, name_ru, commentary, created_at, updated_at
+             FROM Materials ORDER BY id",
+            |row| Ok(MaterialRecord {
+                id: row.get(0)?,
+                name_en: row.get(1)?,
+                name_ru: row.get(2)?,
+                commentary: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            }))?,
+        subject_materials: query_all(conn,
+            "SELECT subject_id, material_id FROM SubjectMaterials ORDER BY subject_id, material_id",
+            |row| Ok(SubjectMaterialRecord {
+                subject_id: row.get(0)?,
+                material_id: row.get(1)?,
+            }))?,
+        problems: query_all(conn,
+            "SELECT id, generated_id, material_id, title, content_type, description,
+                    image_filename, COALESCE(is_solved, 0), created_at, updated_at
+             FROM Problems ORDER BY id",
+            |row| Ok(ProblemRecord {
+                id: row.get(0)?,
+                generated_id: row.get(1)?,
+                material_id: row.get(2)?,
+                title: row.get(3)?,
+                content_type: row.get(4)?,
+                description: row.get(5)?,
+                image_filename: row.get(6)?,
+                is_solved: row.get(7)?,
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
+            }))?,
+        batches: query_all(conn,
+            "SELECT id, problem_id, batch_number, started_at, ended_at, COALESCE(is_fresh_start, 0)
+             FROM Batches ORDER BY id",
+            |row| Ok(BatchRecord {
+                id: row.get(0)?,
+                problem_id: row.get(1)?,
+                batch_number: row.get(2)?,
+                started_at: row.get(3)?,
+                ended_at: row.get(4)?,
+                is_fresh_start: row.get(5)?,
+            }))?,
+        attempts: query_all(conn,
+            "SELECT id, batch_id, attempt_number, successful, time_spent_minutes, difficulty_rating,
+                    errors, resolution, commentary, status_tag, timestamp
+             FROM Attempts ORDER BY id",
+            |row| Ok(AttemptRecord {
+                id: row.get(0)?,
+                batch_id: row.get(1)?,
+                attempt_number: row.get(2)?,
+                successful: row.get(3)?,
+                time_spent_minutes: row.get(4)?,
+                difficulty_rating: row.get(5)?,
+                errors: row.get(6)?,
+                resolution: row.get(7)?,
+                commentary: row.get(8)?,
+                status_tag: row.get(9)?,
+                timestamp: row.get(10)?,
+            }))?,
+        resources: query_all(conn,
+            "SELECT id, name, type, url, created_at FROM Resources ORDER BY id",
+            |row| Ok(ResourceRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                resource_type: row.get(2)?,
+                url: row.get(3)?,
+                created_at: row.get(4)?,
+            }))?,
+        attempt_resources: query_all(conn,
+            "SELECT attempt_id, resource_id, notes FROM AttemptResources ORDER BY attempt_id, resource_id",
+            |row| Ok(AttemptResourceRecord {
+                attempt_id: row.get(0)?,
+                resource_id: row.get(1)?,
+                notes: row.get(2)?,
+            }))?,
+        russian_drill_attempts: query_all(conn,
+            "SELECT id, material_id, attempt_number, status, commentary, errors_ru, resolution_ru, timestamp
+             FROM RussianDrillAttempts ORDER BY id",
+            |row| Ok(DrillRecord {
+                id: row.get(0)?,
+                material_id: row.get(1)?,
+                attempt_number: row.get(2)?,
+                status: row.get(3)?,
+                commentary: row.get(4)?,
+                errors_ru: row.get(5)?,
+                resolution_ru: row.get(6)?,
+                timestamp: row.get(7)?,
+            }))?,
+        russian_vocabulary: query_all(conn,
+            "SELECT id, word_ru, translation_en, material_id, example_sentence, first_seen,
+                    last_reviewed, COALESCE(review_count, 0), ease, interval_days, repetitions,
+                    lapses, due_at, part_of_speech, gender, animate, aspect, aspect_partner,
+                    stress_position
+             FROM RussianVocabulary ORDER BY id",
+            |row| Ok(VocabularyRecord {
+                id: row.get(0)?,
+                word_ru: row.get(1)?,
+                translation_en: row.get(2)?,
+                material_id: row.get(3)?,
+                example_sentence: row.get(4)?,
+                first_seen: row.get(5)?,
+                last_reviewed: row.get(6)?,
+                review_count: row.get(7)?,
+                ease: row.get(8)?,
+                interval_days: row.get(9)?,
+                repetitions: row.get(10)?,
+                lapses: row.get(11)?,
+                due_at: row.get(12)?,
+                part_of_speech: row.get(13)?,
+                gender: row.get(14)?,
+                animate: row.get(15)?,
+                aspect: row.get(16)?,
+                aspect_partner: row.get(17)?,
+                stress_position: row.get(18)?,
+            }))?,
+        vocabulary_reviews: query_all(conn,
+            "SELECT id, vocabulary_id, grade, interval_days, ease, reviewed_at
+             FROM VocabularyReviews ORDER BY id",
+            |row| Ok(VocabularyReviewRecord {
+                id: row.get(0)?,
+                vocabulary_id: row.get(1)?,
+                grade: row.get(2)?,
+                interval_days: row.get(3)?,
+                ease: row.get(4)?,
+                reviewed_at: row.get(5)?,
+            }))?,
+        drill_vocabulary: query_all(conn,
+            "SELECT drill_id, vocabulary_id FROM DrillVocabulary ORDER BY drill_id, vocabulary_id",
+            |row| Ok(DrillVocabularyRecord {
+                drill_id: row.get(0)?,
+                vocabulary_id: row.get(1)?,
+            }))?,
+        mastery_policies: mastery::list_policies(conn)?,
+        vocabulary_forms: query_all(conn,
+            "SELECT id, vocabulary_id, form, created_at FROM VocabularyForms ORDER BY id",
+            |row| Ok(VocabularyFormRecord {
+                id: row.get(0)?,
+                vocabulary_id: row.get(1)?,
+                form: row.get(2)?,
+                created_at: row.get(3)?,
+            }))?,
+        vocabulary_government: query_all(conn,
+            "SELECT id, vocabulary_id, preposition, grammatical_case, note FROM VocabularyGovernment ORDER BY id",
+            |row| Ok(VocabularyGovernmentRecord {
+                id: row.get(0)?,
+                vocabulary_id: row.get(1)?,
+                preposition: row.get(2)?,
+                grammatical_case: row.get(3)?,
+                note: row.get(4)?,
+            }))?,
+        vocabulary_irregular_forms: query_all(conn,
+            "SELECT id, vocabulary_id, form_key, form FROM VocabularyIrregularForms ORDER BY id",
+            |row| Ok(VocabularyIrregularFormRecord {
+                id: row.get(0)?,
+                vocabulary_id: row.get(1)?,
+                form_key: row.get(2)?,
+                form: row.get(3)?,
+            }))?,
+        form_drill_exercises: query_all(conn,
+            "SELECT id, drill_id, position, vocabulary_id, word_ru, kind, form_key, prompt, accepted,
+                    answer, correct, answered_at
+             FROM FormDrillExercises ORDER BY id",
+            |row| Ok(FormDrillExerciseRecord {
+                id: row.get(0)?,
+                drill_id: row.get(1)?,
+                position: row.get(2)?,
+                vocabulary_id: row.get(3)?,
+                word_ru: row.get(4)?,
+                kind: row.get(5)?,
+                form_key: row.get(6)?,
+                prompt: row.get(7)?,
+                accepted: row.get(8)?,
+                answer: row.get(9)?,
+                correct: row.get(10)?,
+                answered_at: row.get(11)?,
+            }))?,
+        drill_rubric_scores: query_all(conn,
+            "SELECT drill_id, fluency, accuracy, completeness, terminology_coverage, terms_used,
+                    terms_missing, overall, scored_at
+             FROM DrillRubricScores ORDER BY drill_id",
+            |row| Ok(DrillRubricRecord {
+                drill_id: row.get(0)?,
+                fluency: row.get(1)?,
+                accuracy: row.get(2)?,
+                completeness: row.get(3)?,
+                terminology_coverage: row.get(4)?,
+                terms_used: row.get(5)?,
+                terms_missing: row.get(6)?,
+                overall: row.get(7)?,
+                scored_at: row.get(8)?,
+            }))?,
+        material_terms: query_all(conn,
+            "SELECT id, material_id, term_ru, translation_en, notes, created_at
+             FROM MaterialTerms ORDER BY id",
+            |row| Ok(MaterialTermRecord {
+                id: row.get(0)?,
+                material_id: row.get(1)?,
+                term_ru: row.get(2)?,
+                translation_en: row.get(3)?,
+                notes: row.get(4)?,
+                created_at: row.get(5)?,
+            }))?,
+        error_categories: query_all(conn,
+            "SELECT id, name, parent_id, description, created_at FROM ErrorCategories ORDER BY id",
+            |row| Ok(ErrorCategoryRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                description: row.get(3)?,
+                created_at: row.get(4)?,
+            }))?,
+        error_category_rules: query_all(conn,
+            "SELECT id, category_id, kind, pattern, created_at FROM ErrorCategoryRules ORDER BY id",
+            |row| Ok(ErrorCategoryRuleRecord {
+                id: row.get(0)?,
+                category_id: row.get(1)?,
+                kind: row.get(2)?,
+                pattern: row.get(3)?,
+                created_at: row.get(4)?,
+            }))?,
+        attempt_error_categories: query_all(conn,
+            "SELECT attempt_id, category_id, source FROM AttemptErrorCategories ORDER BY attempt_id, category_id",
+            |row| Ok(AttemptErrorCategoryRecord {
+                attempt_id: row.get(0)?,
+                category_id: row.get(1)?,
+                source: row.get(2)?,
+            }))?,
+        attempt_error_overrides: query_all(conn,
+            "SELECT attempt_id FROM AttemptErrorOverrides ORDER BY attempt_id",
+            |row| Ok(AttemptErrorOverrideRecord {
+                attempt_id: row.get(0)?,
+            }))?,
+        settings: query_all(conn,
+            "SELECT key, value, updated_at FROM Settings ORDER BY key",
+            |row| Ok(SettingRecord {
+                key: row.get(0)?,
+                value: row.get(1)?,
+                updated_at: row.get(2)?,
+            }))?,
+    })
+}
+
+// Children first, so the wipe works with foreign keys on
+const REPLACE_DELETE_ORDER: &[&str] = &[
+    "DrillVocabulary",
+    "FormDrillExercises",
+    "DrillRubricScores",
+    "VocabularyReviews",
+    "VocabularyForms",
+    "VocabularyGovernment",
+    "VocabularyIrregularForms",
+    "RussianVocabulary",
+    "RussianDrillAttempts",
+    "AttemptErrorCategories",
+    "AttemptErrorOverrides",
+    "AttemptResources",
+    "Attempts",
+    "Batches",
+    "ProblemSchedules",
+    "Problems",
+    "Resources",
+    "SubjectMaterials",
+    "MaterialTerms",
+    "ErrorCategoryRules",
+    "ErrorCategories",
+    "MasteryPolicies",
+    "Materials",
+    "Subjects",
+    "Settings",
+];
+
+fn validate(dataset: &Dataset) -> Result<(), String> {
+    if dataset.format != DATASET_FORMAT {
+        return Err(format!("Not a dataset file (format '{}')", dataset.format));
+    }
+    if dataset.version > DATASET_VERSION {
+        return Err(format!(
+            "Dataset version {} is newer than this app supports (version {})",
+            dataset.version, DATASET_VERSION
+        ));
+    }
+
+    // Timestamps feed batching and scheduling, so reject garbage up front
+    for attempt in &dataset.attempts {
+        parse_sqlite_time(&attempt.timestamp)
+            .map_err(|e| format!("Attempt {}: {}", attempt.id, e))?;
+    }
+    for batch in &dataset.batches {
+        parse_sqlite_time(&batch.started_at)
+            .map_err(|e| format!("Batch {}: {}", batch.id, e))?;
+    }
+    Ok(())
+}
+
+fn mapped(map: &HashMap<i64, i64>, table: &str, id: i64) -> Result<i64, String> {
+    map.get(&id)
+        .copied()
+        .ok_or_else(|| format!("Dataset references missing {} id {}", table, id))
+}
+
+// Load a dataset into the database. Run inside a transaction: any error
+// leaves the database untouched.
+pub fn import(conn: &Connection, dataset: &Dataset, mode: ImportMode) -> Result<ImportSummary, String> {
+    validate(dataset)?;
+
+    let mut summary = ImportSummary {
+        mode,
+        tables: BTreeMap::new(),
+        conflicts: Vec::new(),
+        problems_rescheduled: 0,
+    };
+
+    let replace = mode == ImportMode::Replace;
+    if replace {
+        for table in REPLACE_DELETE_ORDER {
+            conn.execute(&format!("DELETE FROM {}", table), [])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    // In replace mode rows keep their ids; in merge mode SQLite assigns new ones
+    let keep_id = |id: i64| if replace { Some(id) } else { None };
+
+    // Settings first: rescheduling below uses the scheduler they select.
+    // Existing values win in merge mode.
+    for setting in &dataset.settings {
+        let existing: Option<String> = conn.query_row(
+            "SELECT value FROM Settings WHERE key = ?1",
+            params![&setting.key],
+            |row| row.get(0)
+        ).optional().map_err(|e| e.to_string())?;
+
+        if existing.is_some() {
+            summary.compare("Settings", &setting.key, &[
+                ("value", existing, Some(setting.value.clone())),
+            ]);
+            summary.matched("Settings");
+        } else {
+            conn.execute(
+                "INSERT INTO Settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
+                params![&setting.key, &setting.value, &setting.updated_at],
+            ).map_err(|e| e.to_string())?;
+            summary.inserted("Settings");
+        }
+    }
+
+    // Subjects (by name)
+    let mut subject_ids = HashMap::new();
+    for subject in &dataset.subjects {
+        let existing: Option<(i64, Option<String>, Option<String>)> = conn.query_row(
+            "SELECT id, description, commentary FROM Subjects WHERE name = ?1",
+            params![&subject.name],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ).optional().map_err(|e| e.to_string())?;
+
+        let id = match existing {
+            Some((id, description, commentary)) => {
+                summary.compare("Subjects", &subject.name, &[
+                    ("description", description, subject.description.clone()),
+                    ("commentary", commentary, subject.commentary.clone()),
+                ]);
+                conn.execute(
+                    "UPDATE Subjects SET description = COALESCE(description, ?1),
+                                         commentary = COALESCE(commentary, ?2)
+                     WHERE id = ?3",
+                    params![&subject.description, &subject.commentary, id],
+                ).map_err(|e| e.to_string())?;
+                summary.matched("Subjects");
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO Subjects (id, name, description, commentary, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        keep_id(subject.id),
+                        &subject.name,
+                        &subject.description,
+                        &subject.commentary,
+                        &subject.created_at,
+                        &subject.updated_at,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("Subjects");
+                conn.last_insert_rowid()
+            }
+        };
+        subject_ids.insert(subject.id, id);
+    }
+
+    // Materials (by English name)
+    let mut material_ids = HashMap::new();
+    for material in &dataset.materials {
+        let existing: Option<(i64, Option<String>, Option<String>)> = conn.query_row(
+            "SELECT id, name_ru, commentary FROM Materials WHERE name_en = ?1",
+            params![&material.name_en],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ).optional().map_err(|e| e.to_string())?;
+
+        let id = match existing {
+            Some((id, name_ru, commentary)) => {
+                summary.compare("Materials", &material.name_en, &[
+                    ("name_ru", name_ru, material.name_ru.clone()),
+                    ("commentary", commentary, material.commentary.clone()),
+                ]);
+                conn.execute(
+                    "UPDATE Materials SET name_ru = COALESCE(name_ru, ?1),
+                                          commentary = COALESCE(commentary, ?2)
+                     WHERE id = ?3",
+                    params![&material.name_ru, &material.commentary, id],
+                ).map_err(|e| e.to_string())?;
+                summary.matched("Materials");
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO Materials (id, name_en, name_ru, commentary, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        keep_id(material.id),
+                        &material.name_en,
+                        &material.name_ru,
+                        &material.commentary,
+                        &material.created_at,
+                        &material.updated_at,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("Materials");
+                conn.last_insert_rowid()
+            }
+        };
+        material_ids.insert(material.id, id);
+    }
+
+    for link in &dataset.subject_materials {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO SubjectMaterials (subject_id, material_id) VALUES (?1, ?2)",
+            params![
+                mapped(&subject_ids, "subject", link.subject_id)?,
+                mapped(&material_ids, "material", link.material_id)?,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("SubjectMaterials");
+        } else {
+            summary.matched("SubjectMaterials");
+        }
+    }
+
+    // Problems (by generated id, falling back to material + title which is
+    // also unique)
+    let mut problem_ids = HashMap::new();
+    // Existing problems that receive imported history
+    let mut merged_problems = HashSet::new();
+    let mut new_problems = HashSet::new();
+    for problem in &dataset.problems {
+        let material_id = mapped(&material_ids, "material", problem.material_id)?;
+
+        let by_generated_id: Option<(i64, i64, String)> = conn.query_row(
+            "SELECT id, material_id, title FROM Problems WHERE generated_id = ?1",
+            params![&problem.generated_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ).optional().map_err(|e| e.to_string())?;
+
+        let existing = match by_generated_id {
+            Some((id, existing_material, existing_title)) => {
+                if existing_material != material_id || existing_title != problem.title {
+                    summary.conflict("Problems", &problem.generated_id, format!(
+                        "Already exists as '{}' in another material or under another title; history merged into it",
+                        existing_title
+                    ));
+                }
+                Some(id)
+            }
+            None => {
+                let by_title: Option<(i64, String)> = conn.query_row(
+                    "SELECT id, generated_id FROM Problems WHERE material_id = ?1 AND title = ?2",
+                    params![material_id, &problem.title],
+                    |row| Ok((row.get(0)?, row.get(1)?))
+                ).optional().map_err(|e| e.to_string())?;
+
+                if let Some((_, existing_generated_id)) = &by_title {
+                    summary.conflict("Problems", &problem.generated_id, format!(
+                        "Same title as existing problem {}; history merged into it",
+                        existing_generated_id
+                    ));
+                }
+                by_title.map(|(id, _)| id)
+            }
+        };
+
+        let id = match existing {
+            Some(id) => {
+                conn.execute(
+                    "UPDATE Problems SET description = COALESCE(description, ?1),
+                                         image_filename = COALESCE(image_filename, ?2)
+                     WHERE id = ?3",
+                    params![&problem.description, &problem.image_filename, id],
+                ).map_err(|e| e.to_string())?;
+                summary.matched("Problems");
+                merged_problems.insert(id);
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO Problems
+                     (id, generated_id, material_id, title, content_type, description,
+                      image_filename, is_solved, created_at, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        keep_id(problem.id),
+                        &problem.generated_id,
+                        material_id,
+                        &problem.title,
+                        &problem.content_type,
+                        &problem.description,
+                        &problem.image_filename,
+                        problem.is_solved,
+                        &problem.created_at,
+                        &problem.updated_at,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("Problems");
+                let id = conn.last_insert_rowid();
+                new_problems.insert(id);
+                id
+            }
+        };
+        problem_ids.insert(problem.id, id);
+    }
+
+    // Batches: a batch of an existing problem that started at the same time
+    // is the same session
+    let mut batch_ids = HashMap::new();
+    let mut merged_batches = HashSet::new();
+    for batch in &dataset.batches {
+        let problem_id = mapped(&problem_ids, "problem", batch.problem_id)?;
+
+        let existing: Option<i64> = if merged_problems.contains(&problem_id) {
+            conn.query_row(
+                "SELECT id FROM Batches WHERE problem_id = ?1 AND started_at = ?2",
+                params![problem_id, &batch.started_at],
+                |row| row.get(0)
+            ).optional().map_err(|e| e.to_string())?
+        } else {
+            None
+        };
+
+        let id = match existing {
+            Some(id) => {
+                summary.matched("Batches");
+                merged_batches.insert(id);
+                id
+            }
+            None => {
+                // Numbers of merged problems are fixed up once everything is in
+                let batch_number: i32 = if merged_problems.contains(&problem_id) {
+                    conn.query_row(
+                        "SELECT COALESCE(MAX(batch_number), 0) + 1 FROM Batches WHERE problem_id = ?1",
+                        params![problem_id],
+                        |row| row.get(0)
+                    ).map_err(|e| e.to_string())?
+                } else {
+                    batch.batch_number
+                };
+
+                conn.execute(
+                    "INSERT INTO Batches (id, problem_id, batch_number, started_at, ended_at, is_fresh_start)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        keep_id(batch.id),
+                        problem_id,
+                        batch_number,
+                        &batch.started_at,
+                        &batch.ended_at,
+                        batch.is_fresh_start,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("Batches");
+                conn.last_insert_rowid()
+            }
+        };
+        batch_ids.insert(batch.id, id);
+    }
+
+    // Attempts: within a matched batch, one at the same time is a duplicate
+    let mut attempt_ids = HashMap::new();
+    for attempt in &dataset.attempts {
+        let batch_id = mapped(&batch_ids, "batch", attempt.batch_id)?;
+
+        let existing: Option<i64> = if merged_batches.contains(&batch_id) {
+            conn.query_row(
+                "SELECT id FROM Attempts WHERE batch_id = ?1 AND timestamp = ?2",
+                params![batch_id, &attempt.timestamp],
+                |row| row.get(0)
+            ).optional().map_err(|e| e.to_string())?
+        } else {
+            None
+        };
+
+        let id = match existing {
+            Some(id) => {
+                summary.matched("Attempts");
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO Attempts
+                     (id, batch_id, attempt_number, successful, time_spent_minutes, difficulty_rating,
+                      errors, resolution, commentary, status_tag, timestamp)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        keep_id(attempt.id),
+                        batch_id,
+                        attempt.attempt_number,
+                        attempt.successful,
+                        attempt.time_spent_minutes,
+                        attempt.difficulty_rating,
+                        &attempt.errors,
+                        &attempt.resolution,
+                        &attempt.commentary,
+                        &attempt.status_tag,
+                        &attempt.timestamp,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("Attempts");
+                conn.last_insert_rowid()
+            }
+        };
+        attempt_ids.insert(attempt.id, id);
+    }
+
+    // Resources (by name)
+    let mut resource_ids = HashMap::new();
+    for resource in &dataset.resources {
+        let existing: Option<(i64, Option<String>, Option<String>)> = conn.query_row(
+            "SELECT id, type, url FROM Resources WHERE name = ?1",
+            params![&resource.name],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ).optional().map_err(|e| e.to_string())?;
+
+        let id = match existing {
+            Some((id, resource_type, url)) => {
+                summary.compare("Resources", &resource.name, &[
+                    ("type", resource_type, resource.resource_type.clone()),
+                    ("url", url, resource.url.clone()),
+                ]);
+                conn.execute(
+                    "UPDATE Resources SET url = COALESCE(url, ?1) WHERE id = ?2",
+                    params![&resource.url, id],
+                ).map_err(|e| e.to_string())?;
+                summary.matched("Resources");
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO Resources (id, name, type, url, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        keep_id(resource.id),
+                        &resource.name,
+                        &resource.resource_type,
+                        &resource.url,
+                        &resource.created_at,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("Resources");
+                conn.last_insert_rowid()
+            }
+        };
+        resource_ids.insert(resource.id, id);
+    }
+
+    for link in &dataset.attempt_resources {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO AttemptResources (attempt_id, resource_id, notes) VALUES (?1, ?2, ?3)",
+            params![
+                mapped(&attempt_ids, "attempt", link.attempt_id)?,
+                mapped(&resource_ids, "resource", link.resource_id)?,
+                &link.notes,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("AttemptResources");
+        } else {
+            summary.matched("AttemptResources");
+        }
+    }
+
+    // Drills: same material and time is the same drill
+    let mut drill_ids = HashMap::new();
+    let mut merged_drill_materials = HashSet::new();
+    for drill in &dataset.russian_drill_attempts {
+        let material_id = mapped(&material_ids, "material", drill.material_id)?;
+
+        let existing: Option<i64> = if replace {
+            None
+        } else {
+            conn.query_row(
+                "SELECT id FROM RussianDrillAttempts WHERE material_id = ?1 AND timestamp = ?2",
+                params![material_id, &drill.timestamp],
+                |row| row.get(0)
+            ).optional().map_err(|e| e.to_string())?
+        };
+
+        let id = match existing {
+            Some(id) => {
+                summary.matched("RussianDrillAttempts");
+                id
+            }
+            None => {
+                let has_drills: bool = !replace && conn.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM RussianDrillAttempts WHERE material_id = ?1)",
+                    params![material_id],
+                    |row| row.get(0)
+                ).map_err(|e| e.to_string())?;
+                if has_drills {
+                    merged_drill_materials.insert(material_id);
+                }
+
+                conn.execute(
+                    "INSERT INTO RussianDrillAttempts
+                     (id, material_id, attempt_number, status, commentary, errors_ru, resolution_ru, timestamp)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        keep_id(drill.id),
+                        material_id,
+                        drill.attempt_number,
+                        &drill.status,
+                        &drill.commentary,
+                        &drill.errors_ru,
+                        &drill.resolution_ru,
+                        &drill.timestamp,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("RussianDrillAttempts");
+                conn.last_insert_rowid()
+            }
+        };
+        drill_ids.insert(drill.id, id);
+    }
+
+    // Vocabulary (by word); an existing word keeps its own review state
+    let mut vocabulary_ids = HashMap::new();
+    let mut new_words = HashSet::new();
+    for word in &dataset.russian_vocabulary {
+        let material_id = word.material_id
+            .map(|id| mapped(&material_ids, "material", id))
+            .transpose()?;
+
+        let existing: Option<(i64, String, Option<String>)> = conn.query_row(
+            "SELECT id, translation_en, example_sentence FROM RussianVocabulary WHERE word_ru = ?1",
+            params![&word.word_ru],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ).optional().map_err(|e| e.to_string())?;
+
+        let id = match existing {
+            Some((id, translation_en, example_sentence)) => {
+                summary.compare("RussianVocabulary", &word.word_ru, &[
+                    ("translation_en", Some(translation_en), Some(word.translation_en.clone())),
+                    ("example_sentence", example_sentence, word.example_sentence.clone()),
+                ]);
+                conn.execute(
+                    "UPDATE RussianVocabulary SET material_id = COALESCE(material_id, ?1),
+                                                  example_sentence = COALESCE(example_sentence, ?2),
+                                                  part_of_speech = COALESCE(part_of_speech, ?3),
+                                                  gender = COALESCE(gender, ?4),
+                                                  animate = COALESCE(animate, ?5),
+                                                  aspect = COALESCE(aspect, ?6),
+                                                  aspect_partner = COALESCE(aspect_partner, ?7),
+                                                  stress_position = COALESCE(stress_position, ?8)
+                     WHERE id = ?9",
+                    params![
+                        material_id,
+                        &word.example_sentence,
+                        &word.part_of_speech,
+                        &word.gender,
+                        word.animate,
+                        &word.aspect,
+                        &word.aspect_partner,
+                        word.stress_position,
+                        id,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.matched("RussianVocabulary");
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO RussianVocabulary
+                     (id, word_ru, translation_en, material_id, example_sentence, first_seen,
+                      last_reviewed, review_count, ease, interval_days, repetitions, lapses, due_at,
+                      part_of_speech, gender, animate, aspect, aspect_partner, stress_position)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+                    params![
+                        keep_id(word.id),
+                        &word.word_ru,
+                        &word.translation_en,
+                        material_id,
+                        &word.example_sentence,
+                        &word.first_seen,
+                        &word.last_reviewed,
+                        word.review_count,
+                        word.ease,
+                        word.interval_days,
+                        word.repetitions,
+                        word.lapses,
+                        &word.due_at,
+                        &word.part_of_speech,
+                        &word.gender,
+                        word.animate,
+                        &word.aspect,
+                        &word.aspect_partner,
+                        word.stress_position,
+                    ],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("RussianVocabulary");
+                let id = conn.last_insert_rowid();
+                new_words.insert(id);
+                id
+            }
+        };
+        vocabulary_ids.insert(word.id, id);
+    }
+
+    for review in &dataset.vocabulary_reviews {
+        let vocabulary_id = mapped(&vocabulary_ids, "vocabulary", review.vocabulary_id)?;
+
+        // Review history only travels with words that are new here, so it
+        // always matches the word's stored review state
+        if !new_words.contains(&vocabulary_id) {
+            summary.matched("VocabularyReviews");
+            continue;
+        }
+
+        conn.execute(
+            "INSERT INTO VocabularyReviews (id, vocabulary_id, grade, interval_days, ease, reviewed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                keep_id(review.id),
+                vocabulary_id,
+                &review.grade,
+                review.interval_days,
+                review.ease,
+                &review.reviewed_at,
+            ],
+        ).map_err(|e| e.to_string())?;
+        summary.inserted("VocabularyReviews");
+    }
+
+    // Lemmas are derived from the word, so they're never read from the file
+    morphology::backfill_lemmas(conn)?;
+
+    for form in &dataset.vocabulary_forms {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO VocabularyForms (id, vocabulary_id, form, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                keep_id(form.id),
+                mapped(&vocabulary_ids, "vocabulary", form.vocabulary_id)?,
+                &form.form,
+                &form.created_at,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("VocabularyForms");
+        } else {
+            summary.matched("VocabularyForms");
+        }
+    }
+
+    // Grammar rows merge by their natural keys (word + case/preposition,
+    // word + form key); an existing irregular form wins
+    for government in &dataset.vocabulary_government {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO VocabularyGovernment (id, vocabulary_id, preposition, grammatical_case, note)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                keep_id(government.id),
+                mapped(&vocabulary_ids, "vocabulary", government.vocabulary_id)?,
+                &government.preposition,
+                &government.grammatical_case,
+                &government.note,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("VocabularyGovernment");
+        } else {
+            summary.matched("VocabularyGovernment");
+        }
+    }
+
+    for form in &dataset.vocabulary_irregular_forms {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO VocabularyIrregularForms (id, vocabulary_id, form_key, form)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                keep_id(form.id),
+                mapped(&vocabulary_ids, "vocabulary", form.vocabulary_id)?,
+                &form.form_key,
+                &form.form,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("VocabularyIrregularForms");
+        } else {
+            summary.matched("VocabularyIrregularForms");
+        }
+    }
+
+    for link in &dataset.drill_vocabulary {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO DrillVocabulary (drill_id, vocabulary_id) VALUES (?1, ?2)",
+            params![
+                mapped(&drill_ids, "drill", link.drill_id)?,
+                mapped(&vocabulary_ids, "vocabulary", link.vocabulary_id)?,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("DrillVocabulary");
+        } else {
+            summary.matched("DrillVocabulary");
+        }
+    }
+
+    // Form drill exercises: a drill's positions are unique, so exercises of
+    // a drill that was already there are skipped
+    for exercise in &dataset.form_drill_exercises {
+        let vocabulary_id = exercise.vocabulary_id
+            .map(|id| mapped(&vocabulary_ids, "vocabulary", id))
+            .transpose()?;
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO FormDrillExercises
+                (id, drill_id, position, vocabulary_id, word_ru, kind, form_key, prompt, accepted,
+                 answer, correct, answered_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                keep_id(exercise.id),
+                mapped(&drill_ids, "drill", exercise.drill_id)?,
+                exercise.position,
+                vocabulary_id,
+                &exercise.word_ru,
+                &exercise.kind,
+                &exercise.form_key,
+                &exercise.prompt,
+                &exercise.accepted,
+                &exercise.answer,
+                exercise.correct,
+                &exercise.answered_at,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("FormDrillExercises");
+        } else {
+            summary.matched("FormDrillExercises");
+        }
+    }
+
+    // Rubric scores: a drill that already has one keeps it
+    for score in &dataset.drill_rubric_scores {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO DrillRubricScores
+                (drill_id, fluency, accuracy, completeness, terminology_coverage, terms_used,
+                 terms_missing, overall, scored_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                mapped(&drill_ids, "drill", score.drill_id)?,
+                score.fluency,
+                score.accuracy,
+                score.completeness,
+                score.terminology_coverage,
+                &score.terms_used,
+                &score.terms_missing,
+                score.overall,
+                &score.scored_at,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("DrillRubricScores");
+        } else {
+            summary.matched("DrillRubricScores");
+        }
+    }
+
+    // Glossary terms: a term the material already has keeps its translation
+    for term in &dataset.material_terms {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO MaterialTerms (id, material_id, term_ru, translation_en, notes, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                keep_id(term.id),
+                mapped(&material_ids, "material", term.material_id)?,
+                &term.term_ru,
+                &term.translation_en,
+                &term.notes,
+                &term.created_at,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("MaterialTerms");
+        } else {
+            summary.matched("MaterialTerms");
+        }
+    }
+
+    // Error categories (by name). Parents are set once every category
+    // exists; a category that was already there keeps its place.
+    let mut category_ids = HashMap::new();
+    let mut new_categories = Vec::new();
+    for category in &dataset.error_categories {
+        let existing: Option<i64> = conn.query_row(
+            "SELECT id FROM ErrorCategories WHERE name = ?1",
+            params![&category.name],
+            |row| row.get(0)
+        ).optional().map_err(|e| e.to_string())?;
+
+        let id = match existing {
+            Some(id) => {
+                summary.matched("ErrorCategories");
+                id
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO ErrorCategories (id, name, description, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![keep_id(category.id), &category.name, &category.description, &category.created_at],
+                ).map_err(|e| e.to_string())?;
+                summary.inserted("ErrorCategories");
+                new_categories.push(category);
+                conn.last_insert_rowid()
+            }
+        };
+        category_ids.insert(category.id, id);
+    }
+    for category in new_categories {
+        if let Some(parent_id) = category.parent_id {
+            conn.execute(
+                "UPDATE ErrorCategories SET parent_id = ?1 WHERE id = ?2",
+                params![
+                    mapped(&category_ids, "error category", parent_id)?,
+                    mapped(&category_ids, "error category", category.id)?,
+                ],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+
+    // A pattern that doesn't compile would break classifying every attempt
+    for rule in &dataset.error_category_rules {
+        let valid = RuleKind::parse(&rule.kind)
+            .ok_or_else(|| format!("Unknown rule kind '{}'", rule.kind))
+            .and_then(|kind| classification::compile_rule(kind, &rule.pattern));
+        if let Err(e) = valid {
+            summary.conflict("ErrorCategoryRules", &rule.pattern, format!("{}; rule skipped", e));
+            continue;
+        }
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO ErrorCategoryRules (id, category_id, kind, pattern, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                keep_id(rule.id),
+                mapped(&category_ids, "error category", rule.category_id)?,
+                &rule.kind,
+                &rule.pattern,
+                &rule.created_at,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("ErrorCategoryRules");
+        } else {
+            summary.matched("ErrorCategoryRules");
+        }
+    }
+
+    for link in &dataset.attempt_error_categories {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO AttemptErrorCategories (attempt_id, category_id, source) VALUES (?1, ?2, ?3)",
+            params![
+                mapped(&attempt_ids, "attempt", link.attempt_id)?,
+                mapped(&category_ids, "error category", link.category_id)?,
+                &link.source,
+            ],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("AttemptErrorCategories");
+        } else {
+            summary.matched("AttemptErrorCategories");
+        }
+    }
+
+    for link in &dataset.attempt_error_overrides {
+        let changed = conn.execute(
+            "INSERT OR IGNORE INTO AttemptErrorOverrides (attempt_id) VALUES (?1)",
+            params![mapped(&attempt_ids, "attempt", link.attempt_id)?],
+        ).map_err(|e| e.to_string())?;
+        if changed > 0 {
+            summary.inserted("AttemptErrorOverrides");
+        } else {
+            summary.matched("AttemptErrorOverrides");
+        }
+    }
+    // Files from before the overrides mark hand-set tags by their source only
+    if dataset.attempt_error_overrides.is_empty() {
+        conn.execute(
+            "INSERT OR IGNORE INTO AttemptErrorOverrides (attempt_id)
+             SELECT DISTINCT attempt_id FROM AttemptErrorCategories WHERE source = 'manual'",
+            [],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    // Files from before error categories: keep the defaults and tag the
+    // imported attempts with the current rules
+    if dataset.error_categories.is_empty() {
+        classification::seed_defaults(conn)?;
+        classification::reclassify_all(conn)?;
+    }
+
+    // Mastery policies: an existing policy for the same subject wins
+    for policy in &dataset.mastery_policies {
+        policy.validate()?;
+        let key = policy.subject_name.as_deref().unwrap_or("(default)");
+
+        if let Some(subject) = &policy.subject_name {
+            if !dataset.subjects.iter().any(|s| &s.name == subject) {
+                return Err(format!("Dataset has a mastery policy for unknown subject '{}'", subject));
+            }
+        }
+
+        let has_policy: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM MasteryPolicies
+                           WHERE COALESCE(subject_id, 0) = COALESCE((SELECT id FROM Subjects WHERE name = ?1), 0))",
+            params![&policy.subject_name],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+
+        if has_policy {
+            let current = mastery::policy_for_subject(conn, policy.subject_name.as_deref())?;
+            if serde_json::to_value(&current).ok() != serde_json::to_value(policy).ok() {
+                summary.conflict("MasteryPolicies", key, "Existing policy kept".to_string());
+            }
+            summary.matched("MasteryPolicies");
+        } else {
+            mastery::save_policy(conn, policy)?;
+            summary.inserted("MasteryPolicies");
+        }
+    }
+
+    if replace {
+        // Older files may lack the global default
+        conn.execute("INSERT OR IGNORE INTO MasteryPolicies (subject_id) VALUES (NULL)", [])
+            .map_err(|e| e.to_string())?;
+        summary.problems_rescheduled = scheduling::reschedule_all(conn)?;
+        return Ok(summary);
+    }
+
+    // Merged histories get chronological numbering and a fresh verdict;
+    // new problems keep their exported solved status
+    for problem_id in &merged_problems {
+        segmentation::renumber_problem(conn, *problem_id)?;
+        mastery::update_solved_status(conn, *problem_id)?;
+    }
+    for material_id in &merged_drill_materials {
+        renumber_drills(conn, *material_id)?;
+        rubric::refresh_statuses(conn, *material_id)?;
+    }
+    for problem_id in merged_problems.iter().chain(new_problems.iter()) {
+        scheduling::reschedule_problem(conn, *problem_id)?;
+    }
+    summary.problems_rescheduled = merged_problems.len() + new_problems.len();
+
+    Ok(summary)
+}
+
+fn renumber_drills(conn: &Connection, material_id: i64) -> Result<(), String> {
+    let drill_ids: Vec<i64> = conn.prepare(
+        "SELECT id FROM RussianDrillAttempts WHERE material_id = ?1 ORDER BY timestamp ASC, id ASC"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    for (index, drill_id) in drill_ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE RussianDrillAttempts SET attempt_number = ?1 WHERE id = ?2",
+            params![index as i32 + 1, drill_id],
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn rules_that_do_not_compile_are_skipped() {
+        let source = db::open_in_memory();
+        let mut dataset = export(&source).unwrap();
+        let category_id = dataset.error_categories[0].id;
+        for (id, pattern) in [(900, "off[- ]by[- ]one"), (901, "(unclosed")] {
+            dataset.error_category_rules.push(ErrorCategoryRuleRecord {
+                id,
+                category_id,
+                kind: "regex".to_string(),
+                pattern: pattern.to_string(),
+                created_at: "2024-01-01 00:00:00".to_string(),
+            });
+        }
+
+        let conn = db::open_in_memory();
+        let summary = import(&conn, &dataset, ImportMode::Replace).unwrap();
+        assert_eq!(summary.conflicts.len(), 1);
+        assert_eq!(summary.conflicts[0].key, "(unclosed");
+
+        let stored: Vec<String> = conn.prepare("SELECT pattern FROM ErrorCategoryRules WHERE id >= 900").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(stored, vec!["off[- ]by[- ]one"]);
+        assert!(classification::RuleSet::load(&conn).is_ok());
+    }
+
+    #[test]
+    fn settings_are_restored_on_replace_and_kept_on_merge() {
+        let source = db::open_in_memory();
+        source.execute("INSERT INTO Settings (key, value) VALUES ('alpha', '1'), ('beta', '2')", []).unwrap();
+        let dataset = export(&source).unwrap();
+
+        let conn = db::open_in_memory();
+        conn.execute("INSERT INTO Settings (key, value) VALUES ('alpha', '3'), ('local', 'x')", []).unwrap();
+        let summary = import(&conn, &dataset, ImportMode::Merge).unwrap();
+        assert_eq!(summary.conflicts.len(), 1);
+        assert_eq!(summary.conflicts[0].key, "alpha");
+        let value = |key: &str| conn.query_row("SELECT value FROM Settings WHERE key = ?1", params![key], |row| row.get::<_, String>(0)).optional().unwrap();
+        assert_eq!((value("alpha"), value("beta")), (Some("3".to_string()), Some("2".to_string())));
+
+        import(&conn, &dataset, ImportMode::Replace).unwrap();
+        assert_eq!((value("alpha"), value("local")), (Some("1".to_string()), None));
+    }
+}
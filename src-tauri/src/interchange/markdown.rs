@@ -0,0 +1,447 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::db::IMAGES_DIR_NAME;
+
+// Vault layout: one note per problem in `Problems/<generated_id>.md`, index
+// notes in `Subjects/` and `Materials/`, images in `attachments/`. Files
+// are only rewritten when their content changes, and anything written below
+// the marker line of a problem note is kept across exports.
+const PROBLEMS_DIR: &str = "Problems";
+const SUBJECTS_DIR: &str = "Subjects";
+const MATERIALS_DIR: &str = "Materials";
+const ATTACHMENTS_DIR: &str = "attachments";
+const NOTES_MARKER: &str = "%% mastery-learning: everything above this line is regenerated on export %%";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct VaultExportSummary {
+    pub problems: usize,
+    pub subjects: usize,
+    pub materials: usize,
+    // Files created or changed by this export
+    pub written: usize,
+    pub unchanged: usize,
+    pub images_copied: usize,
+    pub missing_images: Vec<String>,
+}
+
+struct VaultAttempt {
+    attempt_number: i32,
+    successful: bool,
+    time_spent_minutes: Option<f64>,
+    difficulty_rating: Option<i32>,
+    status_tag: Option<String>,
+    errors: Option<String>,
+    resolution: Option<String>,
+    commentary: Option<String>,
+    timestamp: String,
+    resources: Option<String>,
+}
+
+struct VaultBatch {
+    batch_number: i32,
+    started_at: String,
+    ended_at: Option<String>,
+    is_fresh_start: bool,
+    attempts: Vec<VaultAttempt>,
+}
+
+struct VaultProblem {
+    id: i64,
+    generated_id: String,
+    title: String,
+    description: Option<String>,
+    image_filename: Option<String>,
+    is_solved: bool,
+    material: String,
+    subjects: Vec<String>,
+    batches: Vec<VaultBatch>,
+}
+
+impl VaultProblem {
+    fn attempts(&self) -> impl Iterator<Item = &VaultAttempt> {
+        self.batches.iter().flat_map(|b| b.attempts.iter())
+    }
+}
+
+// Characters that break file names or Obsidian links
+fn note_name(name: &str) -> String {
+    let cleaned: String = name.chars()
+        .map(|c| if "/\\:*?\"<>|#^[]".contains(c) { '-' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').to_string();
+    if cleaned.is_empty() { "Untitled".to_string() } else { cleaned }
+}
+
+fn link(dir: &str, name: &str) -> String {
+    format!("[[{}/{}|{}]]", dir, note_name(name), name.replace(['[', ']', '|'], ""))
+}
+
+// JSON strings are valid YAML scalars
+fn yaml(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn yaml_list(values: &[String]) -> String {
+    format!("[{}]", values.iter().map(|v| yaml(v)).collect::<Vec<_>>().join(", "))
+}
+
+fn tag(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+// A list item's continuation lines must be indented to stay in the item
+fn indented(text: &str, indent: &str) -> String {
+    text.trim().lines().collect::<Vec<_>>().join(&format!("\n{}", indent))
+}
+
+fn load_problems(conn: &Connection) -> Result<Vec<VaultProblem>, String> {
+    let mut problems = conn.prepare(
+        "SELECT p.id, p.generated_id, p.title, p.description, p.image_filename,
+                COALESCE(p.is_solved, 0), m.name_en,
+                (SELECT GROUP_CONCAT(s.name, '|') FROM SubjectMaterials sm
+                 JOIN Subjects s ON sm.subject_id = s.id WHERE sm.material_id = m.id)
+         FROM Problems p
+         JOIN Materials m ON p.material_id = m.id
+         ORDER BY p.generated_id"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map([], |row| {
+        Ok(VaultProblem {
+            id: row.get(0)?,
+            generated_id: row.get(1)?,
+            title: row.get(2)?,
+            description: row.get(3)?,
+            image_filename: row.get(4)?,
+            is_solved: row.get(5)?,
+            material: row.get(6)?,
+            subjects: row.get::<_, Option<String>>(7)?
+                .map(|s| s.split('|').map(str::to_string).collect())
+                .unwrap_or_default(),
+            batches: Vec::new(),
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut batch_stmt = conn.prepare(
+        "SELECT id, batch_number, started_at, ended_at, COALESCE(is_fresh_start, 0)
+         FROM Batches WHERE problem_id = ?1 ORDER BY batch_number"
+    ).map_err(|e| e.to_string())?;
+    let mut attempt_stmt = conn.prepare(
+        "SELECT a.attempt_number, a.successful, a.time_spent_minutes, a.difficulty_rating, a.status_tag,
+                a.errors, a.resolution, a.commentary, a.timestamp,
+                (SELECT GROUP_CONCAT(r.name, ', ') FROM AttemptResources ar
+                 JOIN Resources r ON ar.resource_id = r.id WHERE ar.attempt_id = a.id)
+         FROM Attempts a WHERE a.batch_id = ?1 ORDER BY a.timestamp, a.id"
+    ).map_err(|e| e.to_string())?;
+
+    for problem in &mut problems {
+        let batches: Vec<(i64, VaultBatch)> = batch_stmt.query_map(params![problem.id], |row| {
+            Ok((row.get(0)?, VaultBatch {
+                batch_number: row.get(1)?,
+                started_at: row.get(2)?,
+                ended_at: row.get(3)?,
+                is_fresh_start: row.get(4)?,
+                attempts: Vec::new(),
+            }))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+        for (batch_id, mut batch) in batches {
+            batch.attempts = attempt_stmt.query_map(params![batch_id], |row| {
+                Ok(VaultAttempt {
+                    attempt_number: row.get(0)?,
+                    successful: row.get(1)?,
+                    time_spent_minutes: row.get(2)?,
+                    difficulty_rating: row.get(3)?,
+                    status_tag: row.get(4)?,
+                    errors: row.get(5)?,
+                    resolution: row.get(6)?,
+                    commentary: row.get(7)?,
+                    timestamp: row.get(8)?,
+                    resources: row.get(9)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+            problem.batches.push(batch);
+        }
+    }
+
+    Ok(problems)
+}
+
+fn problem_note(problem: &VaultProblem) -> String {
+    let attempts: Vec<&VaultAttempt> = problem.attempts().collect();
+    let successes = attempts.iter().filter(|a| a.successful).count();
+
+    let mut tags = vec!["mastery/problem".to_string()];
+    tags.push(if problem.is_solved { "mastery/solved" } else { "mastery/unsolved" }.to_string());
+    tags.extend(problem.subjects.iter().map(|s| format!("subject/{}", tag(s))));
+
+    let mut note = String::new();
+    note.push_str("---\n");
+    note.push_str(&format!("generated_id: {}\n", yaml(&problem.generated_id)));
+    note.push_str(&format!("title: {}\n", yaml(&problem.title)));
+    note.push_str(&format!("subjects: {}\n", yaml_list(&problem.subjects)));
+    note.push_str(&format!("material: {}\n", yaml(&problem.material)));
+    note.push_str(&format!("is_solved: {}\n", problem.is_solved));
+    note.push_str(&format!("attempts: {}\n", attempts.len()));
+    note.push_str(&format!("successful_attempts: {}\n", successes));
+    note.push_str(&format!("failed_attempts: {}\n", attempts.len() - successes));
+    note.push_str(&format!("batches: {}\n", problem.batches.len()));
+    if let Some(first) = attempts.first() {
+        note.push_str(&format!("first_attempt: {}\n", yaml(&first.timestamp)));
+    }
+    if let Some(last) = attempts.last() {
+        note.push_str(&format!("last_attempt: {}\n", yaml(&last.timestamp)));
+    }
+    note.push_str(&format!("tags: {}\n", yaml_list(&tags)));
+    note.push_str("---\n\n");
+
+    note.push_str(&format!("# {}\n\n", problem.title));
+    let subjects: Vec<String> = problem.subjects.iter().map(|s| link(SUBJECTS_DIR, s)).collect();
+    note.push_str(&format!(
+        "**Material:** {} · **Subject:** {} · **Status:** {}\n\n",
+        link(MATERIALS_DIR, &problem.material),
+        if subjects.is_empty() { "—".to_string() } else { subjects.join(", ") },
+        if problem.is_solved { "✅ solved" } else { "⏳ in progress" },
+    ));
+
+    if let Some(description) = problem.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        note.push_str(description.trim());
+        note.push_str("\n\n");
+    }
+    if let Some(image) = &problem.image_filename {
+        note.push_str(&format!("![[{}/{}]]\n\n", ATTACHMENTS_DIR, image));
+    }
+
+    note.push_str("## Attempts\n\n");
+    if problem.batches.is_empty() {
+        note.push_str("_No attempts yet._\n\n");
+    }
+    for batch in &problem.batches {
+        note.push_str(&format!("### Batch {} — {}", batch.batch_number, batch.started_at));
+        if let Some(ended_at) = &batch.ended_at {
+            note.push_str(&format!(" to {}", ended_at));
+        }
+        if batch.is_fresh_start {
+            note.push_str(" (fresh start)");
+        }
+        note.push_str("\n\n");
+
+        for attempt in &batch.attempts {
+            let mut details = vec![attempt.timestamp.clone()];
+            if let Some(minutes) = attempt.time_spent_minutes {
+                details.push(format!("{} min", minutes));
+            }
+            if let Some(difficulty) = attempt.difficulty_rating {
+                details.push(format!("difficulty {}/5", difficulty));
+            }
+            if let Some(status_tag) = &attempt.status_tag {
+                details.push(format!("#{}", status_tag));
+            }
+            note.push_str(&format!(
+                "- **#{}** {} {}\n",
+                attempt.attempt_number,
+                if attempt.successful { "✅" } else { "❌" },
+                details.join(" · "),
+            ));
+
+            for (label, text) in [
+                ("Errors", &attempt.errors),
+                ("Resolution", &attempt.resolution),
+                ("Commentary", &attempt.commentary),
+                ("Resources", &attempt.resources),
+            ] {
+                if let Some(text) = text.as_deref().filter(|t| !t.trim().is_empty()) {
+                    note.push_str(&format!("    - **{}:** {}\n", label, indented(text, "      ")));
+                }
+            }
+        }
+        note.push('\n');
+    }
+
+    note.push_str(NOTES_MARKER);
+    note.push('\n');
+    note
+}
+
+fn subject_note(subject: &str, description: Option<&str>, materials: &[(String, usize, usize)]) -> String {
+    let mut note = format!("---\nsubject: {}\ntags: [\"mastery/subject\"]\n---\n\n# {}\n\n", yaml(subject), subject);
+    if let Some(description) = description.filter(|d| !d.trim().is_empty()) {
+        note.push_str(description.trim());
+        note.push_str("\n\n");
+    }
+    note.push_str("## Materials\n\n");
+    if materials.is_empty() {
+        note.push_str("_No materials yet._\n");
+    }
+    for (material, solved, total) in materials {
+        note.push_str(&format!("- {} — {}/{} solved\n", link(MATERIALS_DIR, material), solved, total));
+    }
+    note
+}
+
+fn material_note(material: &str, name_ru: Option<&str>, subjects: &[String], problems: &[&VaultProblem]) -> String {
+    let mut note = format!("---\nmaterial: {}\n", yaml(material));
+    if let Some(name_ru) = name_ru {
+        note.push_str(&format!("name_ru: {}\n", yaml(name_ru)));
+    }
+    note.push_str(&format!("subjects: {}\ntags: [\"mastery/material\"]\n---\n\n# {}\n\n", yaml_list(subjects), material));
+
+    if !subjects.is_empty() {
+        let links: Vec<String> = subjects.iter().map(|s| link(SUBJECTS_DIR, s)).collect();
+        note.push_str(&format!("**Subjects:** {}\n\n", links.join(", ")));
+    }
+
+    note.push_str("## Problems\n\n");
+    if problems.is_empty() {
+        note.push_str("_No problems yet._\n");
+    } else {
+        note.push_str("| Problem | Title | Status | Attempts |\n|---|---|---|---|\n");
+        for problem in problems {
+            note.push_str(&format!(
+                "| [[{}/{}\\|{}]] | {} | {} | {} |\n",
+                PROBLEMS_DIR,
+                note_name(&problem.generated_id),
+                problem.generated_id,
+                problem.title.replace('|', "\\|"),
+                if problem.is_solved { "✅" } else { "⏳" },
+                problem.attempts().count(),
+            ));
+        }
+    }
+    note
+}
+
+// Write `content` unless the file already has exactly that. Returns whether
+// the file changed.
+fn write_if_changed(path: &Path, content: &[u8]) -> Result<bool, String> {
+    if fs::read(path).map(|existing| existing == content).unwrap_or(false) {
+        return Ok(false);
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(true)
+}
+
+// Regenerated part plus whatever the user wrote below the marker last time
+fn merge_user_notes(path: &Path, generated: String) -> String {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    match existing.find(NOTES_MARKER) {
+        Some(index) => {
+            let user_notes = &existing[index + NOTES_MARKER.len()..];
+            format!("{}{}", generated.trim_end_matches('\n'), user_notes)
+        }
+        None => generated,
+    }
+}
+
+pub fn export(conn: &Connection, app_dir: &Path, vault_dir: &Path) -> Result<VaultExportSummary, String> {
+    for dir in [PROBLEMS_DIR, SUBJECTS_DIR, MATERIALS_DIR, ATTACHMENTS_DIR] {
+        fs::create_dir_all(vault_dir.join(dir)).map_err(|e| e.to_string())?;
+    }
+
+    let mut summary = VaultExportSummary::default();
+    let mut record = |changed: bool| if changed { summary.written += 1 } else { summary.unchanged += 1 };
+
+    let problems = load_problems(conn)?;
+    let images_dir = app_dir.join(IMAGES_DIR_NAME);
+    let mut images_copied = 0;
+    let mut missing_images = Vec::new();
+
+    for problem in &problems {
+        let path = vault_dir.join(PROBLEMS_DIR).join(format!("{}.md", note_name(&problem.generated_id)));
+        let content = merge_user_notes(&path, problem_note(problem));
+        record(write_if_changed(&path, content.as_bytes())?);
+
+        if let Some(image) = &problem.image_filename {
+            match fs::read(images_dir.join(image)) {
+                Ok(bytes) => {
+                    if write_if_changed(&vault_dir.join(ATTACHMENTS_DIR).join(image), &bytes)? {
+                        images_copied += 1;
+                    }
+                }
+                Err(_) => missing_images.push(image.clone()),
+            }
+        }
+    }
+
+    let materials: Vec<(i64, String, Option<String>)> = conn.prepare(
+        "SELECT id, name_en, name_ru FROM Materials ORDER BY name_en"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut material_stats: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (material_id, name_en, name_ru) in &materials {
+        let subjects: Vec<String> = conn.prepare(
+            "SELECT s.name FROM SubjectMaterials sm JOIN Subjects s ON sm.subject_id = s.id
+             WHERE sm.material_id = ?1 ORDER BY s.name"
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![material_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+        let material_problems: Vec<&VaultProblem> = problems.iter().filter(|p| &p.material == name_en).collect();
+        material_stats.insert(name_en.clone(), (
+            material_problems.iter().filter(|p| p.is_solved).count(),
+            material_problems.len(),
+        ));
+
+        let path = vault_dir.join(MATERIALS_DIR).join(format!("{}.md", note_name(name_en)));
+        let content = material_note(name_en, name_ru.as_deref(), &subjects, &material_problems);
+        record(write_if_changed(&path, content.as_bytes())?);
+    }
+
+    let subjects: Vec<(i64, String, Option<String>)> = conn.prepare(
+        "SELECT id, name, description FROM Subjects ORDER BY name"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    for (subject_id, name, description) in &subjects {
+        let material_names: Vec<String> = conn.prepare(
+            "SELECT m.name_en FROM SubjectMaterials sm JOIN Materials m ON sm.material_id = m.id
+             WHERE sm.subject_id = ?1 ORDER BY m.name_en"
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![subject_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+        let rows: Vec<(String, usize, usize)> = material_names.into_iter()
+            .map(|m| {
+                let (solved, total) = material_stats.get(&m).copied().unwrap_or((0, 0));
+                (m, solved, total)
+            })
+            .collect();
+
+        let path = vault_dir.join(SUBJECTS_DIR).join(format!("{}.md", note_name(name)));
+        let content = subject_note(name, description.as_deref(), &rows);
+        record(write_if_changed(&path, content.as_bytes())?);
+    }
+
+    summary.problems = problems.len();
+    summary.materials = materials.len();
+    summary.subjects = subjects.len();
+    summary.images_copied = images_copied;
+    summary.missing_images = missing_images;
+    Ok(summary)
+}
@@ -0,0 +1,6 @@
+// Moving data in and out of the app in formats other than full backups
+pub mod dataset;
+pub mod attempts_csv;
+pub mod anki;
+pub mod vocabulary;
+pub mod markdown;
@@ -0,0 +1,427 @@
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::russian::morphology;
+use crate::scheduling::{sm2, Grade};
+use crate::utils::time::format_sqlite_time;
+use super::anki::{self, PackageCard, PackageNote, PackageReview};
+
+// A field of an Anki note or a column of a word list, by name or by
+// zero-based position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldRef {
+    Index(usize),
+    Name(String),
+}
+
+impl FieldRef {
+    fn resolve(&self, names: &[String]) -> Option<usize> {
+        match self {
+            FieldRef::Index(index) => Some(*index),
+            FieldRef::Name(name) => names.iter().position(|n| n.trim().eq_ignore_ascii_case(name.trim())),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            FieldRef::Index(index) => format!("#{}", index),
+            FieldRef::Name(name) => format!("'{}'", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyFieldMapping {
+    pub word_ru: FieldRef,
+    pub translation_en: FieldRef,
+    pub example_sentence: Option<FieldRef>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    // Keep the existing word untouched
+    Skip,
+    // Replace translation, example, material and review state
+    Overwrite,
+    // Add the new translation to the existing one if it is not there yet
+    AppendTranslation,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyImportOptions {
+    pub mapping: VocabularyFieldMapping,
+    // Existing material every imported word is linked to
+    pub material_name: Option<String>,
+    pub duplicate_policy: DuplicatePolicy,
+    // Word lists only; defaults to tab for .txt/.tsv and ',' otherwise
+    pub delimiter: Option<char>,
+    // Word lists only
+    #[serde(default = "default_true")]
+    pub has_header: bool,
+    // Anki packages only: bring over intervals, ease and the review log
+    #[serde(default = "default_true")]
+    pub import_reviews: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct VocabularyImportSummary {
+    pub rows: usize,
+    pub inserted: usize,
+    pub updated: usize,
+    // New words recorded as forms of an existing entry
+    pub grouped: usize,
+    pub skipped_duplicates: usize,
+    pub skipped_invalid: usize,
+    pub reviews_imported: usize,
+    // Why rows were skipped, e.g. "note 1650000000000: no value for 'Front'"
+    pub warnings: Vec<String>,
+}
+
+struct ImportedWord {
+    source: String,
+    word_ru: String,
+    translation_en: String,
+    example_sentence: Option<String>,
+    card: Option<PackageCard>,
+    reviews: Vec<PackageReview>,
+}
+
+fn field_value(values: &[String], names: &[String], field: &FieldRef) -> Option<String> {
+    field.resolve(names)
+        .and_then(|index| values.get(index))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn map_fields(
+    source: String,
+    values: &[String],
+    names: &[String],
+    mapping: &VocabularyFieldMapping,
+    summary: &mut VocabularyImportSummary,
+) -> Option<(String, String, Option<String>)> {
+    let word_ru = field_value(values, names, &mapping.word_ru);
+    let translation_en = field_value(values, names, &mapping.translation_en);
+
+    match (word_ru, translation_en) {
+        (Some(word_ru), Some(translation_en)) => {
+            let example = mapping.example_sentence.as_ref()
+                .and_then(|field| field_value(values, names, field));
+            Some((word_ru, translation_en, example))
+        }
+        (word_ru, _) => {
+            let missing = if word_ru.is_none() { &mapping.word_ru } else { &mapping.translation_en };
+            summary.skipped_invalid += 1;
+            summary.warnings.push(format!("{}: no value for {}", source, missing.describe()));
+            None
+        }
+    }
+}
+
+fn read_word_list(
+    path: &Path,
+    options: &VocabularyImportOptions,
+    summary: &mut VocabularyImportSummary,
+) -> Result<Vec<ImportedWord>, String> {
+    let is_tab_separated = matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("txt") | Some("tsv")
+    );
+    let delimiter = options.delimiter.unwrap_or(if is_tab_separated { '\t' } else { ',' });
+    if !delimiter.is_ascii() {
+        return Err("Delimiter must be a single ASCII character".to_string());
+    }
+
+    // Anki's plain-text exports start with "#separator:tab" style lines
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .has_headers(options.has_header)
+        .flexible(true)
+        .comment(Some(b'#'))
+        .from_path(path)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+
+    let headers: Vec<String> = if options.has_header {
+        reader.headers().map_err(|e| e.to_string())?.iter().map(str::to_string).collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut words = Vec::new();
+    for result in reader.records() {
+        let record = result.map_err(|e| e.to_string())?;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        summary.rows += 1;
+
+        let source = format!("line {}", record.position().map(|p| p.line()).unwrap_or(0));
+        let values: Vec<String> = record.iter().map(str::to_string).collect();
+        if let Some((word_ru, translation_en, example_sentence)) =
+            map_fields(source.clone(), &values, &headers, &options.mapping, summary)
+        {
+            words.push(ImportedWord { source, word_ru, translation_en, example_sentence, card: None, reviews: Vec::new() });
+        }
+    }
+    Ok(words)
+}
+
+fn package_words(
+    notes: Vec<PackageNote>,
+    options: &VocabularyImportOptions,
+    summary: &mut VocabularyImportSummary,
+) -> Vec<ImportedWord> {
+    let mut words = Vec::new();
+    for note in notes {
+        summary.rows += 1;
+        let source = format!("note {}", note.note_id);
+        if let Some((word_ru, translation_en, example_sentence)) =
+            map_fields(source.clone(), &note.fields, &note.field_names, &options.mapping, summary)
+        {
+            words.push(ImportedWord {
+                source,
+                word_ru,
+                translation_en,
+                example_sentence,
+                card: if options.import_reviews { note.card } else { None },
+                reviews: if options.import_reviews { note.reviews } else { Vec::new() },
+            });
+        }
+    }
+    words
+}
+
+fn grade_for_button(button: i64) -> Grade {
+    match button {
+        1 => Grade::Again,
+        2 => Grade::Hard,
+        4 => Grade::Easy,
+        _ => Grade::Good,
+    }
+}
+
+// Our scheduling state from an Anki card. SM-2 `repetitions` counts the
+// current streak of passed reviews, not all reviews.
+struct ReviewState {
+    ease: f64,
+    interval_days: f64,
+    repetitions: i64,
+    lapses: i64,
+    due_at: Option<String>,
+    last_reviewed: Option<String>,
+    review_count: i64,
+}
+
+fn review_state(card: &PackageCard, reviews: &[PackageReview]) -> ReviewState {
+    let ease = if card.factor > 0 {
+        (card.factor as f64 / 1000.0).max(sm2::MIN_EASE)
+    } else {
+        sm2::INITIAL_EASE
+    };
+
+    let repetitions = if reviews.is_empty() {
+        if card.card_type == 2 { card.repetitions } else { 0 }
+    } else {
+        reviews.iter().rev().take_while(|r| r.button > 1).count() as i64
+    };
+
+    ReviewState {
+        ease,
+        interval_days: if card.card_type == 0 { 0.0 } else { card.interval_days },
+        repetitions,
+        lapses: card.lapses,
+        due_at: card.due_at.as_ref().map(format_sqlite_time),
+        last_reviewed: reviews.last().map(|r| format_sqlite_time(&r.reviewed_at)),
+        review_count: if reviews.is_empty() { card.repetitions } else { reviews.len() as i64 },
+    }
+}
+
+fn apply_review_state(conn: &Connection, vocabulary_id: i64, state: &ReviewState) -> Result<(), String> {
+    conn.execute(
+        "UPDATE RussianVocabulary
+         SET ease = ?1, interval_days = ?2, repetitions = ?3, lapses = ?4, due_at = ?5,
+             last_reviewed = COALESCE(?6, last_reviewed), review_count = ?7
+         WHERE id = ?8",
+        params![
+            state.ease,
+            state.interval_days,
+            state.repetitions,
+            state.lapses,
+            &state.due_at,
+            &state.last_reviewed,
+            state.review_count,
+            vocabulary_id,
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Adds log entries not already present; returns how many were added
+fn import_reviews(conn: &Connection, vocabulary_id: i64, reviews: &[PackageReview]) -> Result<usize, String> {
+    let mut added = 0;
+    for review in reviews {
+        let reviewed_at = format_sqlite_time(&review.reviewed_at);
+        let changed = conn.execute(
+            "INSERT INTO VocabularyReviews (vocabulary_id, grade, interval_days, ease, reviewed_at)
+             SELECT ?1, ?2, ?3, ?4, ?5
+             WHERE NOT EXISTS (SELECT 1 FROM VocabularyReviews WHERE vocabulary_id = ?1 AND reviewed_at = ?5)",
+            params![
+                vocabulary_id,
+                grade_for_button(review.button).as_str(),
+                review.interval_days,
+                if review.factor > 0 { review.factor as f64 / 1000.0 } else { sm2::INITIAL_EASE },
+                &reviewed_at,
+            ],
+        ).map_err(|e| e.to_string())?;
+        added += changed;
+    }
+    Ok(added)
+}
+
+fn first_review(reviews: &[PackageReview]) -> Option<NaiveDateTime> {
+    reviews.first().map(|r| r.reviewed_at)
+}
+
+fn has_translation(existing: &str, translation: &str) -> bool {
+    existing.split([';', ','])
+        .any(|part| part.trim().to_lowercase() == translation.trim().to_lowercase())
+}
+
+fn upsert_word(
+    conn: &Connection,
+    word: &ImportedWord,
+    material_id: Option<i64>,
+    policy: DuplicatePolicy,
+    summary: &mut VocabularyImportSummary,
+) -> Result<(), String> {
+    let existing: Option<(i64, String)> = conn.query_row(
+        "SELECT id, translation_en FROM RussianVocabulary WHERE word_ru = ?1",
+        params![&word.word_ru],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional().map_err(|e| e.to_string())?;
+
+    let state = word.card.as_ref().map(|card| review_state(card, &word.reviews));
+
+    let vocabulary_id = match (existing, policy) {
+        (Some(_), DuplicatePolicy::Skip) => {
+            summary.skipped_duplicates += 1;
+            return Ok(());
+        }
+        (Some((id, _)), DuplicatePolicy::Overwrite) => {
+            conn.execute(
+                "UPDATE RussianVocabulary
+                 SET translation_en = ?1,
+                     example_sentence = COALESCE(?2, example_sentence),
+                     material_id = COALESCE(?3, material_id)
+                 WHERE id = ?4",
+                params![&word.translation_en, &word.example_sentence, material_id, id],
+            ).map_err(|e| e.to_string())?;
+            if let Some(state) = &state {
+                apply_review_state(conn, id, state)?;
+            }
+            summary.updated += 1;
+            id
+        }
+        (Some((id, translation)), DuplicatePolicy::AppendTranslation) => {
+            if has_translation(&translation, &word.translation_en) {
+                summary.skipped_duplicates += 1;
+                return Ok(());
+            }
+            // The existing word keeps its own review state
+            conn.execute(
+                "UPDATE RussianVocabulary
+                 SET translation_en = ?1,
+                     example_sentence = COALESCE(example_sentence, ?2),
+                     material_id = COALESCE(material_id, ?3)
+                 WHERE id = ?4",
+                params![format!("{}; {}", translation, word.translation_en), &word.example_sentence, material_id, id],
+            ).map_err(|e| e.to_string())?;
+            summary.updated += 1;
+            return Ok(());
+        }
+        (None, _) => {
+            // Forms of an existing entry are grouped under it, the same way
+            // as words added by hand
+            let (id, grouped) = morphology::insert_vocabulary(
+                conn,
+                &word.word_ru,
+                &word.translation_en,
+                material_id,
+                word.example_sentence.as_deref(),
+                false,
+                None,
+            )?;
+            if grouped {
+                // The entry keeps its own review state
+                summary.grouped += 1;
+                return Ok(());
+            }
+
+            // A word first seen in Anki keeps its original date
+            if let Some(first_seen) = first_review(&word.reviews) {
+                conn.execute(
+                    "UPDATE RussianVocabulary SET first_seen = ?1 WHERE id = ?2",
+                    params![format_sqlite_time(&first_seen), id],
+                ).map_err(|e| e.to_string())?;
+            }
+            if let Some(state) = &state {
+                apply_review_state(conn, id, state)?;
+            }
+            summary.inserted += 1;
+            id
+        }
+    };
+
+    if state.is_some() {
+        summary.reviews_imported += import_reviews(conn, vocabulary_id, &word.reviews)?;
+    }
+    Ok(())
+}
+
+fn is_anki_package(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("apkg") | Some("colpkg")
+    )
+}
+
+// Import words from an Anki package or a delimited word list. Run inside a
+// transaction. `work_dir` is scratch space for unpacking Anki collections.
+pub fn import(
+    conn: &Connection,
+    path: &Path,
+    work_dir: &Path,
+    options: &VocabularyImportOptions,
+) -> Result<VocabularyImportSummary, String> {
+    let material_id: Option<i64> = match &options.material_name {
+        Some(name) => Some(conn.query_row(
+            "SELECT id FROM Materials WHERE name_en = ?1",
+            params![name],
+            |row| row.get(0)
+        ).optional().map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Material '{}' not found", name))?),
+        None => None,
+    };
+
+    let mut summary = VocabularyImportSummary::default();
+    let words = if is_anki_package(path) {
+        let notes = anki::read_package(path, work_dir)?;
+        package_words(notes, options, &mut summary)
+    } else {
+        read_word_list(path, options, &mut summary)?
+    };
+
+    for word in &words {
+        upsert_word(conn, word, material_id, options.duplicate_policy, &mut summary)
+            .map_err(|e| format!("{} ({}): {}", word.source, word.word_ru, e))?;
+    }
+
+    Ok(summary)
+}
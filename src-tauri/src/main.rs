@@ -4,6 +4,17 @@
 mod db;
 mod commands;
 mod utils;
+mod scheduling;
+mod mastery;
+mod segmentation;
+mod backup;
+mod interchange;
+mod search;
+mod classification;
+mod recurrence;
+mod resources;
+mod catalog;
+mod russian;
 
 use tauri::Manager;
 
@@ -12,6 +23,7 @@ fn main() {
         .setup(|app| {
             let db = db::init_database(&app.handle())?;
             app.manage(db);
+            backup::auto::start(app.handle());
             println!("✅ App setup complete");
             Ok(())
         })
@@ -34,8 +46,86 @@ fn main() {
     commands::russian::add_vocabulary,  // ADD THESE
 commands::russian::get_all_vocabulary,
 commands::russian::search_vocabulary,
+commands::russian::find_vocabulary_group,
+commands::russian::update_vocabulary_grammar,
+commands::russian::query_vocabulary,
+commands::russian::add_vocabulary_batch,
 commands::russian::log_drill_attempt,
+commands::russian::extract_drill_vocabulary,
 commands::russian::get_drill_history,
+commands::russian::score_drill,
+commands::russian::generate_form_drill,
+commands::russian::check_form_answer,
+commands::russian::get_form_drill,
+commands::russian::convert_script,
+commands::russian::add_material_term,
+commands::russian::remove_material_term,
+commands::russian::get_material_terms,
+commands::russian::get_material_russian_readiness,
+commands::russian::review_vocabulary,
+commands::russian::get_due_vocabulary,
+    commands::scheduling::get_scheduler_settings,
+    commands::scheduling::update_scheduler_settings,
+    commands::scheduling::recompute_schedules,
+    commands::scheduling::get_due_problems,
+    commands::mastery::get_mastery_policies,
+    commands::mastery::get_mastery_policy,
+    commands::mastery::set_mastery_policy,
+    commands::mastery::delete_mastery_policy,
+    commands::mastery::recompute_solved_status,
+    commands::classification::get_error_categories,
+    commands::classification::create_error_category,
+    commands::classification::update_error_category,
+    commands::classification::delete_error_category,
+    commands::classification::add_error_rule,
+    commands::classification::delete_error_rule,
+    commands::classification::set_attempt_error_categories,
+    commands::classification::get_attempt_error_categories,
+    commands::classification::reclassify_attempts,
+    commands::classification::get_error_breakdown,
+    commands::recurrence::get_recurring_mistakes,
+    commands::resources::get_resources,
+    commands::resources::create_resource,
+    commands::resources::update_resource,
+    commands::resources::delete_resource,
+    commands::resources::merge_resources,
+    commands::resources::find_duplicate_resources,
+    commands::resources::get_attempt_resources,
+    commands::resources::set_attempt_resource,
+    commands::resources::unlink_attempt_resource,
+    commands::resources::get_resource_effectiveness,
+    commands::catalog::get_subject_details,
+    commands::catalog::get_material_details,
+    commands::catalog::create_subject,
+    commands::catalog::update_subject,
+    commands::catalog::preview_subject_deletion,
+    commands::catalog::delete_subject,
+    commands::catalog::create_material,
+    commands::catalog::update_material,
+    commands::catalog::merge_materials,
+    commands::catalog::link_material_to_subject,
+    commands::catalog::unlink_material_from_subject,
+    commands::catalog::preview_material_deletion,
+    commands::catalog::delete_material,
+    commands::catalog::move_problem,
+    commands::catalog::merge_problems,
+    commands::batches::get_segmentation_settings,
+    commands::batches::update_segmentation_settings,
+    commands::batches::close_batch,
+    commands::batches::resegment_problem,
+    commands::backup::create_backup,
+    commands::backup::restore_backup,
+    commands::backup::list_backups,
+    commands::backup::restore_backup_by_id,
+    commands::backup::get_auto_backup_settings,
+    commands::backup::update_auto_backup_settings,
+    commands::interchange::export_dataset,
+    commands::interchange::import_dataset,
+    commands::interchange::import_attempts_csv,
+    commands::interchange::export_anki,
+    commands::interchange::import_vocabulary,
+    commands::interchange::export_markdown_vault,
+    commands::search::global_search,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
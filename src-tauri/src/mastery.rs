@@ -0,0 +1,373 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// When a problem counts as solved. Only successes inside the current window
+// count: everything since the last failure when `reset_on_failure` is set,
+// otherwise the whole history. A success only qualifies if it also meets the
+// time and difficulty limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MasteryPolicy {
+    // None for the global default
+    pub subject_name: Option<String>,
+    pub required_successes: i32,
+    pub min_distinct_batches: i32,
+    pub min_distinct_days: i32,
+    pub max_time_minutes: Option<f64>,
+    pub max_difficulty: Option<i32>,
+    pub require_fresh_start_success: bool,
+    pub reset_on_failure: bool,
+}
+
+impl Default for MasteryPolicy {
+    fn default() -> Self {
+        MasteryPolicy {
+            subject_name: None,
+            required_successes: 5,
+            min_distinct_batches: 1,
+            min_distinct_days: 1,
+            max_time_minutes: None,
+            max_difficulty: None,
+            require_fresh_start_success: false,
+            reset_on_failure: true,
+        }
+    }
+}
+
+impl MasteryPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.required_successes < 1 {
+            return Err("Required successes must be at least 1".to_string());
+        }
+        if self.min_distinct_batches < 1 || self.min_distinct_days < 1 {
+            return Err("Distinct batch and day minimums must be at least 1".to_string());
+        }
+        if let Some(minutes) = self.max_time_minutes {
+            if minutes <= 0.0 {
+                return Err("Time limit must be greater than 0".to_string());
+            }
+        }
+        if let Some(difficulty) = self.max_difficulty {
+            if !(1..=5).contains(&difficulty) {
+                return Err("Difficulty limit must be between 1 and 5".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AttemptRecord {
+    pub batch_id: i64,
+    pub is_fresh_start: bool,
+    pub successful: bool,
+    pub time_spent_minutes: Option<f64>,
+    pub difficulty_rating: Option<i32>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecomputeSummary {
+    pub evaluated: usize,
+    pub newly_solved: usize,
+    pub newly_unsolved: usize,
+}
+
+// `attempts` must be in chronological order
+pub fn is_mastered(policy: &MasteryPolicy, attempts: &[AttemptRecord]) -> bool {
+    let window = if policy.reset_on_failure {
+        match attempts.iter().rposition(|a| !a.successful) {
+            Some(last_failure) => &attempts[last_failure + 1..],
+            None => attempts,
+        }
+    } else {
+        attempts
+    };
+
+    let qualifying: Vec<&AttemptRecord> = window.iter()
+        .filter(|a| a.successful)
+        .filter(|a| match (policy.max_time_minutes, a.time_spent_minutes) {
+            (Some(limit), Some(minutes)) => minutes <= limit,
+            // An unrecorded time can't prove the limit was met
+            (Some(_), None) => false,
+            (None, _) => true,
+        })
+        .filter(|a| match (policy.max_difficulty, a.difficulty_rating) {
+            (Some(limit), Some(rating)) => rating <= limit,
+            (Some(_), None) => false,
+            (None, _) => true,
+        })
+        .collect();
+
+    let distinct_batches: HashSet<i64> = qualifying.iter().map(|a| a.batch_id).collect();
+    // Timestamps are "YYYY-MM-DD HH:MM:SS"; the day is the date part
+    let distinct_days: HashSet<&str> = qualifying.iter()
+        .map(|a| a.timestamp.get(..10).unwrap_or(&a.timestamp))
+        .collect();
+
+    qualifying.len() >= policy.required_successes as usize
+        && distinct_batches.len() >= policy.min_distinct_batches as usize
+        && distinct_days.len() >= policy.min_distinct_days as usize
+        && (!policy.require_fresh_start_success || qualifying.iter().any(|a| a.is_fresh_start))
+}
+
+// Index of the attempt at which `policy` was first met, i.e. the shortest
+// prefix of `attempts` that is mastered. `attempts` must be in chronological
+// order.
+pub fn mastered_at(policy: &MasteryPolicy, attempts: &[AttemptRecord]) -> Option<usize> {
+    (0..attempts.len()).find(|&i| is_mastered(policy, &attempts[..=i]))
+}
+
+const POLICY_COLUMNS: &str =
+    "s.name, mp.required_successes, mp.min_distinct_batches, mp.min_distinct_days,
+     mp.max_time_minutes, mp.max_difficulty, mp.require_fresh_start_success, mp.reset_on_failure";
+
+fn policy_from_row(row: &rusqlite::Row) -> rusqlite::Result<MasteryPolicy> {
+    Ok(MasteryPolicy {
+        subject_name: row.get(0)?,
+        required_successes: row.get(1)?,
+        min_distinct_batches: row.get(2)?,
+        min_distinct_days: row.get(3)?,
+        max_time_minutes: row.get(4)?,
+        max_difficulty: row.get(5)?,
+        require_fresh_start_success: row.get(6)?,
+        reset_on_failure: row.get(7)?,
+    })
+}
+
+pub fn list_policies(conn: &Connection) -> Result<Vec<MasteryPolicy>, String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM MasteryPolicies mp
+         LEFT JOIN Subjects s ON mp.subject_id = s.id
+         ORDER BY mp.subject_id IS NOT NULL, s.name",
+        POLICY_COLUMNS
+    )).map_err(|e| e.to_string())?;
+
+    let policies = stmt.query_map([], policy_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(policies)
+}
+
+// The subject's own policy if it has one, otherwise the global default
+pub fn policy_for_subject(conn: &Connection, subject_name: Option<&str>) -> Result<MasteryPolicy, String> {
+    let policy = conn.query_row(
+        &format!(
+            "SELECT {}
+             FROM MasteryPolicies mp
+             LEFT JOIN Subjects s ON mp.subject_id = s.id
+             WHERE s.name = ?1 OR mp.subject_id IS NULL
+             ORDER BY mp.subject_id IS NULL
+             LIMIT 1",
+            POLICY_COLUMNS
+        ),
+        params![subject_name],
+        policy_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?;
+
+    Ok(policy.unwrap_or_default())
+}
+
+// A material can belong to several subjects; the lowest subject id with its
+// own policy wins
+pub fn policy_for_problem(conn: &Connection, problem_id: i64) -> Result<MasteryPolicy, String> {
+    let policy = conn.query_row(
+        &format!(
+            "SELECT {}
+             FROM MasteryPolicies mp
+             LEFT JOIN Subjects s ON mp.subject_id = s.id
+             WHERE mp.subject_id IS NULL
+                OR mp.subject_id IN (
+                    SELECT sm.subject_id FROM Problems p
+                    JOIN SubjectMaterials sm ON sm.material_id = p.material_id
+                    WHERE p.id = ?1
+                )
+             ORDER BY mp.subject_id IS NULL, mp.subject_id
+             LIMIT 1",
+            POLICY_COLUMNS
+        ),
+        params![problem_id],
+        policy_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?;
+
+    Ok(policy.unwrap_or_default())
+}
+
+pub fn save_policy(conn: &Connection, policy: &MasteryPolicy) -> Result<(), String> {
+    policy.validate()?;
+
+    let subject_id: Option<i64> = match &policy.subject_name {
+        Some(name) => Some(conn.query_row(
+            "SELECT id FROM Subjects WHERE name = ?1",
+            params![name],
+            |row| row.get(0)
+        ).map_err(|_| format!("Subject '{}' not found", name))?),
+        None => None,
+    };
+
+    conn.execute(
+        "INSERT INTO MasteryPolicies
+         (subject_id, required_successes, min_distinct_batches, min_distinct_days,
+          max_time_minutes, max_difficulty, require_fresh_start_success, reset_on_failure)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(COALESCE(subject_id, 0)) DO UPDATE SET
+            required_successes = excluded.required_successes,
+            min_distinct_batches = excluded.min_distinct_batches,
+            min_distinct_days = excluded.min_distinct_days,
+            max_time_minutes = excluded.max_time_minutes,
+            max_difficulty = excluded.max_difficulty,
+            require_fresh_start_success = excluded.require_fresh_start_success,
+            reset_on_failure = excluded.reset_on_failure,
+            updated_at = datetime('now')",
+        params![
+            subject_id,
+            policy.required_successes,
+            policy.min_distinct_batches,
+            policy.min_distinct_days,
+            policy.max_time_minutes,
+            policy.max_difficulty,
+            policy.require_fresh_start_success,
+            policy.reset_on_failure,
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn problem_attempts(conn: &Connection, problem_id: i64) -> Result<Vec<AttemptRecord>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT a.batch_id, b.is_fresh_start, a.successful, a.time_spent_minutes,
+                a.difficulty_rating, a.timestamp
+         FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         WHERE b.problem_id = ?1
+         ORDER BY a.timestamp ASC, a.id ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let attempts = stmt.query_map(params![problem_id], |row| {
+        Ok(AttemptRecord {
+            batch_id: row.get(0)?,
+            is_fresh_start: row.get(1)?,
+            successful: row.get(2)?,
+            time_spent_minutes: row.get(3)?,
+            difficulty_rating: row.get(4)?,
+            timestamp: row.get(5)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(attempts)
+}
+
+// Re-evaluate a problem against its policy and store the result.
+// Returns (was_solved, is_solved).
+pub fn update_solved_status(conn: &Connection, problem_id: i64) -> Result<(bool, bool), String> {
+    let was_solved: bool = conn.query_row(
+        "SELECT is_solved FROM Problems WHERE id = ?1",
+        params![problem_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let policy = policy_for_problem(conn, problem_id)?;
+    let attempts = problem_attempts(conn, problem_id)?;
+    let is_solved = is_mastered(&policy, &attempts);
+
+    if is_solved != was_solved {
+        conn.execute(
+            "UPDATE Problems SET is_solved = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![is_solved, problem_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok((was_solved, is_solved))
+}
+
+// Re-evaluate every problem of a subject, or every problem when `subject_name` is None
+pub fn recompute(conn: &Connection, subject_name: Option<&str>) -> Result<RecomputeSummary, String> {
+    let problem_ids: Vec<i64> = match subject_name {
+        Some(name) => conn.prepare(
+            "SELECT DISTINCT p.id FROM Problems p
+             JOIN SubjectMaterials sm ON p.material_id = sm.material_id
+             JOIN Subjects s ON sm.subject_id = s.id
+             WHERE s.name = ?1"
+        )
+        .map_err(|e| e.to_string())?
+        .query_map(params![name], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?,
+        None => conn.prepare("SELECT id FROM Problems")
+        .map_err(|e| e.to_string())?
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?,
+    };
+
+    let mut summary = RecomputeSummary::default();
+    for problem_id in problem_ids {
+        let (was_solved, is_solved) = update_solved_status(conn, problem_id)?;
+        summary.evaluated += 1;
+        match (was_solved, is_solved) {
+            (false, true) => summary.newly_solved += 1,
+            (true, false) => summary.newly_unsolved += 1,
+            _ => {}
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attempt(successful: bool, day: u32) -> AttemptRecord {
+        AttemptRecord {
+            batch_id: 1,
+            is_fresh_start: false,
+            successful,
+            time_spent_minutes: None,
+            difficulty_rating: None,
+            timestamp: format!("2026-03-{:02} 10:00:00", day),
+        }
+    }
+
+    fn policy(required_successes: i32) -> MasteryPolicy {
+        MasteryPolicy { required_successes, ..Default::default() }
+    }
+
+    #[test]
+    fn failures_reset_the_window() {
+        let attempts = [attempt(true, 1), attempt(true, 2), attempt(false, 3), attempt(true, 4), attempt(true, 5)];
+        assert!(is_mastered(&policy(2), &attempts));
+        assert!(!is_mastered(&policy(3), &attempts));
+
+        let counting_all = MasteryPolicy { reset_on_failure: false, ..policy(3) };
+        assert!(is_mastered(&counting_all, &attempts));
+    }
+
+    #[test]
+    fn mastered_at_is_the_first_attempt_that_meets_the_policy() {
+        // Successes after mastery don't move it
+        let attempts = [attempt(false, 1), attempt(true, 2), attempt(true, 3), attempt(true, 4), attempt(true, 5)];
+        assert_eq!(mastered_at(&policy(2), &attempts), Some(2));
+        assert_eq!(mastered_at(&policy(5), &attempts), None);
+    }
+
+    #[test]
+    fn mastered_at_respects_distinct_days() {
+        let attempts = [attempt(true, 1), attempt(true, 1), attempt(true, 1), attempt(true, 2)];
+        let spread = MasteryPolicy { min_distinct_days: 2, ..policy(2) };
+        assert_eq!(mastered_at(&spread, &attempts), Some(3));
+    }
+}
@@ -0,0 +1,365 @@
+use rusqlite::{params, Connection};
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::russian::morphology;
+
+// Recurring mistakes: error texts of attempts are compared as TF-IDF vectors
+// and grouped when their cosine similarity reaches SIMILARITY_THRESHOLD,
+// across problems and materials. Groups are transitive: A~B and B~C put A, B
+// and C together. The warning for a newly logged attempt counts the other
+// attempts in its group.
+
+pub const SIMILARITY_THRESHOLD: f64 = 0.5;
+pub const DEFAULT_MIN_OCCURRENCES: usize = 2;
+// Terms used to label a cluster
+const LABEL_TERMS: usize = 3;
+
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does",
+    "doing", "for", "from", "had", "has", "have", "having", "he", "her", "here", "him", "his",
+    "how", "i", "if", "in", "into", "is", "it", "its", "just", "me", "my", "no", "not", "of",
+    "on", "once", "only", "or", "other", "our", "out", "over", "same", "she", "should", "so",
+    "some", "still", "such", "than", "that", "the", "their", "them", "then", "there", "these",
+    "they", "this", "those", "through", "to", "too", "up", "very", "was", "we", "were", "what",
+    "when", "where", "which", "while", "who", "why", "will", "with", "would", "you", "your",
+];
+
+// Stemmed terms of an error text, with the first spelling seen of each stem
+fn terms(text: &str, english: &Stemmer) -> Vec<(String, String)> {
+    morphology::normalize(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 2 && !STOPWORDS.contains(word))
+        .filter(|word| !word.chars().all(|c| c.is_ascii_digit()))
+        .map(|word| {
+            let stem = if word.is_ascii() {
+                english.stem(word).into_owned()
+            } else {
+                morphology::stem(word)
+            };
+            (stem, word.to_string())
+        })
+        .collect()
+}
+
+// How often each stem occurs in one error text
+#[derive(Default)]
+struct TermCounts {
+    counts: HashMap<String, usize>,
+    total: usize,
+}
+
+impl TermCounts {
+    fn new(stems: &[String]) -> Self {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for stem in stems {
+            *counts.entry(stem.clone()).or_default() += 1;
+        }
+        TermCounts { counts, total: stems.len() }
+    }
+
+    // Unit-length TF-IDF vector, given document frequencies over a set of
+    // `documents` texts
+    fn weights(&self, document_frequency: &HashMap<String, usize>, documents: usize) -> HashMap<String, f64> {
+        let mut weights: HashMap<String, f64> = self.counts.iter()
+            .map(|(stem, count)| {
+                let frequency = document_frequency.get(stem).copied().unwrap_or(1);
+                // Smoothed idf, so terms in every document still count a little
+                let idf = ((1.0 + documents as f64) / (1.0 + frequency as f64)).ln() + 1.0;
+                (stem.clone(), *count as f64 / self.total as f64 * idf)
+            })
+            .collect();
+        let norm = weights.values().map(|w| w * w).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            weights.values_mut().for_each(|w| *w /= norm);
+        }
+        weights
+    }
+}
+
+struct Document {
+    attempt_id: i64,
+    // Unit-length TF-IDF vector
+    weights: HashMap<String, f64>,
+}
+
+fn cosine(a: &Document, b: &Document) -> f64 {
+    let (small, large) = if a.weights.len() <= b.weights.len() { (a, b) } else { (b, a) };
+    small.weights.iter()
+        .filter_map(|(term, weight)| large.weights.get(term).map(|other| weight * other))
+        .sum()
+}
+
+// TF-IDF vectors for a set of error texts, with idf taken over the whole set
+pub struct Corpus {
+    documents: Vec<Document>,
+    spellings: HashMap<String, String>,
+}
+
+impl Corpus {
+    pub fn build(errors: &[(i64, String)]) -> Self {
+        let english = Stemmer::create(Algorithm::English);
+        let mut spellings = HashMap::new();
+        let tokenized: Vec<(i64, Vec<String>)> = errors.iter()
+            .map(|(attempt_id, text)| {
+                let stems = terms(text, &english).into_iter()
+                    .map(|(stem, word)| {
+                        spellings.entry(stem.clone()).or_insert(word);
+                        stem
+                    })
+                    .collect();
+                (*attempt_id, stems)
+            })
+            .collect();
+
+        let counts: Vec<(i64, TermCounts)> = tokenized.iter()
+            .map(|(attempt_id, stems)| (*attempt_id, TermCounts::new(stems)))
+            .collect();
+
+        let mut document_frequency: HashMap<String, usize> = HashMap::new();
+        for (_, terms) in &counts {
+            for stem in terms.counts.keys() {
+                *document_frequency.entry(stem.clone()).or_default() += 1;
+            }
+        }
+
+        let documents = counts.iter()
+            .map(|(attempt_id, terms)| Document {
+                attempt_id: *attempt_id,
+                weights: terms.weights(&document_frequency, counts.len()),
+            })
+            .collect();
+
+        Corpus { documents, spellings }
+    }
+
+    // Groups of similar documents, as indexes; singletons included
+    pub fn clusters(&self) -> Vec<Vec<usize>> {
+        let mut parent: Vec<usize> = (0..self.documents.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        for i in 0..self.documents.len() {
+            for j in i + 1..self.documents.len() {
+                if cosine(&self.documents[i], &self.documents[j]) >= SIMILARITY_THRESHOLD {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                    if a != b {
+                        parent[b] = a;
+                    }
+                }
+            }
+        }
+
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..self.documents.len() {
+            let r = root(&mut parent, i);
+            groups.entry(r).or_default().push(i);
+        }
+        groups.into_values().collect()
+    }
+
+    // The other attempts in the cluster of `attempt_id`, in id order
+    pub fn cluster_of(&self, attempt_id: i64) -> Vec<i64> {
+        let Some(index) = self.documents.iter().position(|d| d.attempt_id == attempt_id) else {
+            return Vec::new();
+        };
+        let mut members: Vec<i64> = self.clusters().into_iter()
+            .find(|cluster| cluster.contains(&index))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|i| self.documents[i].attempt_id)
+            .filter(|id| *id != attempt_id)
+            .collect();
+        members.sort_unstable();
+        members
+    }
+
+    // Highest-weighted terms across the given documents
+    fn label(&self, indexes: &[usize]) -> Vec<String> {
+        let mut totals: HashMap<&str, f64> = HashMap::new();
+        for index in indexes {
+            for (stem, weight) in &self.documents[*index].weights {
+                *totals.entry(stem.as_str()).or_default() += weight;
+            }
+        }
+        let mut ranked: Vec<(&str, f64)> = totals.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked.into_iter()
+            .take(LABEL_TERMS)
+            .map(|(stem, _)| self.spellings.get(stem).cloned().unwrap_or_else(|| stem.to_string()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistakeOccurrence {
+    pub attempt_id: i64,
+    pub problem_id: i64,
+    pub generated_id: String,
+    pub problem_title: String,
+    pub material_name: String,
+    pub successful: bool,
+    pub errors: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringMistake {
+    // Most characteristic terms of the cluster's error texts
+    pub label: Vec<String>,
+    pub occurrences: usize,
+    pub problems: usize,
+    pub materials: Vec<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    // Chronological
+    pub attempts: Vec<MistakeOccurrence>,
+}
+
+// Set on LogAttemptResponse when the logged error matches earlier ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringMistakeWarning {
+    pub previous_occurrences: usize,
+    pub similar_attempt_ids: Vec<i64>,
+    pub message: String,
+}
+
+fn error_texts(conn: &Connection, since: Option<&str>) -> Result<Vec<(i64, String)>, String> {
+    conn.prepare(
+        "SELECT id, errors FROM Attempts
+         WHERE TRIM(COALESCE(errors, '')) <> ''
+           AND (?1 IS NULL OR date(timestamp) >= date(?1))
+         ORDER BY id"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![since], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())
+}
+
+fn occurrences(conn: &Connection, attempt_ids: &[i64]) -> Result<Vec<MistakeOccurrence>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT a.id, p.id, p.generated_id, p.title, m.name_en, a.successful, a.errors, a.timestamp
+         FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         JOIN Problems p ON b.problem_id = p.id
+         JOIN Materials m ON p.material_id = m.id
+         WHERE a.id = ?1"
+    ).map_err(|e| e.to_string())?;
+
+    let mut found = Vec::with_capacity(attempt_ids.len());
+    for attempt_id in attempt_ids {
+        found.push(stmt.query_row(params![attempt_id], |row| Ok(MistakeOccurrence {
+            attempt_id: row.get(0)?,
+            problem_id: row.get(1)?,
+            generated_id: row.get(2)?,
+            problem_title: row.get(3)?,
+            material_name: row.get(4)?,
+            successful: row.get(5)?,
+            errors: row.get(6)?,
+            timestamp: row.get(7)?,
+        })).map_err(|e| e.to_string())?);
+    }
+    found.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.attempt_id.cmp(&b.attempt_id)));
+    Ok(found)
+}
+
+// Clusters of at least `min_occurrences` similar errors, most frequent first.
+// With `since` (YYYY-MM-DD), only attempts from that day on are considered.
+pub fn recurring_mistakes(
+    conn: &Connection,
+    min_occurrences: usize,
+    since: Option<&str>,
+) -> Result<Vec<RecurringMistake>, String> {
+    let min_occurrences = min_occurrences.max(2);
+    let corpus = Corpus::build(&error_texts(conn, since)?);
+
+    let mut mistakes = Vec::new();
+    for cluster in corpus.clusters() {
+        if cluster.len() < min_occurrences {
+            continue;
+        }
+
+        let attempt_ids: Vec<i64> = cluster.iter().map(|i| corpus.documents[*i].attempt_id).collect();
+        let attempts = occurrences(conn, &attempt_ids)?;
+        let problems: HashSet<i64> = attempts.iter().map(|a| a.problem_id).collect();
+        let mut materials: Vec<String> = attempts.iter().map(|a| a.material_name.clone()).collect();
+        materials.sort();
+        materials.dedup();
+
+        mistakes.push(RecurringMistake {
+            label: corpus.label(&cluster),
+            occurrences: attempts.len(),
+            problems: problems.len(),
+            materials,
+            first_seen: attempts.first().map(|a| a.timestamp.clone()).unwrap_or_default(),
+            last_seen: attempts.last().map(|a| a.timestamp.clone()).unwrap_or_default(),
+            attempts,
+        });
+    }
+
+    mistakes.sort_by(|a, b| b.occurrences.cmp(&a.occurrences).then_with(|| b.last_seen.cmp(&a.last_seen)));
+    Ok(mistakes)
+}
+
+// Whether a just-logged attempt repeats an earlier mistake: how many other
+// attempts are in the cluster its error text falls into
+pub fn check_attempt(conn: &Connection, attempt_id: i64) -> Result<Option<RecurringMistakeWarning>, String> {
+    let texts = error_texts(conn, None)?;
+    if !texts.iter().any(|(id, _)| *id == attempt_id) {
+        return Ok(None);
+    }
+
+    let similar_attempt_ids = Corpus::build(&texts).cluster_of(attempt_id);
+    let previous_occurrences = similar_attempt_ids.len();
+    if previous_occurrences == 0 {
+        return Ok(None);
+    }
+
+    let message = if previous_occurrences == 1 {
+        "You've made this mistake once before".to_string()
+    } else {
+        format!("You've made this mistake {} times before", previous_occurrences)
+    };
+
+    Ok(Some(RecurringMistakeWarning { previous_occurrences, similar_attempt_ids, message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(errors: &[&str]) -> Vec<(i64, String)> {
+        errors.iter().enumerate().map(|(i, text)| (i as i64 + 1, text.to_string())).collect()
+    }
+
+    // 1 and 3 are only similar through 2
+    const CHAIN: &[&str] = &[
+        "forgot the chain rule derivative",
+        "forgot the chain rule derivative and swapped integral bounds",
+        "swapped integral bounds",
+        "misread the question",
+    ];
+
+    #[test]
+    fn clusters_are_transitive() {
+        let corpus = Corpus::build(&texts(CHAIN));
+        let clusters = corpus.clusters();
+        assert!(clusters.iter().any(|cluster| cluster.len() == 3));
+    }
+
+    #[test]
+    fn warnings_count_the_whole_cluster() {
+        let corpus = Corpus::build(&texts(CHAIN));
+        assert_eq!(corpus.cluster_of(1), vec![2, 3]);
+        assert_eq!(corpus.cluster_of(3), vec![1, 2]);
+        assert!(corpus.cluster_of(4).is_empty());
+        assert!(corpus.cluster_of(5).is_empty());
+    }
+}
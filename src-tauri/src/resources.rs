@@ -0,0 +1,534 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use crate::mastery::{self, AttemptRecord};
+use crate::utils::time::{days_between, parse_sqlite_time};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceType {
+    Ai,
+    Book,
+    Video,
+    Web,
+    Human,
+    Other,
+}
+
+impl ResourceType {
+    pub const ALL: [ResourceType; 6] = [
+        ResourceType::Ai,
+        ResourceType::Book,
+        ResourceType::Video,
+        ResourceType::Web,
+        ResourceType::Human,
+        ResourceType::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceType::Ai => "ai",
+            ResourceType::Book => "book",
+            ResourceType::Video => "video",
+            ResourceType::Web => "web",
+            ResourceType::Human => "human",
+            ResourceType::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+}
+
+// Best guess for a resource first seen by name in a logged attempt. Links are
+// web pages (or videos); well-known assistants are AI; the rest stays other
+// until edited.
+pub fn guess_type(name: &str) -> (ResourceType, Option<String>) {
+    let lower = name.trim().to_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        let video = ["youtube.com", "youtu.be", "vimeo.com"].iter().any(|host| lower.contains(host));
+        let resource_type = if video { ResourceType::Video } else { ResourceType::Web };
+        return (resource_type, Some(name.trim().to_string()));
+    }
+
+    let assistants = ["gpt", "chatgpt", "claude", "gemini", "copilot", "deepseek", "perplexity"];
+    let words: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).collect();
+    if words.iter().any(|word| assistants.contains(word) || word.starts_with("gpt")) {
+        return (ResourceType::Ai, None);
+    }
+
+    (ResourceType::Other, None)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resource {
+    pub id: i64,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub resource_type: ResourceType,
+    pub url: Option<String>,
+    pub created_at: String,
+    // Attempts that used it
+    pub usage_count: usize,
+    pub last_used: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptResource {
+    pub resource_id: i64,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub resource_type: ResourceType,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+}
+
+const RESOURCE_COLUMNS: &str =
+    "r.id, r.name, r.type, r.url, r.created_at,
+     (SELECT COUNT(*) FROM AttemptResources ar WHERE ar.resource_id = r.id),
+     (SELECT MAX(a.timestamp) FROM AttemptResources ar JOIN Attempts a ON ar.attempt_id = a.id
+      WHERE ar.resource_id = r.id)";
+
+fn resource_from_row(row: &rusqlite::Row) -> rusqlite::Result<Resource> {
+    let resource_type: Option<String> = row.get(2)?;
+    Ok(Resource {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        resource_type: resource_type.as_deref().and_then(ResourceType::parse).unwrap_or(ResourceType::Other),
+        url: row.get(3)?,
+        created_at: row.get(4)?,
+        usage_count: row.get(5)?,
+        last_used: row.get(6)?,
+    })
+}
+
+pub fn list_resources(conn: &Connection) -> Result<Vec<Resource>, String> {
+    conn.prepare(&format!("SELECT {} FROM Resources r ORDER BY r.name COLLATE NOCASE", RESOURCE_COLUMNS))
+        .map_err(|e| e.to_string())?
+        .query_map([], resource_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_resource(conn: &Connection, resource_id: i64) -> Result<Resource, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM Resources r WHERE r.id = ?1", RESOURCE_COLUMNS),
+        params![resource_id],
+        resource_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Resource {} not found", resource_id))
+}
+
+fn clean_url(url: Option<&str>) -> Option<String> {
+    url.map(str::trim).filter(|u| !u.is_empty()).map(str::to_string)
+}
+
+// Create (resource_id None) or edit a resource
+pub fn save_resource(
+    conn: &Connection,
+    resource_id: Option<i64>,
+    name: &str,
+    resource_type: ResourceType,
+    url: Option<&str>,
+) -> Result<i64, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Resource name cannot be empty".to_string());
+    }
+
+    let taken: Option<i64> = conn.query_row(
+        "SELECT id FROM Resources WHERE name = ?1",
+        params![name],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    if taken.is_some_and(|id| Some(id) != resource_id) {
+        return Err(format!("A resource named '{}' already exists; merge them instead", name));
+    }
+
+    match resource_id {
+        Some(id) => {
+            let changed = conn.execute(
+                "UPDATE Resources SET name = ?1, type = ?2, url = ?3 WHERE id = ?4",
+                params![name, resource_type.as_str(), clean_url(url), id],
+            ).map_err(|e| e.to_string())?;
+            if changed == 0 {
+                return Err(format!("Resource {} not found", id));
+            }
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO Resources (name, type, url) VALUES (?1, ?2, ?3)",
+                params![name, resource_type.as_str(), clean_url(url)],
+            ).map_err(|e| e.to_string())?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+// Returns how many attempts lose the resource
+pub fn delete_resource(conn: &Connection, resource_id: i64) -> Result<usize, String> {
+    let resource = load_resource(conn, resource_id)?;
+    conn.execute("DELETE FROM Resources WHERE id = ?1", params![resource_id])
+        .map_err(|e| e.to_string())?;
+    Ok(resource.usage_count)
+}
+
+// Fold `merge_ids` into `keep_id`: their attempt links (and notes) move over,
+// and the kept resource takes their URL and type if it has none
+pub fn merge_resources(conn: &Connection, keep_id: i64, merge_ids: &[i64]) -> Result<Resource, String> {
+    let mut kept = load_resource(conn, keep_id)?;
+
+    for merge_id in merge_ids {
+        if *merge_id == keep_id {
+            continue;
+        }
+        let merged = load_resource(conn, *merge_id)?;
+
+        // An attempt linked to both keeps both notes
+        conn.execute(
+            "UPDATE AttemptResources AS k
+             SET notes = CASE
+                 WHEN k.notes IS NULL THEN m.notes
+                 WHEN m.notes IS NULL OR m.notes = k.notes THEN k.notes
+                 ELSE k.notes || char(10) || m.notes
+             END
+             FROM AttemptResources AS m
+             WHERE k.resource_id = ?1 AND m.resource_id = ?2 AND m.attempt_id = k.attempt_id",
+            params![keep_id, merge_id],
+        ).map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE OR IGNORE AttemptResources SET resource_id = ?1 WHERE resource_id = ?2",
+            params![keep_id, merge_id],
+        ).map_err(|e| e.to_string())?;
+
+        if kept.url.is_none() {
+            kept.url = merged.url.clone();
+        }
+        if kept.resource_type == ResourceType::Other {
+            kept.resource_type = merged.resource_type;
+        }
+
+        conn.execute("DELETE FROM Resources WHERE id = ?1", params![merge_id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    conn.execute(
+        "UPDATE Resources SET type = ?1, url = ?2 WHERE id = ?3",
+        params![kept.resource_type.as_str(), kept.url, keep_id],
+    ).map_err(|e| e.to_string())?;
+
+    load_resource(conn, keep_id)
+}
+
+// Name with case, spacing and punctuation ignored: "Chat GPT" and "chatgpt"
+// are the same resource
+fn duplicate_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn url_key(url: &str) -> String {
+    let lower = url.trim().to_lowercase();
+    let without_scheme = lower.split_once("://").map(|(_, rest)| rest).unwrap_or(&lower);
+    without_scheme.trim_start_matches("www.").trim_end_matches('/').to_string()
+}
+
+// Groups of resources that look like the same thing (same name up to case
+// and punctuation, or the same URL), most used first within each group
+pub fn find_duplicates(conn: &Connection) -> Result<Vec<Vec<Resource>>, String> {
+    let resources = list_resources(conn)?;
+
+    // Union resources sharing a name key or a URL key
+    let mut group_of: Vec<usize> = (0..resources.len()).collect();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for (index, resource) in resources.iter().enumerate() {
+        let mut keys = vec![format!("name:{}", duplicate_key(&resource.name))];
+        if let Some(url) = &resource.url {
+            keys.push(format!("url:{}", url_key(url)));
+        }
+        for key in keys {
+            match by_key.get(&key) {
+                Some(&other) => {
+                    let (from, to) = (group_of[index], group_of[other]);
+                    group_of.iter_mut().filter(|g| **g == from).for_each(|g| *g = to);
+                }
+                None => {
+                    by_key.insert(key, index);
+                }
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<Resource>> = BTreeMap::new();
+    for (index, resource) in resources.into_iter().enumerate() {
+        groups.entry(group_of[index]).or_default().push(resource);
+    }
+
+    Ok(groups.into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by(|a, b| b.usage_count.cmp(&a.usage_count).then(a.id.cmp(&b.id)));
+            group
+        })
+        .collect())
+}
+
+pub fn attempt_resources(conn: &Connection, attempt_id: i64) -> Result<Vec<AttemptResource>, String> {
+    conn.prepare(
+        "SELECT r.id, r.name, r.type, r.url, ar.notes
+         FROM AttemptResources ar
+         JOIN Resources r ON ar.resource_id = r.id
+         WHERE ar.attempt_id = ?1
+         ORDER BY r.name COLLATE NOCASE"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![attempt_id], |row| {
+        let resource_type: Option<String> = row.get(2)?;
+        Ok(AttemptResource {
+            resource_id: row.get(0)?,
+            name: row.get(1)?,
+            resource_type: resource_type.as_deref().and_then(ResourceType::parse).unwrap_or(ResourceType::Other),
+            url: row.get(3)?,
+            notes: row.get(4)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())
+}
+
+// Link a resource to an attempt (or update the link's notes)
+pub fn set_attempt_resource(
+    conn: &Connection,
+    attempt_id: i64,
+    resource_id: i64,
+    notes: Option<&str>,
+) -> Result<(), String> {
+    load_resource(conn, resource_id)?;
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM Attempts WHERE id = ?1)",
+        params![attempt_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("Attempt {} not found", attempt_id));
+    }
+
+    let notes = notes.map(str::trim).filter(|n| !n.is_empty());
+    conn.execute(
+        "INSERT INTO AttemptResources (attempt_id, resource_id, notes) VALUES (?1, ?2, ?3)
+         ON CONFLICT(attempt_id, resource_id) DO UPDATE SET notes = excluded.notes",
+        params![attempt_id, resource_id, notes],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn unlink_attempt_resource(conn: &Connection, attempt_id: i64, resource_id: i64) -> Result<(), String> {
+    let deleted = conn.execute(
+        "DELETE FROM AttemptResources WHERE attempt_id = ?1 AND resource_id = ?2",
+        params![attempt_id, resource_id],
+    ).map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Attempt {} doesn't use resource {}", attempt_id, resource_id));
+    }
+    Ok(())
+}
+
+// How a resource relates to outcomes, next to a baseline of the same
+// materials without it. Rates are shares (0-1); None when there is no data.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EffectivenessStats {
+    // Attempts that used the resource
+    pub uses: usize,
+    pub problems: usize,
+    // Share of next attempts on the same problem that succeeded, after an
+    // attempt with the resource vs. after one without it
+    pub next_attempt_success_rate: Option<f64>,
+    pub baseline_next_attempt_success_rate: Option<f64>,
+    // Solved problems that used the resource before being mastered, and
+    // how long mastering them took vs. solved problems that didn't use it
+    pub solved_problems: usize,
+    pub avg_attempts_to_mastery: Option<f64>,
+    pub baseline_attempts_to_mastery: Option<f64>,
+    pub avg_days_to_mastery: Option<f64>,
+    pub baseline_days_to_mastery: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialEffectiveness {
+    pub material_name: String,
+    pub stats: EffectivenessStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceEffectiveness {
+    pub resource_id: i64,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub resource_type: ResourceType,
+    // Across the materials it was used in
+    pub overall: EffectivenessStats,
+    pub materials: Vec<MaterialEffectiveness>,
+}
+
+struct ProblemHistory {
+    material: String,
+    solved: bool,
+    // Chronological, with the ids of the resources each attempt used
+    attempts: Vec<(AttemptRecord, HashSet<i64>)>,
+    // Attempt at which a solved problem first met its mastery policy
+    mastered_at: Option<usize>,
+}
+
+#[derive(Default)]
+struct Tally {
+    uses: usize,
+    problems: usize,
+    next: (usize, usize),
+    baseline_next: (usize, usize),
+    solved: usize,
+    mastery: (f64, f64),
+    baseline_solved: usize,
+    baseline_mastery: (f64, f64),
+}
+
+impl Tally {
+    fn add(&mut self, resource_id: i64, problem: &ProblemHistory) {
+        let uses = problem.attempts.iter().filter(|(_, r)| r.contains(&resource_id)).count();
+        self.uses += uses;
+        if uses > 0 {
+            self.problems += 1;
+        }
+
+        for pair in problem.attempts.windows(2) {
+            let counts = if pair[0].1.contains(&resource_id) { &mut self.next } else { &mut self.baseline_next };
+            counts.1 += 1;
+            if pair[1].0.successful {
+                counts.0 += 1;
+            }
+        }
+
+        let Some(mastered_at) = problem.mastered_at else {
+            return;
+        };
+        let attempts = (mastered_at + 1) as f64;
+        let first = &problem.attempts[0].0.timestamp;
+        let days = match (parse_sqlite_time(first), parse_sqlite_time(&problem.attempts[mastered_at].0.timestamp)) {
+            (Ok(first), Ok(mastered)) => days_between(&first, &mastered),
+            _ => 0.0,
+        };
+        let used_before = problem.attempts[..=mastered_at].iter().any(|(_, r)| r.contains(&resource_id));
+        if used_before {
+            self.solved += 1;
+            self.mastery.0 += attempts;
+            self.mastery.1 += days;
+        } else {
+            self.baseline_solved += 1;
+            self.baseline_mastery.0 += attempts;
+            self.baseline_mastery.1 += days;
+        }
+    }
+
+    fn stats(&self) -> EffectivenessStats {
+        let rate = |(hits, total): (usize, usize)| (total > 0).then(|| hits as f64 / total as f64);
+        let mean = |sum: f64, count: usize| (count > 0).then(|| sum / count as f64);
+        EffectivenessStats {
+            uses: self.uses,
+            problems: self.problems,
+            next_attempt_success_rate: rate(self.next),
+            baseline_next_attempt_success_rate: rate(self.baseline_next),
+            solved_problems: self.solved,
+            avg_attempts_to_mastery: mean(self.mastery.0, self.solved),
+            baseline_attempts_to_mastery: mean(self.baseline_mastery.0, self.baseline_solved),
+            avg_days_to_mastery: mean(self.mastery.1, self.solved),
+            baseline_days_to_mastery: mean(self.baseline_mastery.1, self.baseline_solved),
+        }
+    }
+}
+
+// Effectiveness of every used resource, per material, most used first.
+// With `material_name`, only that material is considered.
+pub fn effectiveness(conn: &Connection, material_name: Option<&str>) -> Result<Vec<ResourceEffectiveness>, String> {
+    let rows: Vec<(i64, String, bool, AttemptRecord, Option<String>)> = conn.prepare(
+        "SELECT p.id, m.name_en, p.is_solved, a.batch_id, b.is_fresh_start, a.successful,
+                a.time_spent_minutes, a.difficulty_rating, a.timestamp,
+                (SELECT GROUP_CONCAT(ar.resource_id) FROM AttemptResources ar WHERE ar.attempt_id = a.id)
+         FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         JOIN Problems p ON b.problem_id = p.id
+         JOIN Materials m ON p.material_id = m.id
+         WHERE (?1 IS NULL OR m.name_en = ?1)
+         ORDER BY p.id, a.timestamp ASC, a.id ASC"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![material_name], |row| {
+        let attempt = AttemptRecord {
+            batch_id: row.get(3)?,
+            is_fresh_start: row.get(4)?,
+            successful: row.get(5)?,
+            time_spent_minutes: row.get(6)?,
+            difficulty_rating: row.get(7)?,
+            timestamp: row.get(8)?,
+        };
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, attempt, row.get(9)?))
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut problems: BTreeMap<i64, ProblemHistory> = BTreeMap::new();
+    for (problem_id, material, solved, attempt, resource_ids) in rows {
+        let resource_ids: HashSet<i64> = resource_ids.as_deref().unwrap_or("")
+            .split(',')
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        problems.entry(problem_id)
+            .or_insert_with(|| ProblemHistory { material, solved, attempts: Vec::new(), mastered_at: None })
+            .attempts
+            .push((attempt, resource_ids));
+    }
+
+    for (problem_id, problem) in problems.iter_mut().filter(|(_, p)| p.solved) {
+        let policy = mastery::policy_for_problem(conn, *problem_id)?;
+        let records: Vec<AttemptRecord> = problem.attempts.iter().map(|(a, _)| a.clone()).collect();
+        problem.mastered_at = mastery::mastered_at(&policy, &records);
+    }
+
+    let mut report = Vec::new();
+    for resource in list_resources(conn)? {
+        let used_in: BTreeSet<&str> = problems.values()
+            .filter(|p| p.attempts.iter().any(|(_, r)| r.contains(&resource.id)))
+            .map(|p| p.material.as_str())
+            .collect();
+        if used_in.is_empty() {
+            continue;
+        }
+
+        let mut overall = Tally::default();
+        let mut materials = Vec::new();
+        for material in used_in {
+            let mut tally = Tally::default();
+            for problem in problems.values().filter(|p| p.material == material) {
+                tally.add(resource.id, problem);
+                overall.add(resource.id, problem);
+            }
+            materials.push(MaterialEffectiveness { material_name: material.to_string(), stats: tally.stats() });
+        }
+
+        report.push(ResourceEffectiveness {
+            resource_id: resource.id,
+            name: resource.name,
+            resource_type: resource.resource_type,
+            overall: overall.stats(),
+            materials,
+        });
+    }
+
+    report.sort_by(|a, b| b.overall.uses.cmp(&a.overall.uses).then_with(|| a.name.cmp(&b.name)));
+    Ok(report)
+}
@@ -0,0 +1,156 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use super::morphology;
+
+// Function words and pronouns that are never worth offering as vocabulary
+const STOPWORDS: &[&str] = &[
+    "а", "без", "более", "бы", "был", "была", "были", "было", "быть", "в", "вам", "вас", "весь",
+    "во", "вот", "все", "всё", "всего", "всех", "вы", "где", "да", "даже", "для", "до", "его",
+    "ее", "её", "ей", "ему", "если", "есть", "ещё", "еще", "же", "за", "здесь", "и", "из",
+    "или", "им", "их", "к", "как", "какой", "когда", "кто", "ли", "либо", "меня", "мне",
+    "может", "мы", "на", "над", "нам", "нас", "не", "него", "нее", "неё", "нет", "ни", "них",
+    "но", "ну", "о", "об", "он", "она", "они", "оно", "от", "очень", "по", "под", "при",
+    "с", "со", "так", "также", "такой", "там", "те", "тем", "то", "того", "тоже", "той",
+    "только", "том", "тот", "ту", "у", "уже", "чем", "через", "что", "чтобы", "эта", "эти",
+    "это", "этого", "этой", "этом", "этот", "эту", "я",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedWord {
+    pub vocabulary_id: i64,
+    pub word_ru: String,
+}
+
+// A word from the text with no vocabulary entry yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnknownWord {
+    // First spelling seen in the text
+    pub word: String,
+    // Every spelling seen, all sharing one lemma
+    pub forms: Vec<String>,
+    pub occurrences: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VocabularyExtraction {
+    pub linked: Vec<LinkedWord>,
+    pub unknown: Vec<UnknownWord>,
+}
+
+// Cyrillic words in `text`, lowercased, stress marks removed, in order
+pub fn tokenize(text: &str) -> Vec<String> {
+    morphology::strip_stress(text)
+        .split(|c: char| !(c.is_alphabetic() || c == '-'))
+        .map(|word| word.trim_matches('-').to_lowercase())
+        .filter(|word| word.chars().any(|c| matches!(c, 'а'..='я' | 'ё')))
+        .filter(|word| word.chars().all(|c| matches!(c, 'а'..='я' | 'ё' | '-')))
+        .collect()
+}
+
+fn is_candidate(word: &str) -> bool {
+    word.chars().count() >= 3 && !STOPWORDS.contains(&word)
+}
+
+// Match the words of `texts` against the vocabulary, lemma-aware: inflected
+// forms find their entry, and multi-word entries are found as phrases.
+// Everything else worth learning comes back as unknown, grouped by lemma and
+// most frequent first.
+pub fn extract(conn: &Connection, texts: &[&str]) -> Result<VocabularyExtraction, String> {
+    let text = texts.join("\n");
+    let tokens = tokenize(&text);
+    let stems = morphology::text_stems(&text);
+
+    let mut extraction = VocabularyExtraction::default();
+    let mut linked_ids = HashSet::new();
+    // Stems covered by a matched phrase aren't unknown on their own
+    let mut covered_stems: HashSet<String> = HashSet::new();
+
+    let phrases: Vec<(i64, String)> = conn.prepare(
+        "SELECT id, word_ru FROM RussianVocabulary WHERE word_ru LIKE '% %' ORDER BY id"
+    ).map_err(|e| e.to_string())?
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    for (id, phrase) in phrases {
+        if morphology::mentions(&stems, &phrase) {
+            covered_stems.extend(morphology::text_stems(&phrase));
+            if linked_ids.insert(id) {
+                extraction.linked.push(LinkedWord { vocabulary_id: id, word_ru: phrase });
+            }
+        }
+    }
+
+    let mut unknown: Vec<UnknownWord> = Vec::new();
+    let mut unknown_by_lemma: HashMap<String, usize> = HashMap::new();
+    let mut seen = HashSet::new();
+
+    for token in tokens {
+        let lemma = morphology::lemma_key(&token);
+        if !seen.insert(token.clone()) {
+            if let Some(&index) = unknown_by_lemma.get(&lemma) {
+                unknown[index].occurrences += 1;
+            }
+            continue;
+        }
+
+        if let Some((id, word_ru)) = morphology::find_entry(conn, &token, None)? {
+            if linked_ids.insert(id) {
+                extraction.linked.push(LinkedWord { vocabulary_id: id, word_ru });
+            }
+            continue;
+        }
+
+        if !is_candidate(&token) || covered_stems.contains(&lemma) {
+            continue;
+        }
+
+        match unknown_by_lemma.get(&lemma) {
+            Some(&index) => {
+                let word = &mut unknown[index];
+                word.occurrences += 1;
+                word.forms.push(token);
+            }
+            None => {
+                unknown_by_lemma.insert(lemma, unknown.len());
+                unknown.push(UnknownWord { word: token.clone(), forms: vec![token], occurrences: 1 });
+            }
+        }
+    }
+
+    // Stable sort keeps first-appearance order among equals
+    unknown.sort_by_key(|word| std::cmp::Reverse(word.occurrences));
+    extraction.unknown = unknown;
+
+    Ok(extraction)
+}
+
+// Link the drill to every vocabulary entry found in its text. Returns the
+// extraction, including words that were already linked.
+pub fn link_drill(conn: &Connection, drill_id: i64) -> Result<VocabularyExtraction, String> {
+    let texts: (Option<String>, Option<String>, Option<String>) = conn.query_row(
+        "SELECT errors_ru, resolution_ru, commentary FROM RussianDrillAttempts WHERE id = ?1",
+        params![drill_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Drill {} not found", drill_id))?;
+
+    let extraction = extract(conn, &[
+        texts.0.as_deref().unwrap_or(""),
+        texts.1.as_deref().unwrap_or(""),
+        texts.2.as_deref().unwrap_or(""),
+    ])?;
+
+    for word in &extraction.linked {
+        conn.execute(
+            "INSERT OR IGNORE INTO DrillVocabulary (drill_id, vocabulary_id) VALUES (?1, ?2)",
+            params![drill_id, word.vocabulary_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(extraction)
+}
@@ -0,0 +1,551 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::grammar::{self, VocabularyGrammar};
+use super::{inflection, morphology};
+
+pub const DEFAULT_COUNT: u32 = 10;
+pub const MAX_COUNT: u32 = 50;
+
+// Share of correct answers needed for a drill to count as practicing or
+// mastered (mastered also needs every exercise answered)
+const PRACTICING_ACCURACY: f64 = 0.6;
+const MASTERED_ACCURACY: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormDrillKind {
+    // Noun cases, singular and plural
+    Declension,
+    // Present (future for perfective verbs) by person
+    Conjugation,
+    Past,
+    Imperative,
+}
+
+impl FormDrillKind {
+    pub const ALL: [FormDrillKind; 4] = [
+        FormDrillKind::Declension,
+        FormDrillKind::Conjugation,
+        FormDrillKind::Past,
+        FormDrillKind::Imperative,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FormDrillKind::Declension => "declension",
+            FormDrillKind::Conjugation => "conjugation",
+            FormDrillKind::Past => "past",
+            FormDrillKind::Imperative => "imperative",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == value)
+    }
+
+    fn form_keys(&self) -> Vec<String> {
+        match self {
+            // The nominative singular is the dictionary form itself
+            FormDrillKind::Declension => inflection::noun_form_keys()
+                .into_iter()
+                .filter(|k| k != "nom_sg")
+                .collect(),
+            FormDrillKind::Conjugation => inflection::person_keys().iter().map(|k| k.to_string()).collect(),
+            FormDrillKind::Past => inflection::past_keys().iter().map(|k| k.to_string()).collect(),
+            FormDrillKind::Imperative => inflection::imperative_keys().iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    fn applies_to(&self, grammar: &VocabularyGrammar) -> bool {
+        match self {
+            FormDrillKind::Declension => inflection::is_noun(grammar),
+            _ => inflection::is_verb(grammar),
+        }
+    }
+
+    fn prompt(&self, word: &str, form: &str) -> String {
+        match self {
+            FormDrillKind::Declension => format!("Put «{}» in the {}", word, form),
+            FormDrillKind::Conjugation => format!("Conjugate «{}» in the {}", word, form),
+            FormDrillKind::Past | FormDrillKind::Imperative => format!("Give the {} of «{}»", form, word),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormExercise {
+    pub id: i64,
+    pub position: i32,
+    pub vocabulary_id: Option<i64>,
+    pub word_ru: String,
+    pub translation_en: Option<String>,
+    pub kind: FormDrillKind,
+    pub form_key: String,
+    pub prompt: String,
+    // Filled in once answered; expected answers stay server-side until then
+    pub answer: Option<String>,
+    pub correct: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormDrill {
+    // The RussianDrillAttempts row the results are recorded on
+    pub drill_id: i64,
+    pub material_name: String,
+    pub attempt_number: i32,
+    pub exercises: Vec<FormExercise>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormAnswerResult {
+    pub exercise_id: i64,
+    pub drill_id: i64,
+    pub correct: bool,
+    pub answer: String,
+    // Preferred answer and every accepted spelling
+    pub expected: String,
+    pub accepted: Vec<String>,
+    // Drill progress after this answer
+    pub answered: usize,
+    pub correct_count: usize,
+    pub total: usize,
+    pub status: String,
+}
+
+// Answers are compared ignoring case, stress marks, ё/е, surrounding
+// punctuation and repeated whitespace
+pub fn normalize_answer(answer: &str) -> String {
+    let words: Vec<String> = answer
+        .split_whitespace()
+        .map(|w| morphology::normalize(w.trim_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '\u{301}')))
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.join(" ")
+}
+
+// Small xorshift generator; drills only need a different order each time
+struct Shuffler(u64);
+
+impl Shuffler {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+        Shuffler(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+struct Candidate {
+    vocabulary_id: i64,
+    word_ru: String,
+    translation_en: String,
+    kind: FormDrillKind,
+    form_key: String,
+    prompt: String,
+    accepted: Vec<String>,
+}
+
+fn candidates(conn: &Connection, material_id: i64, kinds: &[FormDrillKind]) -> Result<Vec<Candidate>, String> {
+    let words: Vec<(i64, String, String)> = conn.prepare(
+        "SELECT id, word_ru, translation_en FROM RussianVocabulary WHERE material_id = ?1 ORDER BY id"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut candidates = Vec::new();
+    for (id, word_ru, translation_en) in words {
+        let grammar = grammar::load_grammar(conn, id)?;
+        let shown = match grammar.stress_position {
+            Some(position) => grammar::mark_stress(&word_ru, position),
+            None => word_ru.clone(),
+        };
+
+        for kind in kinds.iter().filter(|k| k.applies_to(&grammar)) {
+            for form_key in kind.form_keys() {
+                let Some(accepted) = inflection::inflect(&word_ru, &grammar, &form_key) else {
+                    continue;
+                };
+                let form = inflection::describe_form(&form_key, grammar.aspect);
+                candidates.push(Candidate {
+                    vocabulary_id: id,
+                    word_ru: word_ru.clone(),
+                    translation_en: translation_en.clone(),
+                    kind: *kind,
+                    prompt: kind.prompt(&shown, &form),
+                    form_key,
+                    accepted,
+                });
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+// Pick `count` random exercises from the material's nouns and verbs, spread
+// over as many different words as possible, and start a drill attempt for
+// them. Words need a part of speech (nouns also a gender for -ь endings);
+// infinitives are recognised as verbs without one.
+pub fn generate(
+    conn: &Connection,
+    material_name: &str,
+    count: u32,
+    kinds: &[FormDrillKind],
+) -> Result<FormDrill, String> {
+    let material_id: i64 = conn.query_row(
+        "SELECT id FROM Materials WHERE name_en = ?1",
+        params![material_name],
+        |row| row.get(0)
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Material '{}' not found", material_name))?;
+
+    let kinds: Vec<FormDrillKind> = if kinds.is_empty() { FormDrillKind::ALL.to_vec() } else { kinds.to_vec() };
+    let count = count.clamp(1, MAX_COUNT) as usize;
+
+    let mut pool = candidates(conn, material_id, &kinds)?;
+    if pool.is_empty() {
+        return Err(format!(
+            "No vocabulary in '{}' can be drilled for {}. Set the part of speech (and gender for nouns) on its words first.",
+            material_name,
+            kinds.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    let mut shuffler = Shuffler::new();
+    shuffler.shuffle(&mut pool);
+
+    // One exercise per word first, then fill up with further forms
+    let mut picked: Vec<Candidate> = Vec::new();
+    let mut rest = Vec::new();
+    for candidate in pool {
+        if picked.len() < count && !picked.iter().any(|p| p.vocabulary_id == candidate.vocabulary_id) {
+            picked.push(candidate);
+        } else {
+            rest.push(candidate);
+        }
+    }
+    let missing = count.saturating_sub(picked.len());
+    picked.extend(rest.into_iter().take(missing));
+    shuffler.shuffle(&mut picked);
+
+    let attempt_number: i32 = conn.query_row(
+        "SELECT COUNT(*) + 1 FROM RussianDrillAttempts WHERE material_id = ?1",
+        params![material_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO RussianDrillAttempts (material_id, attempt_number, status, commentary)
+         VALUES (?1, ?2, 'learning', ?3)",
+        params![material_id, attempt_number, summary_line(0, 0, picked.len())],
+    ).map_err(|e| e.to_string())?;
+    let drill_id = conn.last_insert_rowid();
+
+    let mut exercises = Vec::with_capacity(picked.len());
+    for (index, candidate) in picked.into_iter().enumerate() {
+        let position = index as i32 + 1;
+        let accepted = serde_json::to_string(&candidate.accepted).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO FormDrillExercises (drill_id, position, vocabulary_id, word_ru, kind, form_key, prompt, accepted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                drill_id,
+                position,
+                candidate.vocabulary_id,
+                &candidate.word_ru,
+                candidate.kind.as_str(),
+                &candidate.form_key,
+                &candidate.prompt,
+                accepted,
+            ],
+        ).map_err(|e| e.to_string())?;
+        let exercise_id = conn.last_insert_rowid();
+
+        conn.execute(
+            "INSERT OR IGNORE INTO DrillVocabulary (drill_id, vocabulary_id) VALUES (?1, ?2)",
+            params![drill_id, candidate.vocabulary_id],
+        ).map_err(|e| e.to_string())?;
+
+        exercises.push(FormExercise {
+            id: exercise_id,
+            position,
+            vocabulary_id: Some(candidate.vocabulary_id),
+            word_ru: candidate.word_ru,
+            translation_en: Some(candidate.translation_en),
+            kind: candidate.kind,
+            form_key: candidate.form_key,
+            prompt: candidate.prompt,
+            answer: None,
+            correct: None,
+        });
+    }
+
+    Ok(FormDrill {
+        drill_id,
+        material_name: material_name.to_string(),
+        attempt_number,
+        exercises,
+    })
+}
+
+fn summary_line(correct: usize, answered: usize, total: usize) -> String {
+    format!("Form drill, {} exercises: {} answered, {} correct", total, answered, correct)
+}
+
+fn drill_status(correct: usize, answered: usize, total: usize) -> &'static str {
+    if answered == 0 {
+        return "learning";
+    }
+    let accuracy = correct as f64 / answered as f64;
+    if answered == total && accuracy >= MASTERED_ACCURACY {
+        "mastered"
+    } else if accuracy >= PRACTICING_ACCURACY {
+        "practicing"
+    } else {
+        "learning"
+    }
+}
+
+// Grade an answer to one exercise and refresh the drill attempt: status from
+// the accuracy so far, wrong answers in errors_ru and their corrections in
+// resolution_ru. Each exercise can only be answered once.
+pub fn check(conn: &Connection, exercise_id: i64, answer: &str) -> Result<FormAnswerResult, String> {
+    let (drill_id, accepted, previous): (i64, String, Option<String>) = conn.query_row(
+        "SELECT drill_id, accepted, answer FROM FormDrillExercises WHERE id = ?1",
+        params![exercise_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Exercise {} not found", exercise_id))?;
+
+    if previous.is_some() {
+        return Err("This exercise has already been answered".to_string());
+    }
+
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Err("Answer cannot be empty".to_string());
+    }
+
+    let accepted: Vec<String> = serde_json::from_str(&accepted).map_err(|e| e.to_string())?;
+    let given = normalize_answer(answer);
+    let correct = accepted.iter().any(|a| normalize_answer(a) == given);
+
+    conn.execute(
+        "UPDATE FormDrillExercises SET answer = ?1, correct = ?2, answered_at = datetime('now') WHERE id = ?3",
+        params![answer, correct, exercise_id],
+    ).map_err(|e| e.to_string())?;
+
+    let (answered, correct_count, total) = refresh_drill(conn, drill_id)?;
+
+    Ok(FormAnswerResult {
+        exercise_id,
+        drill_id,
+        correct,
+        answer: answer.to_string(),
+        expected: accepted.first().cloned().unwrap_or_default(),
+        accepted,
+        answered,
+        correct_count,
+        total,
+        status: drill_status(correct_count, answered, total).to_string(),
+    })
+}
+
+struct Outcome {
+    word_ru: String,
+    form_key: String,
+    accepted: String,
+    answer: Option<String>,
+    correct: Option<bool>,
+}
+
+fn refresh_drill(conn: &Connection, drill_id: i64) -> Result<(usize, usize, usize), String> {
+    let outcomes = conn.prepare(
+        "SELECT word_ru, form_key, accepted, answer, correct FROM FormDrillExercises
+         WHERE drill_id = ?1 ORDER BY position"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![drill_id], |row| Ok(Outcome {
+        word_ru: row.get(0)?,
+        form_key: row.get(1)?,
+        accepted: row.get(2)?,
+        answer: row.get(3)?,
+        correct: row.get(4)?,
+    }))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let total = outcomes.len();
+    let mut answered = 0;
+    let mut correct_count = 0;
+    let mut errors = Vec::new();
+    let mut resolutions = Vec::new();
+
+    for outcome in &outcomes {
+        let Some(answer) = &outcome.answer else { continue };
+        answered += 1;
+        if outcome.correct == Some(true) {
+            correct_count += 1;
+            continue;
+        }
+        let expected: Vec<String> = serde_json::from_str(&outcome.accepted).map_err(|e| e.to_string())?;
+        let form = inflection::describe_form(&outcome.form_key, None);
+        errors.push(format!("{} ({}): {}", outcome.word_ru, form, answer));
+        resolutions.push(format!("{} ({}): {}", outcome.word_ru, form, expected.first().cloned().unwrap_or_default()));
+    }
+
+    let join = |lines: Vec<String>| if lines.is_empty() { None } else { Some(lines.join("\n")) };
+    conn.execute(
+        "UPDATE RussianDrillAttempts SET status = ?1, commentary = ?2, errors_ru = ?3, resolution_ru = ?4 WHERE id = ?5",
+        params![
+            drill_status(correct_count, answered, total),
+            summary_line(correct_count, answered, total),
+            join(errors),
+            join(resolutions),
+            drill_id,
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    Ok((answered, correct_count, total))
+}
+
+pub fn load_drill(conn: &Connection, drill_id: i64) -> Result<FormDrill, String> {
+    let (material_name, attempt_number): (String, i32) = conn.query_row(
+        "SELECT m.name_en, d.attempt_number FROM RussianDrillAttempts d
+         JOIN Materials m ON d.material_id = m.id
+         WHERE d.id = ?1",
+        params![drill_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Drill {} not found", drill_id))?;
+
+    let exercises = conn.prepare(
+        "SELECT e.id, e.position, e.vocabulary_id, e.word_ru, v.translation_en, e.kind, e.form_key,
+                e.prompt, e.answer, e.correct
+         FROM FormDrillExercises e
+         LEFT JOIN RussianVocabulary v ON e.vocabulary_id = v.id
+         WHERE e.drill_id = ?1
+         ORDER BY e.position"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![drill_id], |row| {
+        let kind: String = row.get(5)?;
+        Ok(FormExercise {
+            id: row.get(0)?,
+            position: row.get(1)?,
+            vocabulary_id: row.get(2)?,
+            word_ru: row.get(3)?,
+            translation_en: row.get(4)?,
+            kind: FormDrillKind::parse(&kind).unwrap_or(FormDrillKind::Declension),
+            form_key: row.get(6)?,
+            prompt: row.get(7)?,
+            answer: row.get(8)?,
+            correct: row.get(9)?,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(FormDrill { drill_id, material_name, attempt_number, exercises })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::russian::grammar::{Gender, PartOfSpeech};
+
+    fn material_with_noun(conn: &Connection) -> i64 {
+        conn.execute("INSERT INTO Materials (id, name_en) VALUES (1, 'Limits')", []).unwrap();
+        conn.execute(
+            "INSERT INTO RussianVocabulary (word_ru, translation_en, lemma, material_id) VALUES ('книга', 'book', ?1, 1)",
+            params![morphology::lemma_key("книга")],
+        ).unwrap();
+        let id = conn.last_insert_rowid();
+        let grammar = VocabularyGrammar { part_of_speech: Some(PartOfSpeech::Noun), gender: Some(Gender::Feminine), ..Default::default() };
+        grammar::save_grammar(conn, id, &grammar).unwrap();
+        id
+    }
+
+    #[test]
+    fn answers_ignore_case_stress_and_punctuation() {
+        assert_eq!(normalize_answer("  Кни\u{301}ги! "), normalize_answer("книги"));
+        assert_eq!(normalize_answer("ЁЛКИ"), normalize_answer("елки"));
+        assert_eq!(normalize_answer("буду   читать"), "буду читать");
+    }
+
+    #[test]
+    fn status_follows_accuracy() {
+        assert_eq!(drill_status(0, 0, 5), "learning");
+        assert_eq!(drill_status(2, 4, 5), "learning");
+        assert_eq!(drill_status(3, 4, 5), "practicing");
+        assert_eq!(drill_status(4, 4, 5), "practicing");
+        assert_eq!(drill_status(5, 5, 5), "mastered");
+    }
+
+    #[test]
+    fn drills_only_tagged_words() {
+        let conn = db::open_in_memory();
+        conn.execute("INSERT INTO Materials (name_en) VALUES ('Limits')", []).unwrap();
+        conn.execute("INSERT INTO RussianVocabulary (word_ru, translation_en, material_id) VALUES ('предел', 'limit', 1)", []).unwrap();
+
+        assert!(generate(&conn, "Limits", 5, &[FormDrillKind::Declension]).is_err());
+        assert!(generate(&conn, "Series", 5, &[]).is_err());
+    }
+
+    #[test]
+    fn answers_are_graded_and_recorded_on_the_drill() {
+        let conn = db::open_in_memory();
+        let id = material_with_noun(&conn);
+
+        let drill = generate(&conn, "Limits", 3, &[FormDrillKind::Declension]).unwrap();
+        assert_eq!(drill.exercises.len(), 3);
+        assert!(drill.exercises.iter().all(|e| e.vocabulary_id == Some(id) && e.form_key != "nom_sg"));
+
+        let first = &drill.exercises[0];
+        let expected = inflection::inflect("книга", &grammar::load_grammar(&conn, id).unwrap(), &first.form_key).unwrap();
+        let result = check(&conn, first.id, &expected[0].to_uppercase()).unwrap();
+        assert!(result.correct);
+        assert_eq!((result.answered, result.correct_count, result.total), (1, 1, 3));
+        assert!(check(&conn, first.id, &expected[0]).is_err());
+
+        let result = check(&conn, drill.exercises[1].id, "книгой-то").unwrap();
+        assert!(!result.correct);
+        assert_eq!(result.status, "learning");
+
+        let errors: Option<String> = conn.query_row(
+            "SELECT errors_ru FROM RussianDrillAttempts WHERE id = ?1",
+            params![drill.drill_id],
+            |row| row.get(0),
+        ).unwrap();
+        assert!(errors.unwrap().contains("книгой-то"));
+
+        let loaded = load_drill(&conn, drill.drill_id).unwrap();
+        assert_eq!(loaded.exercises[0].correct, Some(true));
+        assert_eq!(loaded.exercises[2].answer, None);
+    }
+}
@@ -0,0 +1,293 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use super::morphology;
+
+// A material is ready for Russian drilling (Phase 4) once this share of its
+// problems is solved in English...
+pub const READY_SOLVED_SHARE: f64 = 0.8;
+// ...its required terms are all written up with a translation or notes...
+pub const READY_GLOSSARY_COMPLETENESS: f64 = 1.0;
+// ...and this share of them has come up in drills
+pub const READY_TERM_COVERAGE: f64 = 0.5;
+
+// A required Russian term of a material, with how often it has appeared in
+// the material's drills
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialTerm {
+    pub id: i64,
+    pub material_name: String,
+    pub term_ru: String,
+    pub translation_en: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: String,
+    // Vocabulary entry for the term (or one of its forms), if there is one
+    pub vocabulary_id: Option<i64>,
+    // Drills whose errors_ru or resolution_ru mention the term in any form,
+    // or that are linked to its vocabulary entry
+    pub drill_count: usize,
+    pub last_seen: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialReadiness {
+    pub material_name: String,
+    pub name_ru: Option<String>,
+    pub problems_total: usize,
+    pub problems_solved: usize,
+    // Share of problems solved; 0 when the material has none
+    pub english_mastery: f64,
+    pub terms_total: usize,
+    pub terms_complete: usize,
+    // Share of terms with a translation or notes; None without a glossary
+    pub glossary_completeness: Option<f64>,
+    // Terms with neither a translation nor notes yet
+    pub incomplete_terms: Vec<String>,
+    pub terms_covered: usize,
+    // Share of terms seen in at least one drill; None without a glossary
+    pub term_coverage: Option<f64>,
+    // Terms not yet seen in any drill
+    pub missing_terms: Vec<String>,
+    pub ready: bool,
+    // Why the material isn't ready yet, empty when it is
+    pub blockers: Vec<String>,
+}
+
+fn material_id(conn: &Connection, material_name: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT id FROM Materials WHERE name_en = ?1",
+        params![material_name],
+        |row| row.get(0)
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Material '{}' not found", material_name))
+}
+
+// Adding a term that's already in the glossary updates its translation and
+// notes. Returns the term id.
+pub fn add_term(
+    conn: &Connection,
+    material_name: &str,
+    term_ru: &str,
+    translation_en: Option<&str>,
+    notes: Option<&str>,
+) -> Result<i64, String> {
+    let term_ru = term_ru.trim();
+    if term_ru.is_empty() {
+        return Err("Term cannot be empty".to_string());
+    }
+
+    let material_id = material_id(conn, material_name)?;
+    conn.query_row(
+        "INSERT INTO MaterialTerms (material_id, term_ru, translation_en, notes)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(material_id, term_ru) DO UPDATE SET
+            translation_en = COALESCE(excluded.translation_en, translation_en),
+            notes = COALESCE(excluded.notes, notes)
+         RETURNING id",
+        params![material_id, term_ru, translation_en, notes],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())
+}
+
+pub fn remove_term(conn: &Connection, term_id: i64) -> Result<(), String> {
+    let deleted = conn.execute("DELETE FROM MaterialTerms WHERE id = ?1", params![term_id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Term {} not found", term_id));
+    }
+    Ok(())
+}
+
+struct DrillText {
+    id: i64,
+    timestamp: String,
+    stems: Vec<String>,
+}
+
+// The material's glossary with drill coverage, in alphabetical order
+pub fn list_terms(conn: &Connection, material_name: &str) -> Result<Vec<MaterialTerm>, String> {
+    let material_id = material_id(conn, material_name)?;
+
+    let drills: Vec<DrillText> = conn.prepare(
+        "SELECT id, timestamp, COALESCE(errors_ru, '') || char(10) || COALESCE(resolution_ru, '')
+         FROM RussianDrillAttempts
+         WHERE material_id = ?1
+         ORDER BY timestamp ASC, id ASC"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| {
+        let text: String = row.get(2)?;
+        Ok(DrillText { id: row.get(0)?, timestamp: row.get(1)?, stems: morphology::text_stems(&text) })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut terms: Vec<MaterialTerm> = conn.prepare(
+        "SELECT id, term_ru, translation_en, notes, created_at
+         FROM MaterialTerms
+         WHERE material_id = ?1
+         ORDER BY term_ru"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| Ok(MaterialTerm {
+        id: row.get(0)?,
+        material_name: material_name.to_string(),
+        term_ru: row.get(1)?,
+        translation_en: row.get(2)?,
+        notes: row.get(3)?,
+        created_at: row.get(4)?,
+        vocabulary_id: None,
+        drill_count: 0,
+        last_seen: None,
+    }))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut linked_stmt = conn.prepare(
+        "SELECT drill_id FROM DrillVocabulary WHERE vocabulary_id = ?1"
+    ).map_err(|e| e.to_string())?;
+
+    for term in &mut terms {
+        term.vocabulary_id = morphology::find_entry(conn, &term.term_ru, Some(material_id))?.map(|(id, _)| id);
+
+        let linked: Vec<i64> = match term.vocabulary_id {
+            Some(vocab_id) => linked_stmt.query_map(params![vocab_id], |row| row.get(0))
+                .map_err(|e| e.to_string())?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?,
+            None => Vec::new(),
+        };
+
+        for drill in &drills {
+            if linked.contains(&drill.id) || morphology::mentions(&drill.stems, &term.term_ru) {
+                term.drill_count += 1;
+                term.last_seen = Some(drill.timestamp.clone());
+            }
+        }
+    }
+
+    Ok(terms)
+}
+
+pub fn readiness(conn: &Connection, material_name: &str) -> Result<MaterialReadiness, String> {
+    let (material_id, name_ru): (i64, Option<String>) = conn.query_row(
+        "SELECT id, name_ru FROM Materials WHERE name_en = ?1",
+        params![material_name],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Material '{}' not found", material_name))?;
+
+    let (problems_total, problems_solved): (usize, usize) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(is_solved), 0) FROM Problems WHERE material_id = ?1",
+        params![material_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| e.to_string())?;
+
+    let english_mastery = if problems_total == 0 {
+        0.0
+    } else {
+        problems_solved as f64 / problems_total as f64
+    };
+
+    let terms = list_terms(conn, material_name)?;
+    let terms_total = terms.len();
+    let written_up = |text: &Option<String>| text.as_deref().is_some_and(|t| !t.trim().is_empty());
+    let incomplete_terms: Vec<String> = terms.iter()
+        .filter(|t| !written_up(&t.translation_en) && !written_up(&t.notes))
+        .map(|t| t.term_ru.clone())
+        .collect();
+    let terms_complete = terms_total - incomplete_terms.len();
+    let glossary_completeness = if terms_total == 0 {
+        None
+    } else {
+        Some(terms_complete as f64 / terms_total as f64)
+    };
+    let missing_terms: Vec<String> = terms.iter()
+        .filter(|t| t.drill_count == 0)
+        .map(|t| t.term_ru.clone())
+        .collect();
+    let terms_covered = terms_total - missing_terms.len();
+    let term_coverage = if terms_total == 0 {
+        None
+    } else {
+        Some(terms_covered as f64 / terms_total as f64)
+    };
+
+    let mut blockers = Vec::new();
+    if problems_total == 0 {
+        blockers.push("No problems logged for this material yet".to_string());
+    } else if english_mastery < READY_SOLVED_SHARE {
+        blockers.push(format!(
+            "{} of {} problems solved; {:.0}% needed",
+            problems_solved, problems_total, READY_SOLVED_SHARE * 100.0
+        ));
+    }
+    match glossary_completeness {
+        None => blockers.push("No required Russian terms defined".to_string()),
+        Some(completeness) if completeness < READY_GLOSSARY_COMPLETENESS => blockers.push(format!(
+            "{} of {} terms have a translation or notes; {:.0}% needed",
+            terms_complete, terms_total, READY_GLOSSARY_COMPLETENESS * 100.0
+        )),
+        Some(_) => {}
+    }
+    if term_coverage.is_some_and(|coverage| coverage < READY_TERM_COVERAGE) {
+        blockers.push(format!(
+            "{} of {} terms seen in drills; {:.0}% needed",
+            terms_covered, terms_total, READY_TERM_COVERAGE * 100.0
+        ));
+    }
+
+    Ok(MaterialReadiness {
+        material_name: material_name.to_string(),
+        name_ru,
+        problems_total,
+        problems_solved,
+        english_mastery,
+        terms_total,
+        terms_complete,
+        glossary_completeness,
+        incomplete_terms,
+        terms_covered,
+        term_coverage,
+        missing_terms,
+        ready: blockers.is_empty(),
+        blockers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn readiness_needs_mastery_a_complete_glossary_and_drill_coverage() {
+        let conn = db::open_in_memory();
+        conn.execute_batch(
+            "INSERT INTO Materials (id, name_en) VALUES (1, 'Limits');
+             INSERT INTO Problems (generated_id, material_id, title, is_solved) VALUES ('MATH_001', 1, 'Squeeze', 1);",
+        ).unwrap();
+        for term in ["предел", "производная", "интеграл"] {
+            add_term(&conn, "Limits", term, Some("term"), None).unwrap();
+        }
+
+        let status = readiness(&conn, "Limits").unwrap();
+        assert_eq!(status.glossary_completeness, Some(1.0));
+        assert_eq!(status.term_coverage, Some(0.0));
+        assert!(!status.ready);
+        assert_eq!(status.blockers, vec!["0 of 3 terms seen in drills; 50% needed"]);
+
+        conn.execute(
+            "INSERT INTO RussianDrillAttempts (material_id, attempt_number, resolution_ru)
+             VALUES (1, 1, 'Предел равен производной в нуле')",
+            [],
+        ).unwrap();
+        let status = readiness(&conn, "Limits").unwrap();
+        assert_eq!(status.terms_covered, 2);
+        assert_eq!(status.missing_terms, vec!["интеграл"]);
+        assert!(status.ready);
+    }
+}
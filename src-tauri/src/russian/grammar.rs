@@ -0,0 +1,408 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use super::morphology;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PartOfSpeech {
+    Noun,
+    Verb,
+    Adjective,
+    Adverb,
+    Pronoun,
+    Numeral,
+    Preposition,
+    Conjunction,
+    Particle,
+    Interjection,
+    Phrase,
+}
+
+impl PartOfSpeech {
+    pub const ALL: [PartOfSpeech; 11] = [
+        PartOfSpeech::Noun,
+        PartOfSpeech::Verb,
+        PartOfSpeech::Adjective,
+        PartOfSpeech::Adverb,
+        PartOfSpeech::Pronoun,
+        PartOfSpeech::Numeral,
+        PartOfSpeech::Preposition,
+        PartOfSpeech::Conjunction,
+        PartOfSpeech::Particle,
+        PartOfSpeech::Interjection,
+        PartOfSpeech::Phrase,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PartOfSpeech::Noun => "noun",
+            PartOfSpeech::Verb => "verb",
+            PartOfSpeech::Adjective => "adjective",
+            PartOfSpeech::Adverb => "adverb",
+            PartOfSpeech::Pronoun => "pronoun",
+            PartOfSpeech::Numeral => "numeral",
+            PartOfSpeech::Preposition => "preposition",
+            PartOfSpeech::Conjunction => "conjunction",
+            PartOfSpeech::Particle => "particle",
+            PartOfSpeech::Interjection => "interjection",
+            PartOfSpeech::Phrase => "phrase",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter,
+    // Nouns like сирота that take either agreement
+    Common,
+}
+
+impl Gender {
+    pub const ALL: [Gender; 4] = [Gender::Masculine, Gender::Feminine, Gender::Neuter, Gender::Common];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Gender::Masculine => "masculine",
+            Gender::Feminine => "feminine",
+            Gender::Neuter => "neuter",
+            Gender::Common => "common",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|g| g.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aspect {
+    Imperfective,
+    Perfective,
+    // Verbs like использовать that serve as both
+    Biaspectual,
+}
+
+impl Aspect {
+    pub const ALL: [Aspect; 3] = [Aspect::Imperfective, Aspect::Perfective, Aspect::Biaspectual];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Aspect::Imperfective => "imperfective",
+            Aspect::Perfective => "perfective",
+            Aspect::Biaspectual => "biaspectual",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.as_str() == value)
+    }
+
+    // Aspect of the other verb in a pair
+    pub fn partner(&self) -> Option<Aspect> {
+        match self {
+            Aspect::Imperfective => Some(Aspect::Perfective),
+            Aspect::Perfective => Some(Aspect::Imperfective),
+            Aspect::Biaspectual => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GrammaticalCase {
+    Nominative,
+    Genitive,
+    Dative,
+    Accusative,
+    Instrumental,
+    Prepositional,
+}
+
+impl GrammaticalCase {
+    pub const ALL: [GrammaticalCase; 6] = [
+        GrammaticalCase::Nominative,
+        GrammaticalCase::Genitive,
+        GrammaticalCase::Dative,
+        GrammaticalCase::Accusative,
+        GrammaticalCase::Instrumental,
+        GrammaticalCase::Prepositional,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GrammaticalCase::Nominative => "nominative",
+            GrammaticalCase::Genitive => "genitive",
+            GrammaticalCase::Dative => "dative",
+            GrammaticalCase::Accusative => "accusative",
+            GrammaticalCase::Instrumental => "instrumental",
+            GrammaticalCase::Prepositional => "prepositional",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == value)
+    }
+
+    // Prefix used in irregular form keys, e.g. "gen" in "gen_pl"
+    pub fn key(&self) -> &'static str {
+        match self {
+            GrammaticalCase::Nominative => "nom",
+            GrammaticalCase::Genitive => "gen",
+            GrammaticalCase::Dative => "dat",
+            GrammaticalCase::Accusative => "acc",
+            GrammaticalCase::Instrumental => "ins",
+            GrammaticalCase::Prepositional => "prep",
+        }
+    }
+}
+
+// Case (and optional preposition) a word governs: помогать + dat.,
+// надеяться на + acc.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Government {
+    pub preposition: Option<String>,
+    pub case: GrammaticalCase,
+    pub note: Option<String>,
+}
+
+// Keys for irregular forms. Noun/adjective cases are `<case>_<sg|pl>` (e.g.
+// "gen_pl"); verbs use `pres_<person><number>` (the simple future for
+// perfective verbs), `past_<m|f|n|pl>` and `imp_<sg|pl>`; adjectives also
+// have `short_<m|f|n|pl>` and `comparative`.
+pub fn is_form_key(key: &str) -> bool {
+    const VERB_KEYS: &[&str] = &[
+        "pres_1sg", "pres_2sg", "pres_3sg", "pres_1pl", "pres_2pl", "pres_3pl",
+        "past_m", "past_f", "past_n", "past_pl",
+        "imp_sg", "imp_pl",
+    ];
+    const ADJECTIVE_KEYS: &[&str] = &["short_m", "short_f", "short_n", "short_pl", "comparative"];
+
+    if VERB_KEYS.contains(&key) || ADJECTIVE_KEYS.contains(&key) {
+        return true;
+    }
+    match key.split_once('_') {
+        Some((case, number)) => {
+            GrammaticalCase::ALL.iter().any(|c| c.key() == case) && matches!(number, "sg" | "pl")
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VocabularyGrammar {
+    pub part_of_speech: Option<PartOfSpeech>,
+    // Nouns
+    pub gender: Option<Gender>,
+    pub animate: Option<bool>,
+    // Verbs: own aspect and the other verb of the pair (делать/сделать)
+    pub aspect: Option<Aspect>,
+    pub aspect_partner: Option<String>,
+    // 1-based index of the stressed vowel, e.g. 2 for доро́га
+    pub stress_position: Option<i32>,
+    pub government: Vec<Government>,
+    // Forms the regular inflection rules get wrong, by form key
+    pub irregular_forms: BTreeMap<String, String>,
+}
+
+const VOWELS: &str = "аеёиоуыэюяАЕЁИОУЫЭЮЯ";
+
+pub fn vowel_count(word: &str) -> usize {
+    word.chars().filter(|c| VOWELS.contains(*c)).count()
+}
+
+// Stress position marked in the word itself with a combining acute accent
+// (доро́га) or by ё, which is always stressed
+pub fn stress_from_marks(word: &str) -> Option<i32> {
+    let mut vowels = 0;
+    let mut yo_position = None;
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        if VOWELS.contains(c) {
+            vowels += 1;
+            if chars.peek() == Some(&'\u{301}') {
+                return Some(vowels);
+            }
+            if matches!(c, 'ё' | 'Ё') && yo_position.is_none() {
+                yo_position = Some(vowels);
+            }
+        }
+    }
+    yo_position
+}
+
+// The word with a combining acute accent after the stressed vowel
+pub fn mark_stress(word: &str, stress_position: i32) -> String {
+    let plain = morphology::strip_stress(word);
+    let mut marked = String::with_capacity(plain.len() + 2);
+    let mut vowels = 0;
+    for c in plain.chars() {
+        marked.push(c);
+        if VOWELS.contains(c) {
+            vowels += 1;
+            // ё is stressed by definition and never marked
+            if vowels == stress_position && !matches!(c, 'ё' | 'Ё') {
+                marked.push('\u{301}');
+            }
+        }
+    }
+    marked
+}
+
+impl VocabularyGrammar {
+    pub fn validate(&self, word_ru: &str) -> Result<(), String> {
+        if let Some(position) = self.stress_position {
+            let vowels = vowel_count(&morphology::strip_stress(word_ru));
+            if position < 1 || position as usize > vowels {
+                return Err(format!(
+                    "Stress position {} is out of range: '{}' has {} vowel(s)",
+                    position, word_ru, vowels
+                ));
+            }
+        }
+        for key in self.irregular_forms.keys() {
+            if !is_form_key(key) {
+                return Err(format!("Unknown irregular form key '{}'", key));
+            }
+        }
+        for form in self.irregular_forms.values() {
+            if form.trim().is_empty() {
+                return Err("Irregular forms can't be empty".to_string());
+            }
+        }
+        if self.aspect_partner.as_deref().is_some_and(|p| p.trim() == word_ru.trim()) {
+            return Err("A verb can't be its own aspect partner".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn load_grammar(conn: &Connection, vocabulary_id: i64) -> Result<VocabularyGrammar, String> {
+    let mut grammar = conn.query_row(
+        "SELECT part_of_speech, gender, animate, aspect, aspect_partner, stress_position
+         FROM RussianVocabulary WHERE id = ?1",
+        params![vocabulary_id],
+        |row| Ok(VocabularyGrammar {
+            part_of_speech: row.get::<_, Option<String>>(0)?.as_deref().and_then(PartOfSpeech::parse),
+            gender: row.get::<_, Option<String>>(1)?.as_deref().and_then(Gender::parse),
+            animate: row.get(2)?,
+            aspect: row.get::<_, Option<String>>(3)?.as_deref().and_then(Aspect::parse),
+            aspect_partner: row.get(4)?,
+            stress_position: row.get(5)?,
+            ..Default::default()
+        })
+    ).map_err(|_| format!("Vocabulary entry {} not found", vocabulary_id))?;
+
+    grammar.government = conn.prepare(
+        "SELECT preposition, grammatical_case, note FROM VocabularyGovernment
+         WHERE vocabulary_id = ?1 ORDER BY id"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![vocabulary_id], |row| {
+        Ok((row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+    })
+    .map_err(|e| e.to_string())?
+    .filter_map(|row| match row {
+        Ok((preposition, case, note)) => GrammaticalCase::parse(&case)
+            .map(|case| Ok(Government { preposition, case, note })),
+        Err(e) => Some(Err(e.to_string())),
+    })
+    .collect::<Result<Vec<_>, String>>()?;
+
+    grammar.irregular_forms = conn.prepare(
+        "SELECT form_key, form FROM VocabularyIrregularForms WHERE vocabulary_id = ?1"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![vocabulary_id], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<BTreeMap<_, _>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(grammar)
+}
+
+// Replace a word's grammar. A stress mark or ё in the word fills in the stress
+// position when none is given. Naming an aspect partner that is also in the
+// vocabulary links it back (and gives it the opposite aspect) unless it
+// already has a partner of its own.
+pub fn save_grammar(conn: &Connection, vocabulary_id: i64, grammar: &VocabularyGrammar) -> Result<(), String> {
+    let word_ru: String = conn.query_row(
+        "SELECT word_ru FROM RussianVocabulary WHERE id = ?1",
+        params![vocabulary_id],
+        |row| row.get(0)
+    ).map_err(|_| format!("Vocabulary entry {} not found", vocabulary_id))?;
+
+    grammar.validate(&word_ru)?;
+
+    let aspect_partner = grammar.aspect_partner.as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+    let stress_position = grammar.stress_position.or_else(|| stress_from_marks(&word_ru));
+
+    conn.execute(
+        "UPDATE RussianVocabulary
+         SET part_of_speech = ?1, gender = ?2, animate = ?3, aspect = ?4,
+             aspect_partner = ?5, stress_position = ?6
+         WHERE id = ?7",
+        params![
+            grammar.part_of_speech.map(|p| p.as_str()),
+            grammar.gender.map(|g| g.as_str()),
+            grammar.animate,
+            grammar.aspect.map(|a| a.as_str()),
+            aspect_partner,
+            stress_position,
+            vocabulary_id,
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM VocabularyGovernment WHERE vocabulary_id = ?1", params![vocabulary_id])
+        .map_err(|e| e.to_string())?;
+    for government in &grammar.government {
+        conn.execute(
+            "INSERT OR IGNORE INTO VocabularyGovernment (vocabulary_id, preposition, grammatical_case, note)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                vocabulary_id,
+                government.preposition.as_deref().map(str::trim).filter(|p| !p.is_empty()),
+                government.case.as_str(),
+                government.note,
+            ],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    conn.execute("DELETE FROM VocabularyIrregularForms WHERE vocabulary_id = ?1", params![vocabulary_id])
+        .map_err(|e| e.to_string())?;
+    for (key, form) in &grammar.irregular_forms {
+        conn.execute(
+            "INSERT INTO VocabularyIrregularForms (vocabulary_id, form_key, form) VALUES (?1, ?2, ?3)",
+            params![vocabulary_id, key, form.trim()],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    if let Some(partner) = aspect_partner {
+        conn.execute(
+            "UPDATE RussianVocabulary
+             SET aspect_partner = ?1,
+                 part_of_speech = COALESCE(part_of_speech, 'verb'),
+                 aspect = COALESCE(aspect, ?2)
+             WHERE word_ru = ?3 AND id != ?4 AND aspect_partner IS NULL",
+            params![
+                &word_ru,
+                grammar.aspect.and_then(|a| a.partner()).map(|a| a.as_str()),
+                partner,
+                vocabulary_id,
+            ],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,812 @@
+use super::grammar::{self, Aspect, Gender, GrammaticalCase, PartOfSpeech, VocabularyGrammar};
+use super::morphology;
+
+// Rule-based inflection for the regular noun declensions and verb
+// conjugations, plus a table of common irregular verbs. A word's stored
+// irregular forms always win. Where the rules can't be trusted (fleeting
+// vowels, unknown gender, -ти/-чь verbs...) the functions return None rather
+// than guess, so drills simply skip that form.
+
+const HUSHING: &[char] = &['ж', 'ш', 'ч', 'щ'];
+const VELAR: &[char] = &['г', 'к', 'х'];
+const VOWELS: &[char] = &['а', 'е', 'ё', 'и', 'о', 'у', 'ы', 'э', 'ю', 'я'];
+
+fn is_vowel(c: char) -> bool {
+    VOWELS.contains(&c)
+}
+
+fn last_char(s: &str) -> Option<char> {
+    s.chars().last()
+}
+
+// Join a stem and an ending, applying the spelling rules: ы→и after velars
+// and hushing consonants, я→а and ю→у after hushing consonants and ц
+fn attach(stem: &str, ending: &str) -> String {
+    let mut chars = ending.chars();
+    let first = chars.next();
+    let rest: String = chars.collect();
+    let last = last_char(stem);
+
+    let first = match (last, first) {
+        (Some(l), Some('ы')) if VELAR.contains(&l) || HUSHING.contains(&l) => Some('и'),
+        (Some(l), Some('я')) if HUSHING.contains(&l) || l == 'ц' => Some('а'),
+        (Some(l), Some('ю')) if HUSHING.contains(&l) || l == 'ц' => Some('у'),
+        (_, first) => first,
+    };
+
+    let mut word = stem.to_string();
+    if let Some(first) = first {
+        word.push(first);
+    }
+    word.push_str(&rest);
+    word
+}
+
+// After hushing consonants and ц an ending's о is written е when unstressed
+// (улицей, месяцем) and о when stressed (душой, ножом). Without stress data,
+// one-syllable stems are assumed to be end-stressed. Both spellings are
+// returned, the likelier first.
+fn attach_o(stem: &str, ending: &str, end_stressed: bool) -> Vec<String> {
+    let last = last_char(stem);
+    if !last.is_some_and(|l| HUSHING.contains(&l) || l == 'ц') {
+        return vec![attach(stem, ending)];
+    }
+    let with_o = attach(stem, ending);
+    let with_e = attach(stem, &ending.replacen('о', "е", 1));
+    if end_stressed { vec![with_o, with_e] } else { vec![with_e, with_o] }
+}
+
+// ---------------------------------------------------------------------------
+// Nouns
+
+const INDECLINABLE: &[&str] = &[
+    "кофе", "метро", "кино", "пальто", "такси", "радио", "кафе", "меню", "интервью",
+    "шоссе", "пианино", "фото", "видео", "депо", "бюро", "кенгуру", "жюри", "кредо",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Declension {
+    // стол
+    MasculineHard,
+    // словарь
+    MasculineSoft,
+    // музей, санаторий
+    MasculineJ,
+    // книга; also masculine папа, мужчина
+    A,
+    // неделя
+    Ya,
+    // история
+    Iya,
+    // дверь
+    FeminineSoft,
+    // окно
+    NeuterO,
+    // поле
+    NeuterE,
+    // здание
+    NeuterIe,
+}
+
+fn declension(word: &str, gender: Option<Gender>) -> Option<(Declension, String)> {
+    let stem = |suffix: &str| word[..word.len() - suffix.len()].to_string();
+
+    if word.ends_with("мя") {
+        // время, имя: a small irregular class, needs stored forms
+        return None;
+    }
+    if word.ends_with("ия") {
+        return Some((Declension::Iya, stem("я")));
+    }
+    if word.ends_with("ие") {
+        return Some((Declension::NeuterIe, stem("е")));
+    }
+    if word.ends_with('а') {
+        return Some((Declension::A, stem("а")));
+    }
+    if word.ends_with('я') {
+        return Some((Declension::Ya, stem("я")));
+    }
+    if word.ends_with('о') {
+        return Some((Declension::NeuterO, stem("о")));
+    }
+    if word.ends_with('е') || word.ends_with('ё') {
+        return Some((Declension::NeuterE, word[..word.len() - 'е'.len_utf8()].to_string()));
+    }
+    if word.ends_with('й') {
+        return Some((Declension::MasculineJ, stem("й")));
+    }
+    if word.ends_with('ь') {
+        return match gender {
+            Some(Gender::Feminine) => Some((Declension::FeminineSoft, stem("ь"))),
+            Some(Gender::Masculine) => Some((Declension::MasculineSoft, stem("ь"))),
+            _ => None,
+        };
+    }
+    match last_char(word) {
+        Some(c) if !is_vowel(c) => Some((Declension::MasculineHard, word.to_string())),
+        _ => None,
+    }
+}
+
+// Zero-ending genitive plural (книг, окон, ручек). Final consonant clusters
+// usually take a fleeting vowel; only the predictable -к- and -ь- cases are
+// handled.
+fn zero_genitive_plural(stem: &str) -> Option<String> {
+    let chars: Vec<char> = stem.chars().collect();
+    let n = chars.len();
+    // No cluster: книга → книг; война → войн
+    if n < 2 || is_vowel(chars[n - 1]) || is_vowel(chars[n - 2]) || (chars[n - 2] == 'й' && chars[n - 1] != 'к') {
+        return Some(stem.to_string());
+    }
+
+    let (before, last) = (chars[n - 2], chars[n - 1]);
+    let head: String = chars[..n - 1].iter().collect();
+    let head_without_soft: String = chars[..n - 2].iter().collect();
+    if before == 'ь' {
+        // письмо → писем
+        return Some(format!("{}е{}", head_without_soft, last));
+    }
+    if before == 'й' {
+        // чайка → чаек
+        return Some(format!("{}е{}", head_without_soft, last));
+    }
+    if last == 'к' {
+        // ручка → ручек, лодка → лодок
+        let vowel = if HUSHING.contains(&before) { 'е' } else { 'о' };
+        return Some(format!("{}{}{}", head, vowel, last));
+    }
+    None
+}
+
+// мать and дочь decline on a longer stem (матери, дочерью)
+const IRREGULAR_NOUNS: &[&str] = &["мать", "дочь"];
+
+// Masculine nouns that usually lose a vowel in the oblique cases (отец →
+// отца, подарок → подарка, день → дня). One-syllable -ок/-ец nouns keep it
+// (сок → сока); -ень nouns don't (пень → пня).
+fn has_fleeting_vowel(word: &str) -> bool {
+    word.ends_with("ень")
+        || word.ends_with("ёк")
+        || ((word.ends_with("ок") || word.ends_with("ец")) && grammar::vowel_count(word) > 1)
+}
+
+fn decline_noun(word: &str, grammar: &VocabularyGrammar, case: GrammaticalCase, plural: bool) -> Option<Vec<String>> {
+    if INDECLINABLE.contains(&word) {
+        return Some(vec![word.to_string()]);
+    }
+    if IRREGULAR_NOUNS.contains(&word) {
+        return None;
+    }
+
+    let (class, stem) = declension(word, grammar.gender)?;
+
+    // A stored genitive singular reveals the oblique stem (отец → отца,
+    // день → дня) for the masculine declensions
+    let oblique = match (class, grammar.irregular_forms.get("gen_sg")) {
+        (Declension::MasculineHard | Declension::MasculineSoft | Declension::MasculineJ, Some(genitive)) => {
+            let genitive = morphology::strip_stress(genitive);
+            let trimmed = genitive.trim_end_matches(['а', 'я']);
+            if trimmed.len() < genitive.len() { trimmed.to_string() } else { stem.clone() }
+        }
+        // Without the genitive a fleeting vowel can't be placed
+        (Declension::MasculineHard | Declension::MasculineSoft, None) if has_fleeting_vowel(word) => return None,
+        _ => stem.clone(),
+    };
+
+    let animate = grammar.animate.unwrap_or(false);
+    // A stressed ending in the stored genitive (отца́) settles it
+    let end_stressed = match grammar.irregular_forms.get("gen_sg") {
+        Some(genitive) if grammar::stress_from_marks(genitive).is_some() => {
+            grammar::stress_from_marks(genitive) == Some(grammar::vowel_count(genitive) as i32)
+        }
+        _ => grammar::vowel_count(word) <= 1,
+    };
+
+    // Accusative follows the genitive for animate nouns (all plurals, and
+    // masculine singulars outside the -а/-я declension)
+    if case == GrammaticalCase::Accusative {
+        let like_genitive = if plural {
+            animate
+        } else {
+            animate && matches!(class, Declension::MasculineHard | Declension::MasculineSoft | Declension::MasculineJ)
+        };
+        if like_genitive {
+            return decline_noun(word, grammar, GrammaticalCase::Genitive, plural);
+        }
+        if plural {
+            return decline_noun(word, grammar, GrammaticalCase::Nominative, true);
+        }
+    }
+
+    use GrammaticalCase::*;
+    let forms = match (class, plural, case) {
+        (_, false, Nominative) => vec![word.to_string()],
+
+        (Declension::MasculineHard, false, Genitive) => vec![attach(&oblique, "а")],
+        (Declension::MasculineHard, false, Dative) => vec![attach(&oblique, "у")],
+        (Declension::MasculineHard, false, Accusative) => vec![word.to_string()],
+        (Declension::MasculineHard, false, Instrumental) => attach_o(&oblique, "ом", end_stressed),
+        (Declension::MasculineHard, false, Prepositional) => vec![attach(&oblique, "е")],
+        (Declension::MasculineHard, true, Nominative) => vec![attach(&oblique, "ы")],
+        (Declension::MasculineHard, true, Genitive) => match last_char(&oblique) {
+            Some(c) if HUSHING.contains(&c) => vec![attach(&oblique, "ей")],
+            Some('ц') => attach_o(&oblique, "ов", end_stressed),
+            _ => vec![attach(&oblique, "ов")],
+        },
+
+        (Declension::MasculineSoft, false, Genitive) => vec![attach(&oblique, "я")],
+        (Declension::MasculineSoft, false, Dative) => vec![attach(&oblique, "ю")],
+        (Declension::MasculineSoft, false, Accusative) => vec![word.to_string()],
+        (Declension::MasculineSoft, false, Instrumental) => {
+            let (plain, stressed) = (attach(&oblique, "ем"), attach(&oblique, "ём"));
+            if end_stressed { vec![stressed, plain] } else { vec![plain, stressed] }
+        }
+        (Declension::MasculineSoft, false, Prepositional) => vec![attach(&oblique, "е")],
+        (Declension::MasculineSoft, true, Nominative) => vec![attach(&oblique, "и")],
+        (Declension::MasculineSoft, true, Genitive) => vec![attach(&oblique, "ей")],
+
+        (Declension::MasculineJ, false, Genitive) => vec![attach(&oblique, "я")],
+        (Declension::MasculineJ, false, Dative) => vec![attach(&oblique, "ю")],
+        (Declension::MasculineJ, false, Accusative) => vec![word.to_string()],
+        (Declension::MasculineJ, false, Instrumental) => vec![attach(&oblique, "ем")],
+        // санаторий → в санатории
+        (Declension::MasculineJ, false, Prepositional) if oblique.ends_with('и') => vec![attach(&oblique, "и")],
+        (Declension::MasculineJ, false, Prepositional) => vec![attach(&oblique, "е")],
+        (Declension::MasculineJ, true, Nominative) => vec![attach(&oblique, "и")],
+        (Declension::MasculineJ, true, Genitive) => vec![attach(&oblique, "ев")],
+
+        (Declension::A, false, Genitive) => vec![attach(&stem, "ы")],
+        (Declension::A, false, Dative) => vec![attach(&stem, "е")],
+        (Declension::A, false, Accusative) => vec![attach(&stem, "у")],
+        (Declension::A, false, Instrumental) => {
+            let mut forms = attach_o(&stem, "ой", end_stressed);
+            // Poetic/literary variant (книгою) is also correct
+            forms.extend(attach_o(&stem, "ою", end_stressed));
+            forms
+        }
+        (Declension::A, false, Prepositional) => vec![attach(&stem, "е")],
+        (Declension::A, true, Nominative) => vec![attach(&stem, "ы")],
+        (Declension::A, true, Genitive) => vec![zero_genitive_plural(&stem)?],
+
+        (Declension::Ya, false, Genitive) => vec![attach(&stem, "и")],
+        (Declension::Ya, false, Dative) => vec![attach(&stem, "е")],
+        (Declension::Ya, false, Accusative) => vec![attach(&stem, "ю")],
+        (Declension::Ya, false, Instrumental) => {
+            let (plain, stressed) = (attach(&stem, "ей"), attach(&stem, "ёй"));
+            if end_stressed { vec![stressed, plain, attach(&stem, "ею")] } else { vec![plain, stressed, attach(&stem, "ею")] }
+        }
+        (Declension::Ya, false, Prepositional) => vec![attach(&stem, "е")],
+        (Declension::Ya, true, Nominative) => vec![attach(&stem, "и")],
+        (Declension::Ya, true, Genitive) => match last_char(&stem) {
+            // идея → идей
+            Some(c) if is_vowel(c) => vec![attach(&stem, "й")],
+            _ => {
+                let chars: Vec<char> = stem.chars().collect();
+                // A cluster before -ня/-ля etc. is unpredictable (песня → песен)
+                if chars.len() >= 2 && !is_vowel(chars[chars.len() - 2]) {
+                    return None;
+                }
+                vec![format!("{}ь", stem)]
+            }
+        },
+
+        (Declension::Iya, false, Genitive | Dative | Prepositional) => vec![attach(&stem, "и")],
+        (Declension::Iya, false, Accusative) => vec![attach(&stem, "ю")],
+        (Declension::Iya, false, Instrumental) => vec![attach(&stem, "ей"), attach(&stem, "ею")],
+        (Declension::Iya, true, Nominative) => vec![attach(&stem, "и")],
+        (Declension::Iya, true, Genitive) => vec![attach(&stem, "й")],
+
+        (Declension::FeminineSoft, false, Genitive | Dative | Prepositional) => vec![attach(&stem, "и")],
+        (Declension::FeminineSoft, false, Accusative) => vec![word.to_string()],
+        (Declension::FeminineSoft, false, Instrumental) => vec![attach(&stem, "ью")],
+        (Declension::FeminineSoft, true, Nominative) => vec![attach(&stem, "и")],
+        (Declension::FeminineSoft, true, Genitive) => vec![attach(&stem, "ей")],
+
+        (Declension::NeuterO, false, Genitive) => vec![attach(&stem, "а")],
+        (Declension::NeuterO, false, Dative) => vec![attach(&stem, "у")],
+        (Declension::NeuterO, false, Accusative) => vec![word.to_string()],
+        (Declension::NeuterO, false, Instrumental) => attach_o(&stem, "ом", end_stressed),
+        (Declension::NeuterO, false, Prepositional) => vec![attach(&stem, "е")],
+        (Declension::NeuterO, true, Nominative) => vec![attach(&stem, "а")],
+        (Declension::NeuterO, true, Genitive) => vec![zero_genitive_plural(&stem)?],
+
+        (Declension::NeuterE, false, Genitive) => vec![attach(&stem, "я")],
+        (Declension::NeuterE, false, Dative) => vec![attach(&stem, "ю")],
+        (Declension::NeuterE, false, Accusative) => vec![word.to_string()],
+        (Declension::NeuterE, false, Instrumental) => vec![attach(&stem, "ем")],
+        (Declension::NeuterE, false, Prepositional) => vec![attach(&stem, "е")],
+        (Declension::NeuterE, true, Nominative) => vec![attach(&stem, "я")],
+        // солнце → солнц, but поле → полей
+        (Declension::NeuterE, true, Genitive) if last_char(&stem).is_some_and(|c| HUSHING.contains(&c) || c == 'ц') => {
+            return None;
+        }
+        (Declension::NeuterE, true, Genitive) => vec![attach(&stem, "ей")],
+
+        (Declension::NeuterIe, false, Genitive) => vec![attach(&stem, "я")],
+        (Declension::NeuterIe, false, Dative) => vec![attach(&stem, "ю")],
+        (Declension::NeuterIe, false, Accusative) => vec![word.to_string()],
+        (Declension::NeuterIe, false, Instrumental) => vec![attach(&stem, "ем")],
+        (Declension::NeuterIe, false, Prepositional) => vec![attach(&stem, "и")],
+        (Declension::NeuterIe, true, Nominative) => vec![attach(&stem, "я")],
+        (Declension::NeuterIe, true, Genitive) => vec![attach(&stem, "й")],
+
+        // Plural dative, instrumental and prepositional are shared by all
+        // declensions: -ам/-ям, -ами/-ями, -ах/-ях
+        (class, true, Dative | Instrumental | Prepositional) => {
+            let soft = matches!(
+                class,
+                Declension::MasculineSoft | Declension::MasculineJ | Declension::Ya | Declension::Iya
+                    | Declension::FeminineSoft | Declension::NeuterE | Declension::NeuterIe
+            );
+            let base = if matches!(class, Declension::MasculineHard | Declension::MasculineSoft | Declension::MasculineJ) {
+                &oblique
+            } else {
+                &stem
+            };
+            let ending = match (case, soft) {
+                (Dative, false) => "ам",
+                (Dative, true) => "ям",
+                (Instrumental, false) => "ами",
+                (Instrumental, true) => "ями",
+                (_, false) => "ах",
+                (_, true) => "ях",
+            };
+            vec![attach(base, ending)]
+        }
+
+        _ => return None,
+    };
+
+    Some(forms)
+}
+
+// ---------------------------------------------------------------------------
+// Verbs
+
+// Verb prefixes used to recognise prefixed forms of irregular verbs
+// (написать → писать)
+const PREFIXES: &[&str] = &[
+    "по", "за", "вы", "на", "про", "пере", "при", "у", "от", "ото", "отъ", "до", "раз", "разо",
+    "разъ", "рас", "с", "со", "съ", "в", "во", "вз", "взо", "вс", "об", "обо", "объ", "под",
+    "подо", "подъ", "из", "изо", "недо", "над",
+];
+
+// Split `verb` into a known prefix and `base`. A consonant-final prefix
+// takes ъ before е/ё/ю/я (съесть), so с + есть is not сесть.
+fn prefixed<'a>(verb: &'a str, base: &str) -> Option<&'a str> {
+    let prefix = verb.strip_suffix(base)?;
+    if prefix.is_empty() {
+        return Some(prefix);
+    }
+    if !PREFIXES.contains(&prefix) {
+        return None;
+    }
+    let iotated = base.starts_with(['е', 'ё', 'ю', 'я']);
+    if iotated && last_char(prefix).is_some_and(|c| !is_vowel(c) && c != 'ъ') {
+        return None;
+    }
+    Some(prefix)
+}
+
+struct IrregularVerb {
+    infinitive: &'static str,
+    // 1sg, 2sg, 3sg, 1pl, 2pl, 3pl
+    present: [&'static str; 6],
+    // m, f, n, pl; None when regular
+    past: Option<[&'static str; 4]>,
+    // sg, pl; None when there is no usable imperative (мочь, хотеть)
+    imperative: Option<[&'static str; 2]>,
+}
+
+const IRREGULAR_VERBS: &[IrregularVerb] = &[
+    IrregularVerb { infinitive: "быть", present: ["буду", "будешь", "будет", "будем", "будете", "будут"], past: None, imperative: Some(["будь", "будьте"]) },
+    IrregularVerb { infinitive: "хотеть", present: ["хочу", "хочешь", "хочет", "хотим", "хотите", "хотят"], past: None, imperative: None },
+    IrregularVerb { infinitive: "есть", present: ["ем", "ешь", "ест", "едим", "едите", "едят"], past: Some(["ел", "ела", "ело", "ели"]), imperative: Some(["ешь", "ешьте"]) },
+    IrregularVerb { infinitive: "дать", present: ["дам", "дашь", "даст", "дадим", "дадите", "дадут"], past: None, imperative: Some(["дай", "дайте"]) },
+    IrregularVerb { infinitive: "идти", present: ["иду", "идёшь", "идёт", "идём", "идёте", "идут"], past: Some(["шёл", "шла", "шло", "шли"]), imperative: Some(["иди", "идите"]) },
+    IrregularVerb { infinitive: "йти", present: ["йду", "йдёшь", "йдёт", "йдём", "йдёте", "йдут"], past: Some(["шёл", "шла", "шло", "шли"]), imperative: Some(["йди", "йдите"]) },
+    IrregularVerb { infinitive: "ехать", present: ["еду", "едешь", "едет", "едем", "едете", "едут"], past: None, imperative: None },
+    IrregularVerb { infinitive: "мочь", present: ["могу", "можешь", "может", "можем", "можете", "могут"], past: Some(["мог", "могла", "могло", "могли"]), imperative: None },
+    IrregularVerb { infinitive: "бежать", present: ["бегу", "бежишь", "бежит", "бежим", "бежите", "бегут"], past: None, imperative: Some(["беги", "бегите"]) },
+    IrregularVerb { infinitive: "жить", present: ["живу", "живёшь", "живёт", "живём", "живёте", "живут"], past: None, imperative: Some(["живи", "живите"]) },
+    IrregularVerb { infinitive: "пить", present: ["пью", "пьёшь", "пьёт", "пьём", "пьёте", "пьют"], past: None, imperative: Some(["пей", "пейте"]) },
+    IrregularVerb { infinitive: "бить", present: ["бью", "бьёшь", "бьёт", "бьём", "бьёте", "бьют"], past: None, imperative: Some(["бей", "бейте"]) },
+    IrregularVerb { infinitive: "лить", present: ["лью", "льёшь", "льёт", "льём", "льёте", "льют"], past: None, imperative: Some(["лей", "лейте"]) },
+    IrregularVerb { infinitive: "шить", present: ["шью", "шьёшь", "шьёт", "шьём", "шьёте", "шьют"], past: None, imperative: Some(["шей", "шейте"]) },
+    IrregularVerb { infinitive: "писать", present: ["пишу", "пишешь", "пишет", "пишем", "пишете", "пишут"], past: None, imperative: Some(["пиши", "пишите"]) },
+    IrregularVerb { infinitive: "казать", present: ["кажу", "кажешь", "кажет", "кажем", "кажете", "кажут"], past: None, imperative: Some(["кажи", "кажите"]) },
+    IrregularVerb { infinitive: "искать", present: ["ищу", "ищешь", "ищет", "ищем", "ищете", "ищут"], past: None, imperative: Some(["ищи", "ищите"]) },
+    IrregularVerb { infinitive: "плакать", present: ["плачу", "плачешь", "плачет", "плачем", "плачете", "плачут"], past: None, imperative: Some(["плачь", "плачьте"]) },
+    IrregularVerb { infinitive: "брать", present: ["беру", "берёшь", "берёт", "берём", "берёте", "берут"], past: None, imperative: Some(["бери", "берите"]) },
+    IrregularVerb { infinitive: "взять", present: ["возьму", "возьмёшь", "возьмёт", "возьмём", "возьмёте", "возьмут"], past: None, imperative: Some(["возьми", "возьмите"]) },
+    IrregularVerb { infinitive: "ждать", present: ["жду", "ждёшь", "ждёт", "ждём", "ждёте", "ждут"], past: None, imperative: Some(["жди", "ждите"]) },
+    IrregularVerb { infinitive: "звать", present: ["зову", "зовёшь", "зовёт", "зовём", "зовёте", "зовут"], past: None, imperative: Some(["зови", "зовите"]) },
+    IrregularVerb { infinitive: "петь", present: ["пою", "поёшь", "поёт", "поём", "поёте", "поют"], past: None, imperative: Some(["пой", "пойте"]) },
+    IrregularVerb { infinitive: "мыть", present: ["мою", "моешь", "моет", "моем", "моете", "моют"], past: None, imperative: Some(["мой", "мойте"]) },
+    IrregularVerb { infinitive: "крыть", present: ["крою", "кроешь", "кроет", "кроем", "кроете", "кроют"], past: None, imperative: Some(["крой", "кройте"]) },
+    IrregularVerb { infinitive: "стать", present: ["стану", "станешь", "станет", "станем", "станете", "станут"], past: None, imperative: Some(["стань", "станьте"]) },
+    IrregularVerb { infinitive: "начать", present: ["начну", "начнёшь", "начнёт", "начнём", "начнёте", "начнут"], past: None, imperative: Some(["начни", "начните"]) },
+    IrregularVerb { infinitive: "спать", present: ["сплю", "спишь", "спит", "спим", "спите", "спят"], past: None, imperative: Some(["спи", "спите"]) },
+];
+
+// -еть and -ать verbs that belong to the second conjugation (смотреть →
+// смотришь, слышать → слышишь); the rest are conjugated as first
+const SECOND_CONJUGATION: &[&str] = &[
+    "видеть", "смотреть", "сидеть", "лететь", "висеть", "терпеть", "вертеть", "зависеть",
+    "обидеть", "ненавидеть", "шуметь", "гореть", "звенеть", "свистеть", "храпеть", "кипеть",
+    "блестеть", "велеть", "болеть",
+    "слышать", "дышать", "держать", "молчать", "кричать", "стучать", "звучать", "лежать",
+    "дрожать", "стоять", "бояться",
+];
+
+// -ать/-ять/-еть verbs known to keep the vowel stem (читать → читаю).
+// Others of this shape alternate (писать → пишу), shift stems (понять →
+// пойму) or belong to a different class (надеть → надену), so they are only
+// conjugated when listed here or in IRREGULAR_VERBS.
+const VOWEL_STEM_VERBS: &[&str] = &[
+    "читать", "делать", "знать", "думать", "играть", "слушать", "работать", "понимать",
+    "отвечать", "изучать", "получать", "помогать", "начинать", "кончать", "отдыхать",
+    "обедать", "завтракать", "ужинать", "бегать", "летать", "плавать", "гулять",
+    "стрелять", "терять", "повторять", "объяснять", "проверять", "менять", "решать",
+    "мечтать", "считать", "спрашивать", "уметь", "иметь", "краснеть", "бледнеть",
+];
+
+const PERSON_KEYS: [&str; 6] = ["pres_1sg", "pres_2sg", "pres_3sg", "pres_1pl", "pres_2pl", "pres_3pl"];
+const PAST_KEYS: [&str; 4] = ["past_m", "past_f", "past_n", "past_pl"];
+const IMPERATIVE_KEYS: [&str; 2] = ["imp_sg", "imp_pl"];
+
+fn add_reflexive(form: &str) -> String {
+    if last_char(form).is_some_and(is_vowel) {
+        format!("{}сь", form)
+    } else {
+        format!("{}ся", form)
+    }
+}
+
+// 1sg consonant alternation in the second conjugation (просить → прошу,
+// любить → люблю)
+fn mutate_first_person(stem: &str) -> String {
+    if let Some(head) = stem.strip_suffix("ст") {
+        return format!("{}щ", head);
+    }
+    let Some(last) = last_char(stem) else { return stem.to_string() };
+    let head = &stem[..stem.len() - last.len_utf8()];
+    match last {
+        'д' | 'з' => format!("{}ж", head),
+        'т' => format!("{}ч", head),
+        'с' => format!("{}ш", head),
+        'б' | 'п' | 'в' | 'ф' | 'м' => format!("{}л", stem),
+        _ => stem.to_string(),
+    }
+}
+
+struct Conjugation {
+    present: [Vec<String>; 6],
+    // Infinitive stem for the past tense (читать → чита-)
+    past_stem: Option<String>,
+    past: Option<[String; 4]>,
+    imperative: Option<[String; 2]>,
+    defective_imperative: bool,
+}
+
+fn regular_present(verb: &str) -> Option<[Vec<String>; 6]> {
+    let first = |stem: &str, vowel_stem: bool| -> [Vec<String>; 6] {
+        let (sg1, pl3) = if vowel_stem { ("ю", "ют") } else { ("у", "ут") };
+        [
+            vec![attach(stem, sg1)],
+            vec![attach(stem, "ешь")],
+            vec![attach(stem, "ет")],
+            vec![attach(stem, "ем")],
+            vec![attach(stem, "ете")],
+            vec![attach(stem, pl3)],
+        ]
+    };
+    let second = |stem: &str| -> [Vec<String>; 6] {
+        [
+            vec![attach(&mutate_first_person(stem), "ю")],
+            vec![attach(stem, "ишь")],
+            vec![attach(stem, "ит")],
+            vec![attach(stem, "им")],
+            vec![attach(stem, "ите")],
+            vec![attach(stem, "ят")],
+        ]
+    };
+
+    if SECOND_CONJUGATION.iter().any(|base| prefixed(verb, base).is_some()) {
+        let stem = verb.strip_suffix("еть")
+            .or_else(|| verb.strip_suffix("ать"))
+            .or_else(|| verb.strip_suffix("ять"))?;
+        return Some(second(stem));
+    }
+    if ["давать", "ставать", "знавать"].iter().any(|base| verb.ends_with(base)) {
+        // давать → даю, даёшь
+        let stem = &verb[..verb.len() - "вать".len()];
+        let forms = first(stem, true);
+        return Some(forms.map(|f| vec![f[0].replacen("ае", "аё", 1)]));
+    }
+    if let Some(stem) = verb.strip_suffix("овать").or_else(|| verb.strip_suffix("евать")) {
+        // рисовать → рисую; танцевать → танцую
+        return Some(first(&format!("{}у", stem), true));
+    }
+    if let Some(stem) = verb.strip_suffix("нуть") {
+        // крикнуть → крикну
+        return Some(first(&format!("{}н", stem), false));
+    }
+    if let Some(stem) = verb.strip_suffix("ить") {
+        // One-syllable -ить verbs (пить, жить) are irregular
+        if grammar::vowel_count(verb) < 2 {
+            return None;
+        }
+        return Some(second(stem));
+    }
+    // Imperfectives in -ывать/-ивать are regular (рассказывать → рассказываю)
+    let suffixed = verb.ends_with("ывать") || verb.ends_with("ивать");
+    if suffixed || VOWEL_STEM_VERBS.iter().any(|base| prefixed(verb, base).is_some()) {
+        let stem = &verb[..verb.len() - "ть".len()];
+        return Some(first(stem, true));
+    }
+    None
+}
+
+fn conjugate(infinitive: &str, grammar: &VocabularyGrammar) -> Option<Conjugation> {
+    let (verb, reflexive) = match infinitive.strip_suffix("ся").or_else(|| infinitive.strip_suffix("сь")) {
+        Some(base) => (base, true),
+        None => (infinitive, false),
+    };
+
+    let irregular = IRREGULAR_VERBS.iter()
+        .find_map(|v| prefixed(verb, v.infinitive).map(|prefix| (prefix, v)));
+
+    let mut conjugation = if let Some((prefix, irregular)) = irregular {
+        let with_prefix = |form: &str| {
+            // при + йду → приду; раз + бью → разобью, с + шью → сошью
+            let form = if prefix == "при" { form.strip_prefix('й').unwrap_or(form) } else { form };
+            let joiner = if prefix.ends_with(|c: char| !is_vowel(c) && c != 'ъ') && form.chars().nth(1) == Some('ь') {
+                "о"
+            } else {
+                ""
+            };
+            let form = format!("{}{}{}", prefix, joiner, form);
+            // вы- takes the stress in perfectives: выпьешь, вышел
+            if prefix == "вы" { form.replace('ё', "е") } else { form }
+        };
+        Conjugation {
+            present: irregular.present.map(|f| vec![with_prefix(f)]),
+            past_stem: verb.strip_suffix("ть").map(str::to_string),
+            past: irregular.past.map(|forms| forms.map(with_prefix)),
+            imperative: irregular.imperative.map(|forms| forms.map(with_prefix)),
+            defective_imperative: irregular.imperative.is_none(),
+        }
+    } else {
+        let present = regular_present(verb)?;
+        Conjugation {
+            present,
+            past_stem: verb.strip_suffix("ть").map(str::to_string),
+            past: None,
+            imperative: None,
+            defective_imperative: false,
+        }
+    };
+
+    if conjugation.past.is_none() {
+        let stem = conjugation.past_stem.clone()?;
+        conjugation.past = Some(["л", "ла", "ло", "ли"].map(|ending| format!("{}{}", stem, ending)));
+    }
+
+    if conjugation.imperative.is_none() && !conjugation.defective_imperative {
+        // From the 3pl stem: vowel → -й (читай), consonant → -и (говори),
+        // or -ь when the stem is stressed (гото́вь)
+        let third_plural = &conjugation.present[5][0];
+        let stem = ["ют", "ут", "ят", "ат"].iter()
+            .find_map(|e| third_plural.strip_suffix(e))?
+            .to_string();
+        let stem = if ["давать", "ставать", "знавать"].iter().any(|base| verb.ends_with(base)) {
+            format!("{}ва", stem)
+        } else {
+            stem
+        };
+        let stem_stressed = grammar.stress_position
+            .is_some_and(|p| (p as usize) < grammar::vowel_count(verb))
+            && verb.ends_with("ить");
+        let singular = if last_char(&stem).is_some_and(is_vowel) {
+            format!("{}й", stem)
+        } else if stem_stressed {
+            format!("{}ь", stem)
+        } else {
+            format!("{}и", stem)
+        };
+        let plural = format!("{}те", singular);
+        conjugation.imperative = Some([singular, plural]);
+    }
+
+    if reflexive {
+        conjugation.present = conjugation.present.map(|forms| forms.iter().map(|f| add_reflexive(f)).collect());
+        conjugation.past = conjugation.past.map(|forms| forms.map(|f| add_reflexive(&f)));
+        conjugation.imperative = conjugation.imperative.map(|forms| forms.map(|f| add_reflexive(&f)));
+    }
+
+    Some(conjugation)
+}
+
+// ---------------------------------------------------------------------------
+
+// Only words tagged as verbs: кровать and память end in -ть too
+pub fn is_verb(grammar: &VocabularyGrammar) -> bool {
+    grammar.part_of_speech == Some(PartOfSpeech::Verb)
+}
+
+pub fn is_noun(grammar: &VocabularyGrammar) -> bool {
+    grammar.part_of_speech == Some(PartOfSpeech::Noun)
+}
+
+// Accepted answers for `form_key` of `word`, most likely first. None when the
+// form can't be produced reliably.
+pub fn inflect(word: &str, grammar: &VocabularyGrammar, form_key: &str) -> Option<Vec<String>> {
+    if let Some(form) = grammar.irregular_forms.get(form_key) {
+        // Several accepted spellings may be stored comma separated
+        let forms: Vec<String> = form.split(',')
+            .map(|f| morphology::strip_stress(f.trim()))
+            .filter(|f| !f.is_empty())
+            .collect();
+        return if forms.is_empty() { None } else { Some(forms) };
+    }
+
+    let word = morphology::strip_stress(word.trim()).to_lowercase();
+    if word.contains(' ') {
+        return None;
+    }
+
+    if is_verb(grammar) {
+        let conjugation = conjugate(&word, grammar)?;
+        if let Some(i) = PERSON_KEYS.iter().position(|k| *k == form_key) {
+            return Some(conjugation.present[i].clone());
+        }
+        if let Some(i) = PAST_KEYS.iter().position(|k| *k == form_key) {
+            return conjugation.past.map(|forms| vec![forms[i].clone()]);
+        }
+        if let Some(i) = IMPERATIVE_KEYS.iter().position(|k| *k == form_key) {
+            return conjugation.imperative.map(|forms| vec![forms[i].clone()]);
+        }
+        return None;
+    }
+
+    if is_noun(grammar) {
+        let (case, number) = form_key.split_once('_')?;
+        let case = GrammaticalCase::ALL.into_iter().find(|c| c.key() == case)?;
+        let plural = match number {
+            "sg" => false,
+            "pl" => true,
+            _ => return None,
+        };
+        return decline_noun(&word, grammar, case, plural);
+    }
+
+    None
+}
+
+pub fn noun_form_keys() -> Vec<String> {
+    let mut keys = Vec::new();
+    for plural in [false, true] {
+        for case in GrammaticalCase::ALL {
+            keys.push(format!("{}_{}", case.key(), if plural { "pl" } else { "sg" }));
+        }
+    }
+    keys
+}
+
+pub fn person_keys() -> &'static [&'static str] {
+    &PERSON_KEYS
+}
+
+pub fn past_keys() -> &'static [&'static str] {
+    &PAST_KEYS
+}
+
+pub fn imperative_keys() -> &'static [&'static str] {
+    &IMPERATIVE_KEYS
+}
+
+// Human-readable name of a form, e.g. "genitive plural" or "3rd person
+// plural future"
+pub fn describe_form(form_key: &str, aspect: Option<Aspect>) -> String {
+    let tense = if aspect == Some(Aspect::Perfective) { "future" } else { "present" };
+    match form_key {
+        "pres_1sg" => format!("1st person singular {}", tense),
+        "pres_2sg" => format!("2nd person singular {}", tense),
+        "pres_3sg" => format!("3rd person singular {}", tense),
+        "pres_1pl" => format!("1st person plural {}", tense),
+        "pres_2pl" => format!("2nd person plural {}", tense),
+        "pres_3pl" => format!("3rd person plural {}", tense),
+        "past_m" => "masculine past tense".to_string(),
+        "past_f" => "feminine past tense".to_string(),
+        "past_n" => "neuter past tense".to_string(),
+        "past_pl" => "plural past tense".to_string(),
+        "imp_sg" => "singular imperative".to_string(),
+        "imp_pl" => "plural imperative".to_string(),
+        key => match key.split_once('_') {
+            Some((case, number)) => {
+                let case = GrammaticalCase::ALL.into_iter()
+                    .find(|c| c.key() == case)
+                    .map(|c| c.as_str())
+                    .unwrap_or(case);
+                format!("{} {}", case, if number == "pl" { "plural" } else { "singular" })
+            }
+            None => key.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verb() -> VocabularyGrammar {
+        VocabularyGrammar { part_of_speech: Some(PartOfSpeech::Verb), ..Default::default() }
+    }
+
+    fn noun(gender: Gender) -> VocabularyGrammar {
+        VocabularyGrammar { part_of_speech: Some(PartOfSpeech::Noun), gender: Some(gender), ..Default::default() }
+    }
+
+    fn first(word: &str, grammar: &VocabularyGrammar, key: &str) -> Option<String> {
+        inflect(word, grammar, key).map(|forms| forms[0].clone())
+    }
+
+    #[test]
+    fn conjugates_regular_and_prefixed_verbs() {
+        assert_eq!(first("читать", &verb(), "pres_1sg").as_deref(), Some("читаю"));
+        assert_eq!(first("прочитать", &verb(), "pres_3pl").as_deref(), Some("прочитают"));
+        assert_eq!(first("говорить", &verb(), "pres_2sg").as_deref(), Some("говоришь"));
+        assert_eq!(first("написать", &verb(), "pres_1sg").as_deref(), Some("напишу"));
+        assert_eq!(first("съесть", &verb(), "pres_1sg").as_deref(), Some("съем"));
+        assert_eq!(first("давать", &verb(), "pres_2sg").as_deref(), Some("даёшь"));
+        assert_eq!(first("плавать", &verb(), "pres_1sg").as_deref(), Some("плаваю"));
+        assert_eq!(first("рассказывать", &verb(), "pres_1sg").as_deref(), Some("рассказываю"));
+    }
+
+    #[test]
+    fn refuses_verbs_the_rules_cant_handle() {
+        for word in ["сесть", "успеть", "понять", "принять", "создать", "резать", "описать", "надеть"] {
+            assert_eq!(inflect(word, &verb(), "pres_1sg"), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn only_tagged_verbs_are_conjugated() {
+        let untagged = VocabularyGrammar::default();
+        for word in ["кровать", "память", "сеть"] {
+            assert_eq!(inflect(word, &untagged, "pres_1sg"), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn declines_regular_nouns() {
+        assert_eq!(first("стол", &noun(Gender::Masculine), "gen_sg").as_deref(), Some("стола"));
+        assert_eq!(first("книга", &noun(Gender::Feminine), "gen_pl").as_deref(), Some("книг"));
+        assert_eq!(first("дверь", &noun(Gender::Feminine), "ins_sg").as_deref(), Some("дверью"));
+        assert_eq!(first("сок", &noun(Gender::Masculine), "gen_sg").as_deref(), Some("сока"));
+    }
+
+    #[test]
+    fn refuses_fleeting_vowels_and_irregular_nouns() {
+        for (word, gender) in [
+            ("отец", Gender::Masculine),
+            ("подарок", Gender::Masculine),
+            ("день", Gender::Masculine),
+            ("мать", Gender::Feminine),
+            ("дочь", Gender::Feminine),
+        ] {
+            assert_eq!(inflect(word, &noun(gender), "gen_sg"), None, "{}", word);
+            assert_eq!(inflect(word, &noun(gender), "gen_pl"), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn stored_genitive_reveals_the_oblique_stem() {
+        let mut grammar = noun(Gender::Masculine);
+        grammar.irregular_forms.insert("gen_sg".to_string(), "отца́".to_string());
+        assert_eq!(first("отец", &grammar, "dat_sg").as_deref(), Some("отцу"));
+        assert_eq!(first("отец", &grammar, "nom_pl").as_deref(), Some("отцы"));
+    }
+}
@@ -0,0 +1,9 @@
+// Russian language helpers shared by vocabulary, drills and search
+pub mod morphology;
+pub mod grammar;
+pub mod inflection;
+pub mod form_drill;
+pub mod transliteration;
+pub mod rubric;
+pub mod extraction;
+pub mod glossary;
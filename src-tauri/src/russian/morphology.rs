@@ -0,0 +1,304 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use rust_stemmers::{Algorithm, Stemmer};
+use super::{grammar, inflection};
+
+// Combining grave and acute accents, used to mark stress in learner texts
+fn is_stress_mark(c: char) -> bool {
+    matches!(c, '\u{300}' | '\u{301}')
+}
+
+pub fn strip_stress(text: &str) -> String {
+    text.chars().filter(|c| !is_stress_mark(*c)).collect()
+}
+
+// ё is optional in written Russian, so treat it as е everywhere we compare
+pub fn fold_yo(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ё' => 'е',
+            'Ё' => 'Е',
+            _ => c,
+        })
+        .collect()
+}
+
+// Spelling-insensitive form of a word: lowercase, no stress marks, ё as е
+pub fn normalize(word: &str) -> String {
+    fold_yo(&strip_stress(word.trim())).to_lowercase()
+}
+
+// Snowball stem of a single word; non-Cyrillic words come back unchanged
+pub fn stem(word: &str) -> String {
+    let word = normalize(word);
+    let stemmed = Stemmer::create(Algorithm::Russian).stem(&word).into_owned();
+    if stemmed.is_empty() { word } else { stemmed }
+}
+
+// Key shared by all inflected forms of a word or phrase: the stem of each
+// word, space separated. "книги" and "книгу" both give "книг".
+pub fn lemma_key(text: &str) -> String {
+    let stemmer = Stemmer::create(Algorithm::Russian);
+    normalize(text)
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .map(|word| word.trim_matches('-'))
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let stemmed = stemmer.stem(word);
+            if stemmed.is_empty() { word.to_string() } else { stemmed.into_owned() }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Stems of every word in a text, in order, for finding vocabulary in
+// free-form answers
+pub fn text_stems(text: &str) -> Vec<String> {
+    lemma_key(text).split(' ').filter(|s| !s.is_empty()).map(str::to_string).collect()
+}
+
+// Whether `phrase` (any inflection of it) occurs in a text given as its
+// `text_stems`. Phrases must appear as consecutive words.
+pub fn mentions(stems: &[String], phrase: &str) -> bool {
+    let wanted = text_stems(phrase);
+    !wanted.is_empty() && stems.windows(wanted.len()).any(|window| window == wanted.as_slice())
+}
+
+// Fill in the lemma for words inserted without one (imports, older rows)
+pub fn backfill_lemmas(conn: &Connection) -> Result<usize, String> {
+    let words: Vec<(i64, String)> = conn.prepare(
+        "SELECT id, word_ru FROM RussianVocabulary WHERE lemma IS NULL"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    for (id, word) in &words {
+        conn.execute(
+            "UPDATE RussianVocabulary SET lemma = ?1 WHERE id = ?2",
+            params![lemma_key(word), id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(words.len())
+}
+
+// Whether the entry's grammar generates `word` as one of its forms. Entries
+// without a part of speech generate nothing.
+fn generates_form(conn: &Connection, vocabulary_id: i64, word_ru: &str, word: &str) -> Result<bool, String> {
+    let grammar = grammar::load_grammar(conn, vocabulary_id)?;
+    let keys: Vec<String> = if inflection::is_noun(&grammar) {
+        inflection::noun_form_keys()
+    } else if inflection::is_verb(&grammar) {
+        inflection::person_keys().iter()
+            .chain(inflection::past_keys())
+            .chain(inflection::imperative_keys())
+            .map(|k| k.to_string())
+            .collect()
+    } else {
+        return Ok(false);
+    };
+
+    let wanted = normalize(word);
+    Ok(keys.iter()
+        .filter_map(|key| inflection::inflect(word_ru, &grammar, key))
+        .flatten()
+        .any(|form| normalize(&form) == wanted))
+}
+
+// Entries sharing `word`'s stem, oldest first. A shared stem alone doesn't
+// make two words the same (нос/носить, гора/горе).
+fn stem_matches(conn: &Connection, word: &str, material_id: Option<i64>) -> Result<Vec<(i64, String)>, String> {
+    let lemma = lemma_key(word);
+    if lemma.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    conn.prepare(
+        "SELECT id, word_ru FROM RussianVocabulary
+         WHERE lemma = ?1 AND (?2 IS NULL OR material_id = ?2)
+         ORDER BY id"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![lemma, material_id], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())
+}
+
+// The entry `word` belongs to: the word itself, a recorded form of it, or
+// an entry sharing its stem whose grammar inflects to it. Only entries of
+// `material_id` when given. Returns (id, word_ru).
+pub fn find_entry(conn: &Connection, word: &str, material_id: Option<i64>) -> Result<Option<(i64, String)>, String> {
+    let exact = conn.query_row(
+        "SELECT id, word_ru FROM RussianVocabulary
+         WHERE word_ru = ?1 AND (?2 IS NULL OR material_id = ?2)
+         UNION ALL
+         SELECT v.id, v.word_ru FROM VocabularyForms f
+         JOIN RussianVocabulary v ON f.vocabulary_id = v.id
+         WHERE f.form = ?1 AND (?2 IS NULL OR v.material_id = ?2)
+         LIMIT 1",
+        params![word.trim(), material_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional().map_err(|e| e.to_string())?;
+
+    if exact.is_some() {
+        return Ok(exact);
+    }
+
+    for (id, word_ru) in stem_matches(conn, word, material_id)? {
+        if generates_form(conn, id, &word_ru, word)? {
+            return Ok(Some((id, word_ru)));
+        }
+    }
+
+    Ok(None)
+}
+
+// The entry `word` might be a form of, by stem alone, for the user to
+// confirm before grouping
+pub fn find_group_candidate(conn: &Connection, word: &str) -> Result<Option<(i64, String)>, String> {
+    if let Some(entry) = find_entry(conn, word, None)? {
+        return Ok(Some(entry));
+    }
+    Ok(stem_matches(conn, word, None)?.into_iter().next())
+}
+
+// Record `form` as an inflection of an existing entry. Spellings that only
+// differ by ё or stress are the same word and aren't stored.
+pub fn add_form(conn: &Connection, vocabulary_id: i64, word_ru: &str, form: &str) -> Result<bool, String> {
+    if normalize(form) == normalize(word_ru) {
+        return Ok(false);
+    }
+
+    let changed = conn.execute(
+        "INSERT OR IGNORE INTO VocabularyForms (vocabulary_id, form) VALUES (?1, ?2)",
+        params![vocabulary_id, form.trim()],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(changed > 0)
+}
+
+// Add a word to the vocabulary, or group it under the entry it is a form of
+// (`group_with`, or the entry find_entry finds unless `keep_separate`).
+// Shared by the vocabulary commands and imports. Returns the entry id and
+// whether the word was grouped under an existing entry.
+pub fn insert_vocabulary(
+    conn: &Connection,
+    word_ru: &str,
+    translation_en: &str,
+    material_id: Option<i64>,
+    example_sentence: Option<&str>,
+    keep_separate: bool,
+    group_with: Option<i64>,
+) -> Result<(i64, bool), String> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM RussianVocabulary WHERE word_ru = ?1)",
+        params![word_ru],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    if exists {
+        return Err(format!("'{}' is already in your vocabulary", word_ru));
+    }
+
+    let group = match group_with {
+        Some(id) => Some((id, conn.query_row(
+            "SELECT word_ru FROM RussianVocabulary WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, String>(0)
+        ).map_err(|_| format!("Vocabulary entry {} not found", id))?)),
+        None if !keep_separate => find_entry(conn, word_ru, None)?,
+        None => None,
+    };
+
+    if let Some((vocab_id, base_word)) = group {
+        add_form(conn, vocab_id, &base_word, word_ru)?;
+        // Keep the caller's translation alongside the entry's own
+        conn.execute(
+            "UPDATE RussianVocabulary SET material_id = COALESCE(material_id, ?1),
+                                          example_sentence = COALESCE(example_sentence, ?2),
+                                          translation_en = CASE
+                                              WHEN ?3 = '' OR instr(lower(translation_en), lower(?3)) > 0 THEN translation_en
+                                              ELSE translation_en || '; ' || ?3
+                                          END
+             WHERE id = ?4",
+            params![material_id, example_sentence, translation_en.trim(), vocab_id],
+        ).map_err(|e| e.to_string())?;
+
+        return Ok((vocab_id, true));
+    }
+
+    conn.execute(
+        "INSERT INTO RussianVocabulary (word_ru, translation_en, material_id, example_sentence, lemma)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![word_ru, translation_en, material_id, example_sentence, lemma_key(word_ru)],
+    ).map_err(|e| e.to_string())?;
+
+    Ok((conn.last_insert_rowid(), false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::russian::grammar::{Gender, PartOfSpeech, VocabularyGrammar};
+
+    fn insert(conn: &Connection, word: &str, grammar: Option<VocabularyGrammar>) -> i64 {
+        conn.execute(
+            "INSERT INTO RussianVocabulary (word_ru, translation_en, lemma) VALUES (?1, '', ?2)",
+            params![word, lemma_key(word)],
+        ).unwrap();
+        let id = conn.last_insert_rowid();
+        if let Some(grammar) = grammar {
+            grammar::save_grammar(conn, id, &grammar).unwrap();
+        }
+        id
+    }
+
+    fn noun(gender: Gender) -> Option<VocabularyGrammar> {
+        Some(VocabularyGrammar { part_of_speech: Some(PartOfSpeech::Noun), gender: Some(gender), ..Default::default() })
+    }
+
+    #[test]
+    fn groups_forms_the_grammar_produces() {
+        let conn = db::open_in_memory();
+        let id = insert(&conn, "книга", noun(Gender::Feminine));
+
+        assert_eq!(find_entry(&conn, "книги", None).unwrap(), Some((id, "книга".to_string())));
+        assert_eq!(find_entry(&conn, "книгу", None).unwrap(), Some((id, "книга".to_string())));
+    }
+
+    #[test]
+    fn shared_stems_alone_are_not_grouped() {
+        let conn = db::open_in_memory();
+        let nose = insert(&conn, "нос", noun(Gender::Masculine));
+        insert(&conn, "вода", None);
+
+        assert_eq!(find_entry(&conn, "носить", None).unwrap(), None);
+        assert_eq!(find_entry(&conn, "водить", None).unwrap(), None);
+        // Still offered for the user to confirm
+        assert_eq!(find_group_candidate(&conn, "носить").unwrap().map(|(id, _)| id), Some(nose));
+    }
+
+    #[test]
+    fn recorded_forms_are_found() {
+        let conn = db::open_in_memory();
+        let id = insert(&conn, "мать", None);
+        add_form(&conn, id, "мать", "матери").unwrap();
+
+        assert_eq!(find_entry(&conn, "матери", None).unwrap(), Some((id, "мать".to_string())));
+    }
+
+    #[test]
+    fn lookups_can_be_scoped_to_a_material() {
+        let conn = db::open_in_memory();
+        conn.execute("INSERT INTO Materials (id, name_en) VALUES (1, 'Limits'), (2, 'Series')", []).unwrap();
+        let id = insert(&conn, "книга", noun(Gender::Feminine));
+        conn.execute("UPDATE RussianVocabulary SET material_id = 1 WHERE id = ?1", params![id]).unwrap();
+
+        assert_eq!(find_entry(&conn, "книги", Some(1)).unwrap().map(|(id, _)| id), Some(id));
+        assert_eq!(find_entry(&conn, "книга", Some(2)).unwrap(), None);
+        assert_eq!(find_entry(&conn, "книги", Some(2)).unwrap(), None);
+    }
+}
@@ -0,0 +1,295 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use super::morphology;
+
+// A drill is mastered after this many scored drills in a row, for the same
+// material, each meeting the mastery bar below
+pub const MASTERY_STREAK: usize = 3;
+const MASTERED_OVERALL: f64 = 4.0;
+const MASTERED_ACCURACY: i32 = 4;
+const MASTERED_COVERAGE: f64 = 0.8;
+const PRACTICING_OVERALL: f64 = 3.0;
+
+// Self-assessed parts of the rubric, each 1-5
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RubricInput {
+    pub fluency: i32,
+    pub accuracy: i32,
+    pub completeness: i32,
+}
+
+impl RubricInput {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, score) in [
+            ("Fluency", self.fluency),
+            ("Accuracy", self.accuracy),
+            ("Completeness", self.completeness),
+        ] {
+            if !(1..=5).contains(&score) {
+                return Err(format!("{} must be between 1 and 5", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrillRubric {
+    pub fluency: i32,
+    pub accuracy: i32,
+    pub completeness: i32,
+    // Share of the material's vocabulary and glossary terms used in
+    // resolution_ru; None when the material has neither
+    pub terminology_coverage: Option<f64>,
+    pub terms_used: Vec<String>,
+    pub terms_missing: Vec<String>,
+    // Mean of the four criteria on the 1-5 scale (coverage scaled to it)
+    pub overall: f64,
+    pub scored_at: String,
+}
+
+impl DrillRubric {
+    fn meets_mastery(&self) -> bool {
+        self.overall >= MASTERED_OVERALL
+            && self.accuracy >= MASTERED_ACCURACY
+            && self.terminology_coverage.is_none_or(|c| c >= MASTERED_COVERAGE)
+    }
+}
+
+pub fn overall_score(input: &RubricInput, coverage: Option<f64>) -> f64 {
+    let mut scores = vec![input.fluency as f64, input.accuracy as f64, input.completeness as f64];
+    if let Some(coverage) = coverage {
+        scores.push(1.0 + 4.0 * coverage);
+    }
+    let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+    (mean * 100.0).round() / 100.0
+}
+
+// Status of the latest drill in `history` (chronological, scored drills of
+// one material only)
+pub fn derive_status(history: &[DrillRubric]) -> &'static str {
+    let Some(latest) = history.last() else {
+        return "learning";
+    };
+
+    let streak = history.iter().rev().take_while(|r| r.meets_mastery()).count();
+    if streak >= MASTERY_STREAK {
+        "mastered"
+    } else if latest.overall >= PRACTICING_OVERALL {
+        "practicing"
+    } else {
+        "learning"
+    }
+}
+
+// Which of the material's vocabulary, plus glossary terms that aren't
+// vocabulary of the material, appear in `text` (in any inflected form,
+// including forms recorded on a word's vocabulary entry). Returns (used,
+// missing) words.
+pub fn terminology_coverage(
+    conn: &Connection,
+    material_id: i64,
+    text: &str,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let words: Vec<(String, Option<String>)> = conn.prepare(
+        "SELECT v.word_ru, GROUP_CONCAT(f.form, char(31))
+         FROM RussianVocabulary v
+         LEFT JOIN VocabularyForms f ON f.vocabulary_id = v.id
+         WHERE v.material_id = ?1
+         GROUP BY v.id
+         UNION ALL
+         SELECT t.term_ru, GROUP_CONCAT(f.form, char(31))
+         FROM MaterialTerms t
+         LEFT JOIN RussianVocabulary v ON v.word_ru = t.term_ru
+         LEFT JOIN VocabularyForms f ON f.vocabulary_id = v.id
+         WHERE t.material_id = ?1
+           AND NOT EXISTS (
+               SELECT 1 FROM RussianVocabulary own
+               WHERE own.material_id = ?1 AND own.word_ru = t.term_ru
+           )
+         GROUP BY t.id
+         ORDER BY 1"
+    ).map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let stems = morphology::text_stems(text);
+    let mut used = Vec::new();
+    let mut missing = Vec::new();
+
+    for (word, forms) in words {
+        let found = morphology::mentions(&stems, &word)
+            || forms.as_deref().unwrap_or("").split('\u{1f}').any(|form| morphology::mentions(&stems, form));
+        if found { used.push(word) } else { missing.push(word) }
+    }
+
+    Ok((used, missing))
+}
+
+// Reads RUBRIC_COLUMNS starting at column `first`
+pub fn rubric_from_row(row: &rusqlite::Row, first: usize) -> rusqlite::Result<DrillRubric> {
+    let used: String = row.get(first + 4)?;
+    let missing: String = row.get(first + 5)?;
+    Ok(DrillRubric {
+        fluency: row.get(first)?,
+        accuracy: row.get(first + 1)?,
+        completeness: row.get(first + 2)?,
+        terminology_coverage: row.get(first + 3)?,
+        terms_used: serde_json::from_str(&used).unwrap_or_default(),
+        terms_missing: serde_json::from_str(&missing).unwrap_or_default(),
+        overall: row.get(first + 6)?,
+        scored_at: row.get(first + 7)?,
+    })
+}
+
+pub const RUBRIC_COLUMNS: &str =
+    "r.fluency, r.accuracy, r.completeness, r.terminology_coverage, r.terms_used, r.terms_missing,
+     r.overall, r.scored_at";
+
+pub fn load_rubric(conn: &Connection, drill_id: i64) -> Result<Option<DrillRubric>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM DrillRubricScores r WHERE r.drill_id = ?1", RUBRIC_COLUMNS),
+        params![drill_id],
+        |row| rubric_from_row(row, 0)
+    ).optional().map_err(|e| e.to_string())
+}
+
+// Score a drill: the self-assessed criteria plus terminology coverage of its
+// resolution_ru. Re-scoring replaces the previous score. Statuses of the
+// material's scored drills are then re-derived.
+pub fn score_drill(conn: &Connection, drill_id: i64, input: &RubricInput) -> Result<DrillRubric, String> {
+    input.validate()?;
+
+    let (material_id, resolution): (i64, Option<String>) = conn.query_row(
+        "SELECT material_id, resolution_ru FROM RussianDrillAttempts WHERE id = ?1",
+        params![drill_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Drill {} not found", drill_id))?;
+
+    let (used, missing) = terminology_coverage(conn, material_id, resolution.as_deref().unwrap_or(""))?;
+    let total = used.len() + missing.len();
+    let coverage = if total == 0 { None } else { Some(used.len() as f64 / total as f64) };
+
+    conn.execute(
+        "INSERT INTO DrillRubricScores
+            (drill_id, fluency, accuracy, completeness, terminology_coverage, terms_used, terms_missing, overall)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(drill_id) DO UPDATE SET
+            fluency = excluded.fluency,
+            accuracy = excluded.accuracy,
+            completeness = excluded.completeness,
+            terminology_coverage = excluded.terminology_coverage,
+            terms_used = excluded.terms_used,
+            terms_missing = excluded.terms_missing,
+            overall = excluded.overall,
+            scored_at = datetime('now')",
+        params![
+            drill_id,
+            input.fluency,
+            input.accuracy,
+            input.completeness,
+            coverage,
+            serde_json::to_string(&used).map_err(|e| e.to_string())?,
+            serde_json::to_string(&missing).map_err(|e| e.to_string())?,
+            overall_score(input, coverage),
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    refresh_statuses(conn, material_id)?;
+
+    load_rubric(conn, drill_id)?.ok_or_else(|| format!("Drill {} not found", drill_id))
+}
+
+// Re-derive the status of every scored drill of a material from the rubric
+// history up to it. Unscored drills keep the status they were logged with.
+pub fn refresh_statuses(conn: &Connection, material_id: i64) -> Result<usize, String> {
+    let scored: Vec<(i64, DrillRubric)> = conn.prepare(&format!(
+        "SELECT d.id, {}
+         FROM RussianDrillAttempts d
+         JOIN DrillRubricScores r ON r.drill_id = d.id
+         WHERE d.material_id = ?1
+         ORDER BY d.timestamp ASC, d.id ASC",
+        RUBRIC_COLUMNS
+    )).map_err(|e| e.to_string())?
+    .query_map(params![material_id], |row| Ok((row.get(0)?, rubric_from_row(row, 1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut history = Vec::with_capacity(scored.len());
+    let mut changed = 0;
+    for (drill_id, rubric) in scored {
+        history.push(rubric);
+        changed += conn.execute(
+            "UPDATE RussianDrillAttempts SET status = ?1 WHERE id = ?2 AND status IS NOT ?1",
+            params![derive_status(&history), drill_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn rubric(overall: f64, accuracy: i32, coverage: Option<f64>) -> DrillRubric {
+        DrillRubric {
+            fluency: 4,
+            accuracy,
+            completeness: 4,
+            terminology_coverage: coverage,
+            terms_used: Vec::new(),
+            terms_missing: Vec::new(),
+            overall,
+            scored_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn coverage_counts_as_a_fourth_criterion() {
+        let input = RubricInput { fluency: 4, accuracy: 5, completeness: 3 };
+        assert_eq!(overall_score(&input, None), 4.0);
+        assert_eq!(overall_score(&input, Some(0.5)), 3.75);
+    }
+
+    #[test]
+    fn mastery_needs_a_streak_of_good_drills() {
+        let good = rubric(4.5, 5, Some(0.9));
+        assert_eq!(derive_status(&[]), "learning");
+        assert_eq!(derive_status(&[good.clone(), good.clone()]), "practicing");
+        assert_eq!(derive_status(&[good.clone(), good.clone(), good.clone()]), "mastered");
+        // Low coverage or accuracy breaks the streak
+        assert_eq!(derive_status(&[good.clone(), good.clone(), rubric(4.5, 5, Some(0.5))]), "practicing");
+        assert_eq!(derive_status(&[good.clone(), good.clone(), rubric(4.5, 3, None)]), "practicing");
+        assert_eq!(derive_status(&[good.clone(), good.clone(), good.clone(), rubric(2.0, 2, None)]), "learning");
+    }
+
+    #[test]
+    fn coverage_counts_the_material_vocabulary_and_glossary() {
+        let conn = db::open_in_memory();
+        conn.execute_batch(
+            "INSERT INTO Materials (id, name_en) VALUES (1, 'Limits'), (2, 'Series');
+             INSERT INTO RussianVocabulary (word_ru, translation_en, material_id) VALUES
+                 ('точка', 'point', 1), ('предел', 'limit', 1), ('ряд', 'series', 2);
+             INSERT INTO MaterialTerms (material_id, term_ru) VALUES (1, 'предел'), (1, 'производная');",
+        ).unwrap();
+
+        let (used, missing) = terminology_coverage(&conn, 1, "Предел в точке равен нулю").unwrap();
+        assert_eq!(used, vec!["предел", "точка"]);
+        assert_eq!(missing, vec!["производная"]);
+
+        // Vocabulary alone is enough
+        let (used, missing) = terminology_coverage(&conn, 2, "Ряд сходится").unwrap();
+        assert_eq!((used, missing.len()), (vec!["ряд".to_string()], 0));
+
+        let (used, missing) = terminology_coverage(&conn, 3, "Предел").unwrap();
+        assert!(used.is_empty() && missing.is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+
+// Latin spellings of Cyrillic text and back, plus correction of text typed
+// with the wrong keyboard layout active (ghbdtn → привет).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    Cyrillic,
+    Latin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransliterationScheme {
+    // ISO 9 / GOST 7.79 system A: one letter per letter, with diacritics (ž, č, ŝ)
+    Iso9,
+    // GOST 7.79 system B: ASCII only (zh, cz, shh, y`)
+    Gost,
+    // How people actually type: zh, kh, ts, shch, ya, y for й and ы
+    Informal,
+    // Same keys on the other keyboard layout (ЙЦУКЕН ↔ QWERTY)
+    Layout,
+}
+
+const CYRILLIC: [char; 33] = [
+    'а', 'б', 'в', 'г', 'д', 'е', 'ё', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+    'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+];
+
+// Latin spelling of each letter in CYRILLIC order
+const ISO9: [&str; 33] = [
+    "a", "b", "v", "g", "d", "e", "ë", "ž", "z", "i", "j", "k", "l", "m", "n", "o", "p",
+    "r", "s", "t", "u", "f", "h", "c", "č", "š", "ŝ", "ʺ", "y", "ʹ", "è", "û", "â",
+];
+const GOST: [&str; 33] = [
+    "a", "b", "v", "g", "d", "e", "yo", "zh", "z", "i", "j", "k", "l", "m", "n", "o", "p",
+    "r", "s", "t", "u", "f", "x", "cz", "ch", "sh", "shh", "``", "y`", "`", "e`", "yu", "ya",
+];
+const INFORMAL: [&str; 33] = [
+    "a", "b", "v", "g", "d", "e", "yo", "zh", "z", "i", "y", "k", "l", "m", "n", "o", "p",
+    "r", "s", "t", "u", "f", "kh", "ts", "ch", "sh", "shch", "", "y", "'", "e", "yu", "ya",
+];
+
+// Extra Latin spellings accepted when reading each scheme, on top of its own
+// table. Longest match wins.
+const ISO9_EXTRA: &[(&str, &str)] = &[("'", "ь"), ("\"", "ъ"), ("ĵ", "й")];
+const GOST_EXTRA: &[(&str, &str)] = &[("c", "ц"), ("'", "ь"), ("y", "ы")];
+const INFORMAL_EXTRA: &[(&str, &str)] = &[
+    ("sch", "щ"), ("tsya", "тся"), ("tz", "ц"), ("jo", "ё"), ("ju", "ю"), ("ja", "я"),
+    ("h", "х"), ("x", "кс"), ("c", "ц"), ("w", "в"), ("q", "к"), ("j", "й"), ("\"", "ъ"),
+    ("`", "ь"),
+];
+
+// Keyboard rows, same keys in the same order
+const QWERTY: &str = "`qwertyuiop[]asdfghjkl;'zxcvbnm,./~QWERTYUIOP{}ASDFGHJKL:\"ZXCVBNM<>?";
+const JCUKEN: &str = "ёйцукенгшщзхъфывапролджэячсмитьбю.ЁЙЦУКЕНГШЩЗХЪФЫВАПРОЛДЖЭЯЧСМИТЬБЮ,";
+
+fn latin_table(scheme: TransliterationScheme) -> &'static [&'static str; 33] {
+    match scheme {
+        TransliterationScheme::Iso9 => &ISO9,
+        TransliterationScheme::Gost => &GOST,
+        _ => &INFORMAL,
+    }
+}
+
+fn is_cyrillic(c: char) -> bool {
+    matches!(c, 'а'..='я' | 'А'..='Я' | 'ё' | 'Ё')
+}
+
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, 'à'..='ž')
+}
+
+// Which script most of the letters in `text` are in
+pub fn detect_script(text: &str) -> Option<Script> {
+    let cyrillic = text.chars().filter(|c| is_cyrillic(*c)).count();
+    let latin = text.chars().filter(|c| is_latin(*c)).count();
+    match (cyrillic, latin) {
+        (0, 0) => None,
+        (c, l) if c >= l => Some(Script::Cyrillic),
+        _ => Some(Script::Latin),
+    }
+}
+
+fn with_case(latin: &str, upper: bool, next_upper: bool) -> String {
+    if !upper {
+        return latin.to_string();
+    }
+    if next_upper {
+        return latin.to_uppercase();
+    }
+    let mut chars = latin.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn to_latin(text: &str, scheme: TransliterationScheme) -> String {
+    if scheme == TransliterationScheme::Layout {
+        return switch_layout(text, Script::Latin);
+    }
+
+    let table = latin_table(scheme);
+    let chars: Vec<char> = text.chars().collect();
+    let mut latin = String::with_capacity(text.len());
+
+    for (i, c) in chars.iter().enumerate() {
+        let lower = c.to_lowercase().next().unwrap_or(*c);
+        let Some(index) = CYRILLIC.iter().position(|l| *l == lower) else {
+            latin.push(*c);
+            continue;
+        };
+
+        let next = chars.get(i + 1).copied();
+        let mut spelling = table[index];
+        // GOST B writes ц as c before е, и, ы, й
+        if scheme == TransliterationScheme::Gost && lower == 'ц' {
+            let next_lower = next.and_then(|n| n.to_lowercase().next());
+            if matches!(next_lower, Some('е' | 'и' | 'ы' | 'й')) {
+                spelling = "c";
+            }
+        }
+
+        let upper = c.is_uppercase();
+        let next_upper = next.is_some_and(|n| n.is_uppercase());
+        latin.push_str(&with_case(spelling, upper, next_upper));
+    }
+
+    latin
+}
+
+fn is_vowel_letter(c: char) -> bool {
+    matches!(c, 'а' | 'е' | 'ё' | 'и' | 'о' | 'у' | 'ы' | 'э' | 'ю' | 'я')
+}
+
+pub fn to_cyrillic(text: &str, scheme: TransliterationScheme) -> String {
+    if scheme == TransliterationScheme::Layout {
+        return switch_layout(text, Script::Cyrillic);
+    }
+
+    let own = latin_table(scheme);
+    let extra = match scheme {
+        TransliterationScheme::Iso9 => ISO9_EXTRA,
+        TransliterationScheme::Gost => GOST_EXTRA,
+        _ => INFORMAL_EXTRA,
+    };
+
+    let mut spellings: Vec<(String, String)> = own.iter()
+        .zip(CYRILLIC.iter())
+        .filter(|(latin, _)| !latin.is_empty())
+        .map(|(latin, cyrillic)| (latin.to_string(), cyrillic.to_string()))
+        .chain(extra.iter().map(|(latin, cyrillic)| (latin.to_string(), cyrillic.to_string())))
+        .collect();
+    // Longest spelling first; the scheme's own table wins ties
+    spellings.sort_by_key(|(latin, _)| std::cmp::Reverse(latin.chars().count()));
+
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let mut cyrillic = String::with_capacity(text.len() * 2);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let matched = spellings.iter().find(|(latin, _)| {
+            let len = latin.chars().count();
+            i + len <= lower.len() && lower[i..i + len].iter().copied().eq(latin.chars())
+        });
+
+        let Some((latin, letters)) = matched else {
+            cyrillic.push(chars[i]);
+            i += 1;
+            continue;
+        };
+
+        let mut letters = letters.clone();
+        // Informal "y" is й after a vowel (moy, russkiy) and ы elsewhere
+        if scheme == TransliterationScheme::Informal && latin == "y" {
+            let after_vowel = cyrillic.chars().last().is_some_and(|c| is_vowel_letter(c.to_lowercase().next().unwrap_or(c)));
+            letters = if after_vowel { "й".to_string() } else { "ы".to_string() };
+        }
+
+        if chars[i].is_uppercase() {
+            let len = latin.chars().count();
+            let all_upper = len > 1 && chars[i..i + len].iter().all(|c| !c.is_alphabetic() || c.is_uppercase());
+            letters = if all_upper || chars.get(i + len).is_some_and(|c| c.is_uppercase()) {
+                letters.to_uppercase()
+            } else {
+                with_case(&letters, true, false)
+            };
+        }
+
+        cyrillic.push_str(&letters);
+        i += latin.chars().count();
+    }
+
+    cyrillic
+}
+
+// Retype `text` as if the other keyboard layout had been active, producing
+// `target` letters
+pub fn switch_layout(text: &str, target: Script) -> String {
+    let (from, to): (Vec<char>, Vec<char>) = match target {
+        Script::Cyrillic => (QWERTY.chars().collect(), JCUKEN.chars().collect()),
+        Script::Latin => (JCUKEN.chars().collect(), QWERTY.chars().collect()),
+    };
+    text.chars()
+        .map(|c| from.iter().position(|f| *f == c).map(|i| to[i]).unwrap_or(c))
+        .collect()
+}
+
+pub fn convert(text: &str, target: Script, scheme: TransliterationScheme) -> String {
+    match target {
+        Script::Cyrillic => to_cyrillic(text, scheme),
+        Script::Latin => to_latin(text, scheme),
+    }
+}
+
+fn initial_e_to_reversed_e(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        let c = match (word_start, c) {
+            (true, 'е') => 'э',
+            (true, 'Е') => 'Э',
+            _ => c,
+        };
+        word_start = !c.is_alphanumeric();
+        result.push(c);
+    }
+    result
+}
+
+// Other readings of a search query: Latin input as transliterated Russian in
+// each scheme and as Russian typed on a QWERTY layout; Cyrillic input as
+// English typed on a ЙЦУКЕН layout. The query itself comes first.
+pub fn search_variants(query: &str) -> Vec<String> {
+    let query = query.trim();
+    let mut variants = vec![query.to_string()];
+
+    let candidates = match detect_script(query) {
+        Some(Script::Latin) => vec![
+            to_cyrillic(query, TransliterationScheme::Informal),
+            // Informal "e" is also э at the start of a word (eto → это)
+            initial_e_to_reversed_e(&to_cyrillic(query, TransliterationScheme::Informal)),
+            to_cyrillic(query, TransliterationScheme::Gost),
+            to_cyrillic(query, TransliterationScheme::Iso9),
+            switch_layout(query, Script::Cyrillic),
+        ],
+        Some(Script::Cyrillic) => vec![switch_layout(query, Script::Latin)],
+        None => Vec::new(),
+    };
+
+    for candidate in candidates {
+        // Half-converted strings can't match anything
+        if detect_script(&candidate) == detect_script(query) {
+            continue;
+        }
+        if !variants.contains(&candidate) {
+            variants.push(candidate);
+        }
+    }
+
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_each_scheme() {
+        assert_eq!(to_latin("Щука", TransliterationScheme::Iso9), "Ŝuka");
+        assert_eq!(to_latin("цирк и улица", TransliterationScheme::Gost), "cirk i ulicza");
+        assert_eq!(to_latin("Щи", TransliterationScheme::Informal), "Shchi");
+        assert_eq!(to_latin("ЖУК", TransliterationScheme::Informal), "ZHUK");
+    }
+
+    #[test]
+    fn reads_informal_spellings() {
+        assert_eq!(to_cyrillic("privet", TransliterationScheme::Informal), "привет");
+        assert_eq!(to_cyrillic("moy drug", TransliterationScheme::Informal), "мой друг");
+        assert_eq!(to_cyrillic("Zhizn'", TransliterationScheme::Informal), "Жизнь");
+        assert_eq!(to_cyrillic("shchuka", TransliterationScheme::Informal), "щука");
+    }
+
+    #[test]
+    fn round_trips_strict_schemes() {
+        let text = "Съешь же ещё этих мягких французских булок";
+        for scheme in [TransliterationScheme::Iso9, TransliterationScheme::Gost] {
+            assert_eq!(to_cyrillic(&to_latin(text, scheme), scheme), text, "{:?}", scheme);
+        }
+    }
+
+    #[test]
+    fn fixes_wrong_keyboard_layout() {
+        assert_eq!(switch_layout("ghbdtn", Script::Cyrillic), "привет");
+        assert_eq!(switch_layout("руддщ", Script::Latin), "hello");
+    }
+
+    #[test]
+    fn search_variants_cover_both_readings() {
+        assert_eq!(detect_script("123"), None);
+        assert!(search_variants("eto").contains(&"это".to_string()));
+        assert!(search_variants("ghbdtn").contains(&"привет".to_string()));
+        assert_eq!(search_variants("руддщ"), vec!["руддщ".to_string(), "hello".to_string()]);
+    }
+}
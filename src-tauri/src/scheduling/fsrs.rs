@@ -0,0 +1,96 @@
+use super::{Grade, MemoryState};
+
+// FSRS v4.5 with the published default parameters
+const W: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474,
+    0.1367, 1.0461, 2.1072, 0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+pub const DECAY: f64 = -0.5;
+pub const FACTOR: f64 = 19.0 / 81.0;
+
+const MAX_INTERVAL_DAYS: f64 = 36_500.0;
+
+// Probability of recall after `elapsed_days` for a memory of the given stability
+pub fn retrievability(stability: f64, elapsed_days: f64) -> f64 {
+    if stability <= 0.0 {
+        return 0.0;
+    }
+    (1.0 + FACTOR * elapsed_days.max(0.0) / stability).powf(DECAY)
+}
+
+// Days until retrievability falls to `desired_retention`
+pub fn interval_for(stability: f64, desired_retention: f64) -> f64 {
+    let interval = stability / FACTOR * (desired_retention.powf(1.0 / DECAY) - 1.0);
+    interval.round().clamp(1.0, MAX_INTERVAL_DAYS)
+}
+
+pub fn next_state(
+    state: &MemoryState,
+    grade: Grade,
+    elapsed_days: f64,
+    desired_retention: f64,
+) -> MemoryState {
+    let g = grade as i32 as f64;
+
+    let (stability, difficulty) = match state.difficulty {
+        // First review of this item
+        None => (W[grade as usize - 1], initial_difficulty(g)),
+        Some(difficulty) => {
+            let r = retrievability(state.stability, elapsed_days);
+            let stability = if grade == Grade::Again {
+                forget_stability(state.stability, difficulty, r)
+            } else {
+                recall_stability(state.stability, difficulty, r, grade)
+            };
+            (stability, next_difficulty(difficulty, g))
+        }
+    };
+
+    let (repetitions, lapses) = if grade == Grade::Again {
+        (0, state.lapses + 1)
+    } else {
+        (state.repetitions + 1, state.lapses)
+    };
+
+    MemoryState {
+        stability,
+        difficulty: Some(difficulty),
+        ease: state.ease,
+        interval_days: interval_for(stability, desired_retention),
+        repetitions,
+        lapses,
+    }
+}
+
+fn initial_difficulty(g: f64) -> f64 {
+    (W[4] - (g - 3.0) * W[5]).clamp(1.0, 10.0)
+}
+
+fn next_difficulty(difficulty: f64, g: f64) -> f64 {
+    let updated = difficulty - W[6] * (g - 3.0);
+    // Mean reversion towards the difficulty of a first "Good"
+    (W[7] * initial_difficulty(3.0) + (1.0 - W[7]) * updated).clamp(1.0, 10.0)
+}
+
+fn recall_stability(stability: f64, difficulty: f64, r: f64, grade: Grade) -> f64 {
+    let hard_penalty = if grade == Grade::Hard { W[15] } else { 1.0 };
+    let easy_bonus = if grade == Grade::Easy { W[16] } else { 1.0 };
+
+    stability
+        * (1.0
+            + W[8].exp()
+                * (11.0 - difficulty)
+                * stability.powf(-W[9])
+                * ((W[10] * (1.0 - r)).exp() - 1.0)
+                * hard_penalty
+                * easy_bonus)
+}
+
+fn forget_stability(stability: f64, difficulty: f64, r: f64) -> f64 {
+    let next = W[11]
+        * difficulty.powf(-W[12])
+        * ((stability + 1.0).powf(W[13]) - 1.0)
+        * (W[14] * (1.0 - r)).exp();
+    next.min(stability)
+}
@@ -0,0 +1,274 @@
+use chrono::{Duration, NaiveDateTime};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use crate::db::settings;
+use crate::utils::time::{days_between, format_sqlite_time, parse_sqlite_time};
+
+pub mod fsrs;
+pub mod sm2;
+
+pub const SETTINGS_KEY: &str = "scheduler";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    Again = 1,
+    Hard = 2,
+    Good = 3,
+    Easy = 4,
+}
+
+impl Grade {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Sm2,
+    #[default]
+    Fsrs,
+}
+
+impl Algorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Algorithm::Sm2 => "sm2",
+            Algorithm::Fsrs => "fsrs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerSettings {
+    pub algorithm: Algorithm,
+    // Target probability of recall when a problem comes due (FSRS only)
+    pub desired_retention: f64,
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        SchedulerSettings {
+            algorithm: Algorithm::Fsrs,
+            desired_retention: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryState {
+    pub stability: f64,
+    // FSRS difficulty (1-10); `None` until FSRS has seen a review
+    pub difficulty: Option<f64>,
+    pub ease: f64,
+    pub interval_days: f64,
+    pub repetitions: i32,
+    pub lapses: i32,
+}
+
+impl Default for MemoryState {
+    fn default() -> Self {
+        MemoryState {
+            stability: 0.0,
+            difficulty: None,
+            ease: sm2::INITIAL_EASE,
+            interval_days: 0.0,
+            repetitions: 0,
+            lapses: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Review {
+    pub at: NaiveDateTime,
+    pub grade: Grade,
+}
+
+pub fn next_state(
+    settings: &SchedulerSettings,
+    state: &MemoryState,
+    grade: Grade,
+    elapsed_days: f64,
+) -> MemoryState {
+    match settings.algorithm {
+        Algorithm::Sm2 => sm2::next_state(state, grade),
+        Algorithm::Fsrs => fsrs::next_state(state, grade, elapsed_days, settings.desired_retention),
+    }
+}
+
+// Fold a chronological review history into the current memory state
+pub fn replay(settings: &SchedulerSettings, reviews: &[Review]) -> MemoryState {
+    let mut state = MemoryState::default();
+    let mut last_at: Option<NaiveDateTime> = None;
+
+    for review in reviews {
+        let elapsed = last_at.map(|last| days_between(&last, &review.at)).unwrap_or(0.0);
+        state = next_state(settings, &state, review.grade, elapsed);
+        last_at = Some(review.at);
+    }
+
+    state
+}
+
+pub fn due_date(last_review: &NaiveDateTime, interval_days: f64) -> NaiveDateTime {
+    *last_review + Duration::seconds((interval_days * 86_400.0) as i64)
+}
+
+pub fn load_settings(conn: &Connection) -> Result<SchedulerSettings, String> {
+    settings::load(conn, SETTINGS_KEY)
+}
+
+// Each batch is one review session: the first attempt of the batch is the
+// recall test, graded by success, the self-reported difficulty and how long
+// it took compared to the problem's usual solve time.
+pub fn problem_reviews(conn: &Connection, problem_id: i64) -> Result<Vec<Review>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT a.batch_id, a.successful, a.difficulty_rating, a.time_spent_minutes, a.timestamp
+         FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         WHERE b.problem_id = ?1
+         ORDER BY a.timestamp ASC, a.id ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![problem_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, bool>(1)?,
+            row.get::<_, Option<i32>>(2)?,
+            row.get::<_, Option<f64>>(3)?,
+            row.get::<_, String>(4)?,
+        ))
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut success_times: Vec<f64> = rows.iter()
+        .filter(|r| r.1)
+        .filter_map(|r| r.3)
+        .collect();
+    success_times.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let median_time = success_times.get(success_times.len() / 2).copied();
+
+    let mut reviews = Vec::new();
+    let mut seen_batches = Vec::new();
+
+    for (batch_id, successful, difficulty, minutes, timestamp) in rows {
+        if seen_batches.contains(&batch_id) {
+            continue;
+        }
+        seen_batches.push(batch_id);
+
+        reviews.push(Review {
+            at: parse_sqlite_time(&timestamp)?,
+            grade: grade_attempt(successful, difficulty, minutes, median_time),
+        });
+    }
+
+    Ok(reviews)
+}
+
+fn grade_attempt(
+    successful: bool,
+    difficulty_rating: Option<i32>,
+    time_spent_minutes: Option<f64>,
+    median_time: Option<f64>,
+) -> Grade {
+    if !successful {
+        return Grade::Again;
+    }
+
+    let grade = match difficulty_rating {
+        Some(1) | Some(2) => Grade::Easy,
+        Some(4) | Some(5) => Grade::Hard,
+        _ => Grade::Good,
+    };
+
+    // A noticeably slow solve counts one step harder
+    let slow = match (time_spent_minutes, median_time) {
+        (Some(minutes), Some(median)) if median > 0.0 => minutes > median * 1.5,
+        _ => false,
+    };
+
+    match (grade, slow) {
+        (Grade::Easy, true) => Grade::Good,
+        (Grade::Good, true) => Grade::Hard,
+        (grade, _) => grade,
+    }
+}
+
+// Recompute and store a problem's schedule from its full attempt history
+pub fn reschedule_problem(conn: &Connection, problem_id: i64) -> Result<(), String> {
+    let settings = load_settings(conn)?;
+    let reviews = problem_reviews(conn, problem_id)?;
+
+    let last_review = match reviews.last() {
+        Some(review) => review.at,
+        None => {
+            conn.execute(
+                "DELETE FROM ProblemSchedules WHERE problem_id = ?1",
+                params![problem_id],
+            ).map_err(|e| e.to_string())?;
+            return Ok(());
+        }
+    };
+
+    let state = replay(&settings, &reviews);
+    let due_at = due_date(&last_review, state.interval_days);
+
+    conn.execute(
+        "INSERT INTO ProblemSchedules
+         (problem_id, algorithm, stability, difficulty, ease, interval_days,
+          repetitions, lapses, last_reviewed_at, due_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, datetime('now'))
+         ON CONFLICT(problem_id) DO UPDATE SET
+            algorithm = excluded.algorithm,
+            stability = excluded.stability,
+            difficulty = excluded.difficulty,
+            ease = excluded.ease,
+            interval_days = excluded.interval_days,
+            repetitions = excluded.repetitions,
+            lapses = excluded.lapses,
+            last_reviewed_at = excluded.last_reviewed_at,
+            due_at = excluded.due_at,
+            updated_at = excluded.updated_at",
+        params![
+            problem_id,
+            settings.algorithm.as_str(),
+            state.stability,
+            state.difficulty,
+            state.ease,
+            state.interval_days,
+            state.repetitions,
+            state.lapses,
+            format_sqlite_time(&last_review),
+            format_sqlite_time(&due_at),
+        ],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub fn reschedule_all(conn: &Connection) -> Result<usize, String> {
+    let problem_ids: Vec<i64> = conn.prepare("SELECT id FROM Problems")
+        .map_err(|e| e.to_string())?
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for problem_id in &problem_ids {
+        reschedule_problem(conn, *problem_id)?;
+    }
+
+    Ok(problem_ids.len())
+}
@@ -0,0 +1,40 @@
+use super::{Grade, MemoryState};
+
+pub const INITIAL_EASE: f64 = 2.5;
+pub const MIN_EASE: f64 = 1.3;
+
+// Classic SuperMemo-2. Grades map onto SM-2 quality scores 2..5, so `Again`
+// is the only failing grade. SM-2 has no stability model; the interval is
+// used as the stability so retrievability can be estimated the same way for
+// both algorithms.
+pub fn next_state(state: &MemoryState, grade: Grade) -> MemoryState {
+    let quality = match grade {
+        Grade::Again => 2.0,
+        Grade::Hard => 3.0,
+        Grade::Good => 4.0,
+        Grade::Easy => 5.0,
+    };
+
+    let ease = (state.ease + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)).max(MIN_EASE);
+
+    let (repetitions, lapses, interval_days) = if grade == Grade::Again {
+        (0, state.lapses + 1, 1.0)
+    } else {
+        let repetitions = state.repetitions + 1;
+        let interval = match repetitions {
+            1 => 1.0,
+            2 => 6.0,
+            _ => (state.interval_days * ease).round(),
+        };
+        (repetitions, state.lapses, interval)
+    };
+
+    MemoryState {
+        stability: interval_days,
+        difficulty: None,
+        ease,
+        interval_days,
+        repetitions,
+        lapses,
+    }
+}
@@ -0,0 +1,370 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use crate::russian::{morphology, transliteration};
+
+pub const DEFAULT_LIMIT: u32 = 20;
+pub const MAX_LIMIT: u32 = 200;
+
+// Markers passed to snippet(); stripped again before results leave Rust
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+const ELLIPSIS: char = '\u{4}';
+const SNIPPET_TOKENS: i32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchScope {
+    Problems,
+    Attempts,
+    Drills,
+    Vocabulary,
+}
+
+impl SearchScope {
+    pub const ALL: [SearchScope; 4] = [
+        SearchScope::Problems,
+        SearchScope::Attempts,
+        SearchScope::Drills,
+        SearchScope::Vocabulary,
+    ];
+
+    fn spec(self) -> ScopeSpec {
+        match self {
+            SearchScope::Problems => ScopeSpec {
+                table: "ProblemsSearch",
+                originals: "p.title, p.description",
+                columns: &["title", "description"],
+                weights: "10.0, 1.0",
+                details: "p.title, p.id, p.generated_id, m.name_en, p.created_at",
+                joins: "JOIN Problems p ON p.id = ProblemsSearch.rowid
+                        JOIN Materials m ON p.material_id = m.id",
+            },
+            SearchScope::Attempts => ScopeSpec {
+                table: "AttemptsSearch",
+                originals: "a.errors, a.resolution, a.commentary",
+                columns: &["errors", "resolution", "commentary"],
+                weights: "1.0, 1.0, 1.0",
+                details: "p.title || ' · attempt #' || a.attempt_number, p.id, p.generated_id, m.name_en, a.timestamp",
+                joins: "JOIN Attempts a ON a.id = AttemptsSearch.rowid
+                        JOIN Batches b ON a.batch_id = b.id
+                        JOIN Problems p ON b.problem_id = p.id
+                        JOIN Materials m ON p.material_id = m.id",
+            },
+            SearchScope::Drills => ScopeSpec {
+                table: "DrillsSearch",
+                originals: "d.errors_ru, d.resolution_ru, d.commentary",
+                columns: &["errors_ru", "resolution_ru", "commentary"],
+                weights: "1.0, 1.0, 1.0",
+                details: "'Drill #' || d.attempt_number, NULL, NULL, m.name_en, d.timestamp",
+                joins: "JOIN RussianDrillAttempts d ON d.id = DrillsSearch.rowid
+                        JOIN Materials m ON d.material_id = m.id",
+            },
+            SearchScope::Vocabulary => ScopeSpec {
+                table: "VocabularySearch",
+                originals: "v.word_ru, v.translation_en, v.example_sentence",
+                columns: &["word_ru", "translation_en", "example_sentence"],
+                weights: "10.0, 5.0, 1.0",
+                details: "v.word_ru || ' — ' || v.translation_en, NULL, NULL, m.name_en, v.first_seen",
+                joins: "JOIN RussianVocabulary v ON v.id = VocabularySearch.rowid
+                        LEFT JOIN Materials m ON v.material_id = m.id",
+            },
+        }
+    }
+}
+
+struct ScopeSpec {
+    table: &'static str,
+    // The indexed columns as written, in column order
+    originals: &'static str,
+    columns: &'static [&'static str],
+    // bm25() column weights, in column order
+    weights: &'static str,
+    // title, problem_id, generated_id, material_name, timestamp
+    details: &'static str,
+    joins: &'static str,
+}
+
+// Character range of a matched term within `snippet`, in UTF-16 code units
+// so the frontend can slice the string directly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighlightRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub scope: SearchScope,
+    // Row id in the scope's table (Problems, Attempts, ...)
+    pub id: i64,
+    pub title: String,
+    pub problem_id: Option<i64>,
+    pub generated_id: Option<String>,
+    pub material_name: Option<String>,
+    pub timestamp: Option<String>,
+    // Column the snippet was taken from
+    pub field: String,
+    pub snippet: String,
+    pub highlights: Vec<HighlightRange>,
+    // bm25 score; lower is a better match
+    pub rank: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub query: String,
+    // Matches across all requested scopes, ignoring limit/offset
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+// Indexed text has ё folded to е and stress marks removed by the tokenizer;
+// queries get the same treatment
+pub fn fold_for_search(text: &str) -> String {
+    morphology::fold_yo(&morphology::strip_stress(text))
+}
+
+fn match_terms(text: &str) -> Option<String> {
+    let terms: Vec<String> = fold_for_search(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", morphology::stem(term)))
+        .collect();
+
+    if terms.is_empty() { None } else { Some(terms.join(" ")) }
+}
+
+// Turn free text into an FTS5 query: every word must match. Words are
+// stemmed and matched as prefixes, so "книги" also finds "книга". Quoting
+// each term keeps punctuation in user input from being parsed as query
+// syntax. Transliterated and wrong-layout readings of the query ("kniga",
+// "rybuf") are OR-ed in.
+pub fn build_match_query(query: &str) -> Option<String> {
+    let alternatives: Vec<String> = transliteration::search_variants(query)
+        .iter()
+        .filter_map(|variant| match_terms(variant))
+        .collect();
+
+    match alternatives.len() {
+        0 => None,
+        1 => alternatives.into_iter().next(),
+        _ => Some(alternatives.iter()
+            .map(|terms| format!("({})", terms))
+            .collect::<Vec<_>>()
+            .join(" OR ")),
+    }
+}
+
+// The index holds text with ё folded to е, so snippets come back folded. Both
+// letters take the same number of bytes, which lets the snippet be located
+// in the folded column and copied from the original character by character.
+fn unfold_snippet(marked: &str, original: &str) -> String {
+    let fragment: String = marked.chars()
+        .filter(|c| ![MATCH_START, MATCH_END, ELLIPSIS].contains(c))
+        .collect();
+    let mut written = morphology::fold_yo(original)
+        .find(&fragment)
+        .map(|start| original[start..].chars());
+
+    marked.chars()
+        .map(|c| match c {
+            MATCH_START | MATCH_END => c,
+            ELLIPSIS => '…',
+            _ => written.as_mut().and_then(|chars| chars.next()).unwrap_or(c),
+        })
+        .collect()
+}
+
+fn split_highlights(marked: &str) -> (String, Vec<HighlightRange>) {
+    let mut text = String::with_capacity(marked.len());
+    let mut highlights = Vec::new();
+    let mut position = 0;
+    let mut start = None;
+
+    for c in marked.chars() {
+        match c {
+            MATCH_START => start = Some(position),
+            MATCH_END => {
+                if let Some(start) = start.take() {
+                    highlights.push(HighlightRange { start, end: position });
+                }
+            }
+            _ => {
+                text.push(c);
+                position += c.len_utf16();
+            }
+        }
+    }
+
+    (text, highlights)
+}
+
+fn count_matches(conn: &Connection, spec: &ScopeSpec, match_query: &str) -> Result<usize, String> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM {0} WHERE {0} MATCH ?1", spec.table),
+        params![match_query],
+        |row| row.get::<_, i64>(0)
+    )
+    .map(|count| count as usize)
+    .map_err(|e| e.to_string())
+}
+
+fn search_scope(
+    conn: &Connection,
+    scope: SearchScope,
+    match_query: &str,
+    fetch: u32,
+) -> Result<Vec<SearchHit>, String> {
+    let spec = scope.spec();
+    let snippets: Vec<String> = (0..spec.columns.len())
+        .map(|i| format!(
+            "snippet({}, {}, char(2), char(3), char(4), {})",
+            spec.table, i, SNIPPET_TOKENS
+        ))
+        .collect();
+
+    let sql = format!(
+        "SELECT {table}.rowid, bm25({table}, {weights}), {details}, {snippets}, {originals}
+         FROM {table}
+         {joins}
+         WHERE {table} MATCH ?1
+         ORDER BY 2
+         LIMIT ?2",
+        table = spec.table,
+        weights = spec.weights,
+        details = spec.details,
+        snippets = snippets.join(", "),
+        originals = spec.originals,
+        joins = spec.joins,
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let hits = stmt.query_map(params![match_query, fetch], |row| {
+        // Report the first (highest weighted) column that actually matched
+        let mut field = spec.columns[0];
+        let mut marked = String::new();
+        for (i, column) in spec.columns.iter().enumerate() {
+            let snippet: Option<String> = row.get(7 + i)?;
+            if let Some(snippet) = snippet.filter(|s| s.contains(MATCH_START)) {
+                let original: String = row.get(7 + spec.columns.len() + i)?;
+                field = column;
+                marked = unfold_snippet(&snippet, &original);
+                break;
+            }
+        }
+        let (snippet, highlights) = split_highlights(&marked);
+
+        Ok(SearchHit {
+            scope,
+            id: row.get(0)?,
+            rank: row.get(1)?,
+            title: row.get(2)?,
+            problem_id: row.get(3)?,
+            generated_id: row.get(4)?,
+            material_name: row.get(5)?,
+            timestamp: row.get(6)?,
+            field: field.to_string(),
+            snippet,
+            highlights,
+        })
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    Ok(hits)
+}
+
+// Ranked search over the selected scopes (all of them when empty). Each scope
+// is ranked by bm25 on its own and the lists are merged by score, so paging
+// fetches `offset + limit` rows per scope.
+pub fn global_search(
+    conn: &Connection,
+    query: &str,
+    scopes: &[SearchScope],
+    limit: u32,
+    offset: u32,
+) -> Result<SearchResults, String> {
+    let mut results = SearchResults {
+        query: query.to_string(),
+        total: 0,
+        hits: Vec::new(),
+    };
+
+    let Some(match_query) = build_match_query(query) else {
+        return Ok(results);
+    };
+
+    let limit = limit.clamp(1, MAX_LIMIT);
+    let fetch = offset.saturating_add(limit);
+
+    let mut hits = Vec::new();
+    for scope in SearchScope::ALL.into_iter().filter(|s| scopes.is_empty() || scopes.contains(s)) {
+        results.total += count_matches(conn, &scope.spec(), &match_query)?;
+        hits.extend(search_scope(conn, scope, &match_query, fetch)?);
+    }
+
+    hits.sort_by(|a, b| a.rank.total_cmp(&b.rank));
+    results.hits = hits.into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn utf16_slice(text: &str, range: &HighlightRange) -> String {
+        let units: Vec<u16> = text.encode_utf16().collect();
+        String::from_utf16(&units[range.start..range.end]).unwrap()
+    }
+
+    #[test]
+    fn yo_is_folded_for_matching_but_shown_as_written() {
+        let conn = db::open_in_memory();
+        conn.execute(
+            "INSERT INTO RussianVocabulary (word_ru, translation_en, example_sentence)
+             VALUES ('ёлка', 'fir tree', 'Зелёная ёлка стоит в лесу')",
+            [],
+        ).unwrap();
+
+        let results = global_search(&conn, "елка", &[SearchScope::Vocabulary], 10, 0).unwrap();
+        assert_eq!(results.total, 1);
+        let hit = &results.hits[0];
+        assert_eq!(hit.field, "word_ru");
+        assert_eq!(hit.highlights.len(), 1);
+        assert_eq!(hit.snippet, "ёлка");
+        assert_eq!(utf16_slice(&hit.snippet, &hit.highlights[0]), "ёлка");
+
+        let results = global_search(&conn, "зеленая", &[SearchScope::Vocabulary], 10, 0).unwrap();
+        let hit = &results.hits[0];
+        assert_eq!(hit.field, "example_sentence");
+        assert_eq!(hit.snippet, "Зелёная ёлка стоит в лесу");
+        assert_eq!(utf16_slice(&hit.snippet, &hit.highlights[0]), "Зелёная");
+
+        conn.execute("UPDATE RussianVocabulary SET word_ru = 'ёлочка'", []).unwrap();
+        conn.execute("DELETE FROM RussianVocabulary", []).unwrap();
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0)).unwrap();
+        assert_eq!(integrity, "ok");
+        conn.execute("INSERT INTO VocabularySearch(VocabularySearch) VALUES ('integrity-check')", []).unwrap();
+    }
+
+    #[test]
+    fn cut_snippets_keep_the_original_spelling() {
+        let conn = db::open_in_memory();
+        conn.execute_batch(
+            "INSERT INTO Materials (id, name_en) VALUES (1, 'Limits');
+             INSERT INTO Problems (generated_id, material_id, title, description) VALUES
+                 ('MATH_001', 1, 'Предел', 'Один два три четыре пять шесть семь восемь девять десять одиннадцать двенадцать тринадцать: всё ещё ёмкость, её не считаем.');",
+        ).unwrap();
+
+        let hit = &global_search(&conn, "емкость", &[SearchScope::Problems], 10, 0).unwrap().hits[0];
+        assert!(hit.snippet.starts_with('…'));
+        assert!(hit.snippet.contains("всё ещё ёмкость, её"));
+        assert_eq!(utf16_slice(&hit.snippet, &hit.highlights[0]), "ёмкость");
+    }
+}
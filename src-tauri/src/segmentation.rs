@@ -0,0 +1,512 @@
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, Offset, TimeZone};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use crate::db::settings;
+use crate::utils::time::{format_sqlite_time, now_utc, parse_sqlite_time};
+
+pub const SETTINGS_KEY: &str = "batch_segmentation";
+
+// Rules for splitting a problem's attempts into batches (practice sessions)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentationSettings {
+    // Start a new batch when more than this many hours pass between attempts;
+    // a closed batch ends this long after its last attempt
+    pub gap_hours: f64,
+    // Start a new batch once a batch has run this long, regardless of gaps
+    pub max_batch_hours: Option<f64>,
+    // Never let a batch span two calendar days
+    pub close_at_day_boundary: bool,
+    // Timezone for day boundaries in minutes east of UTC; None uses the
+    // system timezone
+    pub utc_offset_minutes: Option<i32>,
+}
+
+impl Default for SegmentationSettings {
+    fn default() -> Self {
+        SegmentationSettings {
+            gap_hours: 2.0,
+            max_batch_hours: None,
+            close_at_day_boundary: false,
+            utc_offset_minutes: None,
+        }
+    }
+}
+
+impl SegmentationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.gap_hours <= 0.0 {
+            return Err("Gap threshold must be greater than 0".to_string());
+        }
+        if let Some(max) = self.max_batch_hours {
+            if max <= 0.0 {
+                return Err("Maximum batch length must be greater than 0".to_string());
+            }
+        }
+        if let Some(offset) = self.utc_offset_minutes {
+            if !(-14 * 60..=14 * 60).contains(&offset) {
+                return Err("UTC offset must be between -14 and +14 hours".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn utc_offset(&self, utc: &NaiveDateTime) -> Duration {
+        let minutes = match self.utc_offset_minutes {
+            Some(minutes) => minutes,
+            None => Local.offset_from_utc_datetime(utc).fix().local_minus_utc() / 60,
+        };
+        Duration::minutes(minutes as i64)
+    }
+
+    // Calendar day of a UTC timestamp in the configured timezone
+    pub fn local_date(&self, utc: &NaiveDateTime) -> NaiveDate {
+        (*utc + self.utc_offset(utc)).date()
+    }
+
+    // The UTC instant at which the local day containing `utc` ends
+    fn end_of_local_day(&self, utc: &NaiveDateTime) -> NaiveDateTime {
+        let offset = self.utc_offset(utc);
+        let next_midnight = (*utc + offset).date().and_hms_opt(0, 0, 0)
+            .map(|midnight| midnight + Duration::days(1))
+            .unwrap_or(*utc + offset);
+        next_midnight - offset
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResegmentSummary {
+    pub problem_id: i64,
+    pub batches_before: usize,
+    pub batches_after: usize,
+    pub attempts: usize,
+}
+
+pub fn load_settings(conn: &Connection) -> Result<SegmentationSettings, String> {
+    settings::load(conn, SETTINGS_KEY)
+}
+
+fn hours(value: f64) -> Duration {
+    Duration::seconds((value * 3600.0) as i64)
+}
+
+// Whether an attempt at `next` belongs in a new batch rather than the one
+// that started at `batch_started` and last saw an attempt at `last_attempt`
+pub fn should_split(
+    settings: &SegmentationSettings,
+    batch_started: &NaiveDateTime,
+    last_attempt: &NaiveDateTime,
+    next: &NaiveDateTime,
+) -> bool {
+    if *next - *last_attempt > hours(settings.gap_hours) {
+        return true;
+    }
+    if let Some(max) = settings.max_batch_hours {
+        if *next - *batch_started > hours(max) {
+            return true;
+        }
+    }
+    settings.close_at_day_boundary
+        && settings.local_date(last_attempt) != settings.local_date(next)
+}
+
+// When a batch whose last attempt was at `last_attempt` is considered over:
+// one gap after that attempt, capped by the batch length and day boundary
+pub fn close_time(
+    settings: &SegmentationSettings,
+    batch_started: &NaiveDateTime,
+    last_attempt: &NaiveDateTime,
+) -> NaiveDateTime {
+    let mut end = *last_attempt + hours(settings.gap_hours);
+
+    if let Some(max) = settings.max_batch_hours {
+        end = end.min(*batch_started + hours(max));
+    }
+    if settings.close_at_day_boundary {
+        end = end.min(settings.end_of_local_day(last_attempt));
+    }
+
+    end.max(*last_attempt)
+}
+
+struct AttemptSlot {
+    id: i64,
+    timestamp: NaiveDateTime,
+    // First attempt of a batch that was explicitly started fresh
+    fresh_start: bool,
+}
+
+struct NewBatch {
+    started_at: NaiveDateTime,
+    last_attempt: NaiveDateTime,
+    is_fresh_start: bool,
+    attempt_ids: Vec<i64>,
+}
+
+// Rebuild a problem's batches from its attempt timestamps under `settings`,
+// renumbering batches and attempts chronologically. Fresh-start boundaries
+// are kept. Run inside a transaction.
+pub fn rebuild_batches(
+    conn: &Connection,
+    problem_id: i64,
+    settings: &SegmentationSettings,
+) -> Result<ResegmentSummary, String> {
+    let batches_before: usize = conn.query_row(
+        "SELECT COUNT(*) FROM Batches WHERE problem_id = ?1",
+        params![problem_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let rows = conn.prepare(
+        "SELECT a.id, a.timestamp, a.batch_id, b.is_fresh_start
+         FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         WHERE b.problem_id = ?1
+         ORDER BY a.timestamp ASC, a.id ASC"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![problem_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, bool>(3)?,
+        ))
+    })
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let mut seen_batches = Vec::new();
+    let mut attempts = Vec::with_capacity(rows.len());
+    for (id, timestamp, batch_id, is_fresh_start) in rows {
+        let first_in_batch = !seen_batches.contains(&batch_id);
+        if first_in_batch {
+            seen_batches.push(batch_id);
+        }
+        attempts.push(AttemptSlot {
+            id,
+            timestamp: parse_sqlite_time(&timestamp)?,
+            fresh_start: first_in_batch && is_fresh_start,
+        });
+    }
+
+    let mut batches: Vec<NewBatch> = Vec::new();
+    for attempt in &attempts {
+        let split = match batches.last() {
+            None => true,
+            Some(current) => attempt.fresh_start
+                || should_split(settings, &current.started_at, &current.last_attempt, &attempt.timestamp),
+        };
+
+        if split {
+            batches.push(NewBatch {
+                started_at: attempt.timestamp,
+                last_attempt: attempt.timestamp,
+                is_fresh_start: attempt.fresh_start,
+                attempt_ids: vec![attempt.id],
+            });
+        } else if let Some(current) = batches.last_mut() {
+            current.last_attempt = attempt.timestamp;
+            current.attempt_ids.push(attempt.id);
+        }
+    }
+
+    // Move the old batches out of the way of UNIQUE(problem_id, batch_number)
+    conn.execute(
+        "UPDATE Batches SET batch_number = -batch_number WHERE problem_id = ?1",
+        params![problem_id],
+    ).map_err(|e| e.to_string())?;
+
+    let now = now_utc();
+    let batch_count = batches.len();
+    let mut attempt_number = 0;
+
+    for (index, batch) in batches.iter().enumerate() {
+        // The latest batch stays open until the rules say it is over
+        let is_last = index + 1 == batch_count;
+        let ended_at = if is_last && !should_split(settings, &batch.started_at, &batch.last_attempt, &now) {
+            None
+        } else {
+            Some(format_sqlite_time(&close_time(settings, &batch.started_at, &batch.last_attempt)))
+        };
+
+        conn.execute(
+            "INSERT INTO Batches (problem_id, batch_number, started_at, ended_at, is_fresh_start)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                problem_id,
+                index as i32 + 1,
+                format_sqlite_time(&batch.started_at),
+                ended_at,
+                batch.is_fresh_start,
+            ],
+        ).map_err(|e| e.to_string())?;
+        let batch_id = conn.last_insert_rowid();
+
+        for attempt_id in &batch.attempt_ids {
+            attempt_number += 1;
+            conn.execute(
+                "UPDATE Attempts SET batch_id = ?1, attempt_number = ?2 WHERE id = ?3",
+                params![batch_id, attempt_number, attempt_id],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+
+    conn.execute(
+        "DELETE FROM Batches WHERE problem_id = ?1 AND batch_number <= 0",
+        params![problem_id],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(ResegmentSummary {
+        problem_id,
+        batches_before,
+        batches_after: batch_count,
+        attempts: attempts.len(),
+    })
+}
+
+// Split the attempts parked in `holding_batch_id` into batches of their own
+// under `settings`, leaving the problem's other batches as they are, then
+// renumber chronologically. Returns whether attempts that were already there
+// got new numbers because added ones came before them. Run inside a
+// transaction.
+pub fn segment_added_attempts(
+    conn: &Connection,
+    problem_id: i64,
+    holding_batch_id: i64,
+    settings: &SegmentationSettings,
+) -> Result<bool, String> {
+    let added = conn.prepare(
+        "SELECT id, timestamp FROM Attempts WHERE batch_id = ?1 ORDER BY timestamp ASC, id ASC"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![holding_batch_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let Some((_, earliest)) = added.first() else {
+        conn.execute("DELETE FROM Batches WHERE id = ?1", params![holding_batch_id])
+            .map_err(|e| e.to_string())?;
+        return Ok(false);
+    };
+
+    let renumbered: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM Attempts a JOIN Batches b ON a.batch_id = b.id
+                       WHERE b.problem_id = ?1 AND a.batch_id <> ?2 AND a.timestamp > ?3)",
+        params![problem_id, holding_batch_id, earliest],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let mut batches: Vec<NewBatch> = Vec::new();
+    for (id, timestamp) in &added {
+        let timestamp = parse_sqlite_time(timestamp)?;
+        match batches.last_mut() {
+            Some(current) if !should_split(settings, &current.started_at, &current.last_attempt, &timestamp) => {
+                current.last_attempt = timestamp;
+                current.attempt_ids.push(*id);
+            }
+            _ => batches.push(NewBatch {
+                started_at: timestamp,
+                last_attempt: timestamp,
+                is_fresh_start: false,
+                attempt_ids: vec![*id],
+            }),
+        }
+    }
+
+    for batch in &batches {
+        conn.execute(
+            "INSERT INTO Batches (problem_id, batch_number, started_at, ended_at)
+             VALUES (?1, (SELECT MAX(batch_number) + 1 FROM Batches WHERE problem_id = ?1), ?2, ?3)",
+            params![
+                problem_id,
+                format_sqlite_time(&batch.started_at),
+                format_sqlite_time(&close_time(settings, &batch.started_at, &batch.last_attempt)),
+            ],
+        ).map_err(|e| e.to_string())?;
+        let batch_id = conn.last_insert_rowid();
+
+        for attempt_id in &batch.attempt_ids {
+            conn.execute(
+                "UPDATE Attempts SET batch_id = ?1 WHERE id = ?2",
+                params![batch_id, attempt_id],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    conn.execute("DELETE FROM Batches WHERE id = ?1", params![holding_batch_id])
+        .map_err(|e| e.to_string())?;
+
+    renumber_problem(conn, problem_id)?;
+    close_finished_batches(conn, problem_id, settings)?;
+
+    Ok(renumbered)
+}
+
+// Close every batch but the latest, and the latest too once the rules say
+// it is over
+fn close_finished_batches(conn: &Connection, problem_id: i64, settings: &SegmentationSettings) -> Result<(), String> {
+    let batches = conn.prepare(
+        "SELECT b.id, b.started_at, b.ended_at, MAX(a.timestamp)
+         FROM Batches b
+         LEFT JOIN Attempts a ON a.batch_id = b.id
+         WHERE b.problem_id = ?1
+         GROUP BY b.id
+         ORDER BY b.batch_number ASC"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![problem_id], |row| Ok((
+        row.get::<_, i64>(0)?,
+        row.get::<_, String>(1)?,
+        row.get::<_, Option<String>>(2)?,
+        row.get::<_, Option<String>>(3)?,
+    )))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    let now = now_utc();
+    let batch_count = batches.len();
+    for (index, (batch_id, started_at, ended_at, last_attempt)) in batches.into_iter().enumerate() {
+        let started_at = parse_sqlite_time(&started_at)?;
+        let last_attempt = last_attempt.as_deref().map(parse_sqlite_time).transpose()?.unwrap_or(started_at);
+        let open = index + 1 == batch_count && !should_split(settings, &started_at, &last_attempt, &now);
+
+        let ended_at_now = if open {
+            None
+        } else {
+            // A batch that was already closed keeps its end time
+            Some(ended_at.unwrap_or_else(|| format_sqlite_time(&close_time(settings, &started_at, &last_attempt))))
+        };
+        conn.execute(
+            "UPDATE Batches SET ended_at = ?1 WHERE id = ?2",
+            params![ended_at_now, batch_id],
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Renumber a problem's batches and attempts chronologically without changing
+// which batch an attempt belongs to (used after merging histories)
+pub fn renumber_problem(conn: &Connection, problem_id: i64) -> Result<(), String> {
+    let batch_ids: Vec<i64> = conn.prepare(
+        "SELECT id FROM Batches WHERE problem_id = ?1 ORDER BY started_at ASC, id ASC"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![problem_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE Batches SET batch_number = -batch_number WHERE problem_id = ?1",
+        params![problem_id],
+    ).map_err(|e| e.to_string())?;
+
+    for (index, batch_id) in batch_ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE Batches SET batch_number = ?1 WHERE id = ?2",
+            params![index as i32 + 1, batch_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    let attempt_ids: Vec<i64> = conn.prepare(
+        "SELECT a.id FROM Attempts a
+         JOIN Batches b ON a.batch_id = b.id
+         WHERE b.problem_id = ?1
+         ORDER BY a.timestamp ASC, a.id ASC"
+    )
+    .map_err(|e| e.to_string())?
+    .query_map(params![problem_id], |row| row.get(0))
+    .map_err(|e| e.to_string())?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| e.to_string())?;
+
+    for (index, attempt_id) in attempt_ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE Attempts SET attempt_number = ?1 WHERE id = ?2",
+            params![index as i32 + 1, attempt_id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn attempt(conn: &Connection, batch_id: i64, timestamp: &str) -> i64 {
+        conn.execute(
+            "INSERT INTO Attempts (batch_id, attempt_number, timestamp) VALUES (?1, 0, ?2)",
+            params![batch_id, timestamp],
+        ).unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn batch(conn: &Connection, batch_number: i32, started_at: &str) -> i64 {
+        conn.execute(
+            "INSERT INTO Batches (problem_id, batch_number, started_at) VALUES (1, ?1, ?2)",
+            params![batch_number, started_at],
+        ).unwrap();
+        conn.last_insert_rowid()
+    }
+
+    // (batch number, attempt number) of each attempt, by attempt id
+    fn numbering(conn: &Connection) -> Vec<(i64, i32, i32)> {
+        conn.prepare(
+            "SELECT a.id, b.batch_number, a.attempt_number FROM Attempts a
+             JOIN Batches b ON a.batch_id = b.id ORDER BY a.id"
+        ).unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).unwrap()
+        .collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
+    #[test]
+    fn added_attempts_are_batched_without_touching_existing_batches() {
+        let conn = db::open_in_memory();
+        conn.execute_batch(
+            "INSERT INTO Materials (id, name_en) VALUES (1, 'Graphs');
+             INSERT INTO Problems (id, generated_id, material_id, title) VALUES (1, 'ALG-1', 1, 'BFS');",
+        ).unwrap();
+        let settings = SegmentationSettings::default();
+
+        // Two attempts an hour apart that the gap rule would put together,
+        // kept in separate batches by hand
+        let first = batch(&conn, 1, "2025-01-05 10:00:00");
+        let a = attempt(&conn, first, "2025-01-05 10:00:00");
+        let second = batch(&conn, 2, "2025-01-05 11:00:00");
+        let b = attempt(&conn, second, "2025-01-05 11:00:00");
+
+        let holding = batch(&conn, 3, "2025-01-01 09:00:00");
+        let c = attempt(&conn, holding, "2025-01-01 09:00:00");
+        let d = attempt(&conn, holding, "2025-01-01 10:00:00");
+        let e = attempt(&conn, holding, "2025-01-02 09:00:00");
+
+        assert!(segment_added_attempts(&conn, 1, holding, &settings).unwrap());
+        assert_eq!(numbering(&conn), vec![(a, 3, 4), (b, 4, 5), (c, 1, 1), (d, 1, 2), (e, 2, 3)]);
+
+        let open: i64 = conn.query_row("SELECT COUNT(*) FROM Batches WHERE ended_at IS NULL", [], |row| row.get(0)).unwrap();
+        assert_eq!(open, 0);
+    }
+
+    #[test]
+    fn attempts_added_after_the_history_renumber_nothing() {
+        let conn = db::open_in_memory();
+        conn.execute_batch(
+            "INSERT INTO Materials (id, name_en) VALUES (1, 'Graphs');
+             INSERT INTO Problems (id, generated_id, material_id, title) VALUES (1, 'ALG-1', 1, 'BFS');",
+        ).unwrap();
+
+        let first = batch(&conn, 1, "2025-01-01 10:00:00");
+        let a = attempt(&conn, first, "2025-01-01 10:00:00");
+        conn.execute("UPDATE Attempts SET attempt_number = 1", []).unwrap();
+        let holding = batch(&conn, 2, "2025-01-03 10:00:00");
+        let b = attempt(&conn, holding, "2025-01-03 10:00:00");
+
+        assert!(!segment_added_attempts(&conn, 1, holding, &SegmentationSettings::default()).unwrap());
+        assert_eq!(numbering(&conn), vec![(a, 1, 1), (b, 2, 2)]);
+    }
+}
@@ -1,10 +1,9 @@
 use rusqlite::{Connection, params};
 
-pub fn generate_problem_id(
-    conn: &Connection,
-    subject_name: &str,
-) -> Result<String, String> {
-    // Get subject prefix (up to 4 letters, uppercase)
+pub mod time;
+
+// Subject prefix of generated problem IDs (up to 4 letters, uppercase)
+pub fn problem_id_prefix(subject_name: &str) -> String {
     let subject_prefix: String = subject_name
         .chars()
         .filter(|c| c.is_alphabetic())
@@ -13,11 +12,18 @@ pub fn generate_problem_id(
         .to_uppercase();
     
     // If no letters, use "PROB" as default
-    let subject_prefix = if subject_prefix.is_empty() {
+    if subject_prefix.is_empty() {
         "PROB".to_string()
     } else {
         subject_prefix
-    };
+    }
+}
+
+pub fn generate_problem_id(
+    conn: &Connection,
+    subject_name: &str,
+) -> Result<String, String> {
+    let subject_prefix = problem_id_prefix(subject_name);
     
     // Find next available ID by checking what exists
     // Loop until we find a unique ID
@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+
+// Format produced by SQLite's datetime('now'), always UTC
+pub const SQLITE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn parse_sqlite_time(value: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(value, SQLITE_TIME_FORMAT)
+        .map_err(|e| format!("Time parse error: {}", e))
+}
+
+pub fn format_sqlite_time(value: &NaiveDateTime) -> String {
+    value.format(SQLITE_TIME_FORMAT).to_string()
+}
+
+pub fn now_utc() -> NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+}
+
+// Fractional days between two timestamps (negative if `to` is earlier)
+pub fn days_between(from: &NaiveDateTime, to: &NaiveDateTime) -> f64 {
+    to.signed_duration_since(*from).num_seconds() as f64 / 86_400.0
+}
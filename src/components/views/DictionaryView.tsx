@@ -28,12 +28,20 @@ export function DictionaryView() {
   })
 
   const addMutation = useMutation({
-    mutationFn: () => api.addVocabulary(
-      wordRu,
-      translationEn,
-      materialName || undefined,
-      exampleSentence || undefined
-    ),
+    mutationFn: async () => {
+      // Words that may be forms of an existing entry are only grouped on confirmation
+      const candidate = await api.findVocabularyGroup(wordRu)
+      const asksToGroup = candidate !== null && candidate.word_ru !== wordRu
+      const grouped = asksToGroup && confirm(`Add "${wordRu}" as a form of "${candidate.word_ru}"?`)
+      return api.addVocabulary(
+        wordRu,
+        translationEn,
+        materialName || undefined,
+        exampleSentence || undefined,
+        asksToGroup && !grouped,
+        grouped ? candidate.id : undefined
+      )
+    },
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ['vocabulary'] })
       setWordRu('')
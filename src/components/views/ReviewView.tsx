@@ -24,6 +24,15 @@ export function ReviewView() {
     queryFn: () => api.getRecentProblems(100),
   })
 
+  const since = new Date()
+  since.setDate(since.getDate() - days)
+  const sinceDate = since.toISOString().slice(0, 10)
+
+  const { data: breakdown } = useQuery({
+    queryKey: ['error-breakdown', sinceDate],
+    queryFn: () => api.getErrorBreakdown({ since: sinceDate, failed_only: true }),
+  })
+
   // Filter recent attempts
   const filteredProblems = problems
     ?.map((p) => ({
@@ -43,26 +52,28 @@ export function ReviewView() {
       return true
     })
 
-  // Group by error patterns
+  // Group by the error categories the backend tagged each attempt with
   const errorGroups: ErrorGroup = filteredProblems?.reduce((acc, problem) => {
     problem.recentAttempts
       .filter((a) => !a.successful && a.errors)
       .forEach((attempt) => {
-        const errors = attempt.errors!.toLowerCase()
-        let category = 'Other'
-        
-        if (errors.includes('syntax') || errors.includes('type')) category = 'Syntax/Type'
-        else if (errors.includes('logic') || errors.includes('algorithm')) category = 'Logic'
-        else if (errors.includes('off by one') || errors.includes('boundary')) category = 'Boundary'
-        else if (errors.includes('time') || errors.includes('timeout')) category = 'Performance'
-        else if (errors.includes('forgot') || errors.includes('missed')) category = 'Recall'
-        
-        if (!acc[category]) acc[category] = []
-        acc[category].push({ problem, attempt })
+        const categories = attempt.error_categories.length > 0 ? attempt.error_categories : ['Other']
+        categories.forEach((category) => {
+          if (!acc[category]) acc[category] = []
+          acc[category].push({ problem, attempt })
+        })
       })
     return acc
   }, {} as ErrorGroup) ?? {}
 
+  // Most frequent categories first, by the backend breakdown of the period.
+  // Shares are of all failed attempts; one can count towards several categories.
+  const categoryTotals = new Map(breakdown?.categories.map((c) => [c.name, c]) ?? [])
+  const sortedErrorGroups = Object.entries(errorGroups).sort(
+    ([a, itemsA], [b, itemsB]) =>
+      (categoryTotals.get(b)?.total ?? itemsB.length) - (categoryTotals.get(a)?.total ?? itemsA.length)
+  )
+
   // Group by material
   const materialGroups: MaterialGroup = filteredProblems?.reduce((acc, problem) => {
     const key = `${problem.subject_name} - ${problem.material_name}`
@@ -151,7 +162,7 @@ export function ReviewView() {
             Errors by Pattern
           </h3>
 
-          {Object.entries(errorGroups).map(([category, items]) => (
+          {sortedErrorGroups.map(([category, items]) => (
             <ViewCard key={category} density={density}>
               <div className="flex items-center justify-between">
                 <h4 className={`font-semibold flex items-center gap-2 ${density === 'compact' ? 'text-xs' : 'text-sm'}`}>
@@ -160,6 +171,8 @@ export function ReviewView() {
                 </h4>
                 <Badge variant="secondary" className={density === 'compact' ? 'text-[10px]' : 'text-xs'}>
                   {items.length}
+                  {breakdown && breakdown.attempts > 0 && categoryTotals.has(category) &&
+                    ` • ${Math.round((categoryTotals.get(category)!.total / breakdown.attempts) * 100)}% of failures`}
                 </Badge>
               </div>
 
@@ -3,10 +3,62 @@ import type {
   AttemptInput,
   LogAttemptResponse,
   ProblemDetail,
+  Subject,
+  Material,
+  SubjectUpdate,
+  MaterialMerge,
+  ProblemMove,
+  ProblemMerge,
+  DeletionPreview,
   MaterialStats,
   BatchStats,
   VocabularyEntry,
+  VocabularyGrammar,
+  VocabularyFilter,
   DrillAttempt,
+  NewVocabularyWord,
+  VocabularyBatchResult,
+  LogDrillResponse,
+  VocabularyExtraction,
+  MaterialTerm,
+  MaterialReadiness,
+  RubricInput,
+  Script,
+  TransliterationScheme,
+  FormDrillKind,
+  FormDrill,
+  FormAnswerResult,
+  SchedulerSettings,
+  DueProblem,
+  Grade,
+  MasteryPolicy,
+  RecomputeSummary,
+  ErrorCategory,
+  ErrorRuleKind,
+  ReclassifySummary,
+  ErrorBreakdownFilter,
+  ErrorBreakdown,
+  RecurringMistake,
+  Resource,
+  ResourceType,
+  AttemptResource,
+  ResourceEffectiveness,
+  SegmentationSettings,
+  ResegmentSummary,
+  BackupManifest,
+  BackupInfo,
+  AutoBackupSettings,
+  ImportMode,
+  ImportSummary,
+  CsvImportOptions,
+  CsvImportSummary,
+  AnkiExportFilter,
+  AnkiExportSummary,
+  VocabularyImportOptions,
+  VocabularyImportSummary,
+  VaultExportSummary,
+  SearchScope,
+  SearchResults,
 } from './types'
 
 
@@ -47,6 +99,73 @@ export const api = {
     return await invoke<Array<{ name_en: string; name_ru?: string }>>('get_materials_for_subject', { subjectName })
   },
 
+  // Subject and material management
+  getSubjectDetails: async () => {
+    return await invoke<Subject[]>('get_subject_details')
+  },
+
+  getMaterialDetails: async (subjectName?: string) => {
+    return await invoke<Material[]>('get_material_details', { subjectName })
+  },
+
+  createSubject: async (name: string, description?: string, commentary?: string) => {
+    return await invoke<Subject>('create_subject', { name, description, commentary })
+  },
+
+  updateSubject: async (
+    subjectId: number,
+    name: string,
+    description?: string,
+    commentary?: string,
+    regenerateIds?: boolean
+  ) => {
+    return await invoke<SubjectUpdate>('update_subject', { subjectId, name, description, commentary, regenerateIds })
+  },
+
+  previewSubjectDeletion: async (subjectId: number) => {
+    return await invoke<DeletionPreview>('preview_subject_deletion', { subjectId })
+  },
+
+  deleteSubject: async (subjectId: number) => {
+    return await invoke<DeletionPreview>('delete_subject', { subjectId })
+  },
+
+  createMaterial: async (subjectId: number, nameEn: string, nameRu?: string, commentary?: string) => {
+    return await invoke<Material>('create_material', { subjectId, nameEn, nameRu, commentary })
+  },
+
+  updateMaterial: async (materialId: number, nameEn: string, nameRu?: string, commentary?: string) => {
+    return await invoke<Material>('update_material', { materialId, nameEn, nameRu, commentary })
+  },
+
+  mergeMaterials: async (keepId: number, mergeId: number) => {
+    return await invoke<MaterialMerge>('merge_materials', { keepId, mergeId })
+  },
+
+  linkMaterialToSubject: async (subjectId: number, materialId: number) => {
+    return await invoke<RecomputeSummary>('link_material_to_subject', { subjectId, materialId })
+  },
+
+  unlinkMaterialFromSubject: async (subjectId: number, materialId: number) => {
+    return await invoke<RecomputeSummary>('unlink_material_from_subject', { subjectId, materialId })
+  },
+
+  previewMaterialDeletion: async (materialId: number) => {
+    return await invoke<DeletionPreview>('preview_material_deletion', { materialId })
+  },
+
+  deleteMaterial: async (materialId: number) => {
+    return await invoke<DeletionPreview>('delete_material', { materialId })
+  },
+
+  moveProblem: async (problemId: number, materialName: string, regenerateId?: boolean) => {
+    return await invoke<ProblemMove>('move_problem', { problemId, materialName, regenerateId })
+  },
+
+  mergeProblems: async (keepId: number, mergeId: number) => {
+    return await invoke<ProblemMerge>('merge_problems', { keepId, mergeId })
+  },
+
   getProblemsForMaterial: async (materialName: string) => {
     return await invoke<Array<{ id: number; title: string; generated_id: string }>>(
       'get_problems_for_material',
@@ -88,15 +207,33 @@ export const api = {
   },
 
   // Russian Drilling
-  addVocabulary: async (wordRu: string, translationEn: string, materialName?: string, exampleSentence?: string) => {
+  addVocabulary: async (wordRu: string, translationEn: string, materialName?: string, exampleSentence?: string, keepSeparate?: boolean, groupWith?: number) => {
     return await invoke<number>('add_vocabulary', {
       wordRu,
       translationEn,
       materialName,
-      exampleSentence
+      exampleSentence,
+      keepSeparate,
+      groupWith
     })
   },
 
+  addVocabularyBatch: async (words: NewVocabularyWord[], materialName?: string, drillId?: number) => {
+    return await invoke<VocabularyBatchResult>('add_vocabulary_batch', { words, materialName, drillId })
+  },
+
+  findVocabularyGroup: async (wordRu: string) => {
+    return await invoke<VocabularyEntry | null>('find_vocabulary_group', { wordRu })
+  },
+
+  updateVocabularyGrammar: async (wordId: number, grammar: VocabularyGrammar) => {
+    return await invoke<VocabularyEntry>('update_vocabulary_grammar', { wordId, grammar })
+  },
+
+  queryVocabulary: async (filter: VocabularyFilter) => {
+    return await invoke<VocabularyEntry[]>('query_vocabulary', { filter })
+  },
+
   getAllVocabulary: async () => {
     return await invoke<VocabularyEntry[]>('get_all_vocabulary')
   },
@@ -105,15 +242,35 @@ export const api = {
     return await invoke<VocabularyEntry[]>('search_vocabulary', { searchTerm })
   },
 
+  convertScript: async (text: string, target: Script, scheme?: TransliterationScheme) => {
+    return await invoke<string>('convert_script', { text, target, scheme })
+  },
+
+  addMaterialTerm: async (materialName: string, termRu: string, translationEn?: string, notes?: string) => {
+    return await invoke<MaterialTerm>('add_material_term', { materialName, termRu, translationEn, notes })
+  },
+
+  removeMaterialTerm: async (termId: number) => {
+    return await invoke<void>('remove_material_term', { termId })
+  },
+
+  getMaterialTerms: async (materialName: string) => {
+    return await invoke<MaterialTerm[]>('get_material_terms', { materialName })
+  },
+
+  getMaterialRussianReadiness: async (materialName: string) => {
+    return await invoke<MaterialReadiness>('get_material_russian_readiness', { materialName })
+  },
+
   logDrillAttempt: async (
     materialName: string,
-    status: string,
+    status?: string,
     errorsRu?: string,
     resolutionRu?: string,
     commentary?: string,
     vocabularyWords: string[] = []
   ) => {
-    return await invoke<number>('log_drill_attempt', {
+    return await invoke<LogDrillResponse>('log_drill_attempt', {
       materialName,
       status,
       errorsRu,
@@ -123,10 +280,235 @@ export const api = {
     })
   },
 
+  extractDrillVocabulary: async (drillId: number) => {
+    return await invoke<VocabularyExtraction>('extract_drill_vocabulary', { drillId })
+  },
+
   getDrillHistory: async (limit: number = 20) => {
     return await invoke<DrillAttempt[]>('get_drill_history', { limit })
   },
 
+  scoreDrill: async (drillId: number, rubric: RubricInput) => {
+    return await invoke<DrillAttempt>('score_drill', { drillId, rubric })
+  },
+
+  generateFormDrill: async (materialName: string, count?: number, kinds: FormDrillKind[] = []) => {
+    return await invoke<FormDrill>('generate_form_drill', { materialName, count, kinds })
+  },
+
+  checkFormAnswer: async (exerciseId: number, answer: string) => {
+    return await invoke<FormAnswerResult>('check_form_answer', { exerciseId, answer })
+  },
+
+  getFormDrill: async (drillId: number) => {
+    return await invoke<FormDrill>('get_form_drill', { drillId })
+  },
+
+  reviewVocabulary: async (wordId: number, grade: Grade) => {
+    return await invoke<VocabularyEntry>('review_vocabulary', { wordId, grade })
+  },
+
+  getDueVocabulary: async (limit: number = 20) => {
+    return await invoke<VocabularyEntry[]>('get_due_vocabulary', { limit })
+  },
+
+  // Spaced repetition
+  getSchedulerSettings: async () => {
+    return await invoke<SchedulerSettings>('get_scheduler_settings')
+  },
+
+  updateSchedulerSettings: async (settings: SchedulerSettings) => {
+    return await invoke<number>('update_scheduler_settings', { settings })
+  },
+
+  recomputeSchedules: async () => {
+    return await invoke<number>('recompute_schedules')
+  },
+
+  getDueProblems: async (limit: number = 20) => {
+    return await invoke<DueProblem[]>('get_due_problems', { limit })
+  },
+
+  // Mastery policies
+  getMasteryPolicies: async () => {
+    return await invoke<MasteryPolicy[]>('get_mastery_policies')
+  },
+
+  getMasteryPolicy: async (subjectName?: string) => {
+    return await invoke<MasteryPolicy>('get_mastery_policy', { subjectName })
+  },
+
+  setMasteryPolicy: async (policy: MasteryPolicy) => {
+    return await invoke<RecomputeSummary>('set_mastery_policy', { policy })
+  },
+
+  deleteMasteryPolicy: async (subjectName: string) => {
+    return await invoke<RecomputeSummary>('delete_mastery_policy', { subjectName })
+  },
+
+  recomputeSolvedStatus: async (subjectName?: string) => {
+    return await invoke<RecomputeSummary>('recompute_solved_status', { subjectName })
+  },
+
+  // Error categories
+  getErrorCategories: async () => {
+    return await invoke<ErrorCategory[]>('get_error_categories')
+  },
+
+  createErrorCategory: async (name: string, parentId?: number, description?: string) => {
+    return await invoke<ErrorCategory>('create_error_category', { name, parentId, description })
+  },
+
+  updateErrorCategory: async (categoryId: number, name: string, parentId?: number, description?: string) => {
+    return await invoke<ErrorCategory>('update_error_category', { categoryId, name, parentId, description })
+  },
+
+  deleteErrorCategory: async (categoryId: number) => {
+    return await invoke<void>('delete_error_category', { categoryId })
+  },
+
+  addErrorRule: async (categoryId: number, kind: ErrorRuleKind, pattern: string) => {
+    return await invoke<ReclassifySummary>('add_error_rule', { categoryId, kind, pattern })
+  },
+
+  deleteErrorRule: async (ruleId: number) => {
+    return await invoke<ReclassifySummary>('delete_error_rule', { ruleId })
+  },
+
+  // Pass null to hand the attempt back to the rules
+  setAttemptErrorCategories: async (attemptId: number, categoryIds: number[] | null) => {
+    return await invoke<ErrorCategory[]>('set_attempt_error_categories', { attemptId, categoryIds })
+  },
+
+  getAttemptErrorCategories: async (attemptId: number) => {
+    return await invoke<ErrorCategory[]>('get_attempt_error_categories', { attemptId })
+  },
+
+  reclassifyAttempts: async () => {
+    return await invoke<ReclassifySummary>('reclassify_attempts')
+  },
+
+  getErrorBreakdown: async (filters: ErrorBreakdownFilter = {}) => {
+    return await invoke<ErrorBreakdown>('get_error_breakdown', { filters })
+  },
+
+  getRecurringMistakes: async (minOccurrences?: number, since?: string) => {
+    return await invoke<RecurringMistake[]>('get_recurring_mistakes', { minOccurrences, since })
+  },
+
+  // Resources
+  getResources: async () => {
+    return await invoke<Resource[]>('get_resources')
+  },
+
+  createResource: async (name: string, resourceType: ResourceType, url?: string) => {
+    return await invoke<Resource>('create_resource', { name, resourceType, url })
+  },
+
+  updateResource: async (resourceId: number, name: string, resourceType: ResourceType, url?: string) => {
+    return await invoke<Resource>('update_resource', { resourceId, name, resourceType, url })
+  },
+
+  deleteResource: async (resourceId: number) => {
+    return await invoke<number>('delete_resource', { resourceId })
+  },
+
+  mergeResources: async (keepId: number, mergeIds: number[]) => {
+    return await invoke<Resource>('merge_resources', { keepId, mergeIds })
+  },
+
+  findDuplicateResources: async () => {
+    return await invoke<Resource[][]>('find_duplicate_resources')
+  },
+
+  getAttemptResources: async (attemptId: number) => {
+    return await invoke<AttemptResource[]>('get_attempt_resources', { attemptId })
+  },
+
+  setAttemptResource: async (attemptId: number, resourceId: number, notes?: string) => {
+    return await invoke<AttemptResource[]>('set_attempt_resource', { attemptId, resourceId, notes })
+  },
+
+  unlinkAttemptResource: async (attemptId: number, resourceId: number) => {
+    return await invoke<AttemptResource[]>('unlink_attempt_resource', { attemptId, resourceId })
+  },
+
+  getResourceEffectiveness: async (materialName?: string) => {
+    return await invoke<ResourceEffectiveness[]>('get_resource_effectiveness', { materialName })
+  },
+
+  // Batch segmentation
+  getSegmentationSettings: async () => {
+    return await invoke<SegmentationSettings>('get_segmentation_settings')
+  },
+
+  updateSegmentationSettings: async (settings: SegmentationSettings) => {
+    return await invoke<void>('update_segmentation_settings', { settings })
+  },
+
+  closeBatch: async (problemId: number) => {
+    return await invoke<boolean>('close_batch', { problemId })
+  },
+
+  resegmentProblem: async (problemId: number) => {
+    return await invoke<ResegmentSummary>('resegment_problem', { problemId })
+  },
+
+  // Backup & restore
+  createBackup: async (path: string) => {
+    return await invoke<BackupManifest>('create_backup', { path })
+  },
+
+  restoreBackup: async (path: string) => {
+    return await invoke<BackupManifest>('restore_backup', { path })
+  },
+
+  listBackups: async () => {
+    return await invoke<BackupInfo[]>('list_backups')
+  },
+
+  restoreBackupById: async (id: string) => {
+    return await invoke<BackupManifest>('restore_backup_by_id', { id })
+  },
+
+  getAutoBackupSettings: async () => {
+    return await invoke<AutoBackupSettings>('get_auto_backup_settings')
+  },
+
+  updateAutoBackupSettings: async (settings: AutoBackupSettings) => {
+    return await invoke<string[]>('update_auto_backup_settings', { settings })
+  },
+
+  // Data interchange
+  exportDataset: async (path: string) => {
+    return await invoke<Record<string, number>>('export_dataset', { path })
+  },
+
+  importDataset: async (path: string, mode: ImportMode) => {
+    return await invoke<ImportSummary>('import_dataset', { path, mode })
+  },
+
+  importAttemptsCsv: async (path: string, options: CsvImportOptions) => {
+    return await invoke<CsvImportSummary>('import_attempts_csv', { path, options })
+  },
+
+  exportAnki: async (path: string, filter: AnkiExportFilter) => {
+    return await invoke<AnkiExportSummary>('export_anki', { path, filter })
+  },
+
+  importVocabulary: async (path: string, options: VocabularyImportOptions) => {
+    return await invoke<VocabularyImportSummary>('import_vocabulary', { path, options })
+  },
+
+  exportMarkdownVault: async (dir: string) => {
+    return await invoke<VaultExportSummary>('export_markdown_vault', { dir })
+  },
+
+  // Search
+  globalSearch: async (query: string, scopes: SearchScope[] = [], limit?: number, offset?: number) => {
+    return await invoke<SearchResults>('global_search', { query, scopes, limit, offset })
+  },
+
   getProblemByGeneratedId: async (generatedId: string) => {
     return await invoke<ProblemDetail>('get_problem_by_generated_id', { generatedId })
   },
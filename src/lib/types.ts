@@ -43,6 +43,157 @@ export interface LogAttemptResponse {
   batch_number: number
   attempt_number: number
   batch_closed: boolean
+  error_categories: string[]
+  recurring_mistake: RecurringMistakeWarning | null
+}
+
+export interface RecurringMistakeWarning {
+  previous_occurrences: number
+  similar_attempt_ids: number[]
+  message: string
+}
+
+export interface MistakeOccurrence {
+  attempt_id: number
+  problem_id: number
+  generated_id: string
+  problem_title: string
+  material_name: string
+  successful: boolean
+  errors: string
+  timestamp: string
+}
+
+export interface RecurringMistake {
+  label: string[]
+  occurrences: number
+  problems: number
+  materials: string[]
+  first_seen: string
+  last_seen: string
+  attempts: MistakeOccurrence[]
+}
+
+export type ResourceType = 'ai' | 'book' | 'video' | 'web' | 'human' | 'other'
+
+export interface Resource {
+  id: number
+  name: string
+  type: ResourceType
+  url: string | null
+  created_at: string
+  usage_count: number
+  last_used: string | null
+}
+
+export interface AttemptResource {
+  resource_id: number
+  name: string
+  type: ResourceType
+  url: string | null
+  notes: string | null
+}
+
+export interface EffectivenessStats {
+  uses: number
+  problems: number
+  next_attempt_success_rate: number | null
+  baseline_next_attempt_success_rate: number | null
+  solved_problems: number
+  avg_attempts_to_mastery: number | null
+  baseline_attempts_to_mastery: number | null
+  avg_days_to_mastery: number | null
+  baseline_days_to_mastery: number | null
+}
+
+export interface MaterialEffectiveness {
+  material_name: string
+  stats: EffectivenessStats
+}
+
+export interface ResourceEffectiveness {
+  resource_id: number
+  name: string
+  type: ResourceType
+  overall: EffectivenessStats
+  materials: MaterialEffectiveness[]
+}
+
+export interface Subject {
+  id: number
+  name: string
+  description: string | null
+  commentary: string | null
+  id_prefix: string
+  material_count: number
+  problem_count: number
+  created_at: string
+  updated_at: string
+}
+
+export interface Material {
+  id: number
+  name_en: string
+  name_ru: string | null
+  commentary: string | null
+  subjects: string[]
+  problem_count: number
+  drill_count: number
+  vocabulary_count: number
+  created_at: string
+  updated_at: string
+}
+
+export interface IdChange {
+  problem_id: number
+  old_id: string
+  new_id: string
+}
+
+export interface SubjectUpdate {
+  subject: Subject
+  regenerated: IdChange[]
+}
+
+export interface MaterialMerge {
+  material: Material
+  problems_moved: number
+  problems_merged: number
+  drills_moved: number
+  vocabulary_moved: number
+  terms_moved: number
+  solved_status: RecomputeSummary
+}
+
+export interface ProblemMove {
+  problem_id: number
+  generated_id: string
+  old_generated_id: string | null
+  material_name: string
+  is_solved: boolean
+}
+
+export interface ProblemMerge {
+  problem_id: number
+  generated_id: string
+  merged_generated_id: string
+  batches: number
+  attempts: number
+  is_solved: boolean
+  unused_images: string[]
+}
+
+export interface DeletionPreview {
+  materials: string[]
+  kept_materials: string[]
+  problems: number
+  batches: number
+  attempts: number
+  drills: number
+  terms: number
+  vocabulary_unlinked: number
+  mastery_policy: boolean
+  image_files: string[]
 }
 
 export interface AttemptView {
@@ -57,6 +208,7 @@ export interface AttemptView {
   resolution?: string
   commentary?: string
   timestamp: string
+  error_categories: string[]
   resources?: Array<{ name: string }>
 }
 
@@ -109,6 +261,125 @@ export interface VocabularyEntry {
   first_seen: string
   last_reviewed?: string
   review_count: number
+  ease: number
+  interval_days: number
+  lapses: number
+  due_at?: string
+  lemma?: string
+  forms: string[]
+  grammar: VocabularyGrammar
+}
+
+export type PartOfSpeech =
+  | 'noun' | 'verb' | 'adjective' | 'adverb' | 'pronoun' | 'numeral'
+  | 'preposition' | 'conjunction' | 'particle' | 'interjection' | 'phrase'
+
+export type Gender = 'masculine' | 'feminine' | 'neuter' | 'common'
+
+export type Aspect = 'imperfective' | 'perfective' | 'biaspectual'
+
+export type GrammaticalCase =
+  | 'nominative' | 'genitive' | 'dative' | 'accusative' | 'instrumental' | 'prepositional'
+
+export interface Government {
+  preposition: string | null
+  case: GrammaticalCase
+  note: string | null
+}
+
+export interface VocabularyGrammar {
+  part_of_speech: PartOfSpeech | null
+  gender: Gender | null
+  animate: boolean | null
+  aspect: Aspect | null
+  aspect_partner: string | null
+  // 1-based index of the stressed vowel
+  stress_position: number | null
+  government: Government[]
+  // e.g. { gen_pl: 'людей', pres_1sg: 'хочу' }
+  irregular_forms: Record<string, string>
+}
+
+export interface VocabularyFilter {
+  material_name?: string
+  part_of_speech?: PartOfSpeech
+  gender?: Gender
+  aspect?: Aspect
+  animate?: boolean
+  governs_case?: GrammaticalCase
+  preposition?: string
+  has_irregular_forms?: boolean
+}
+
+export type Script = 'cyrillic' | 'latin'
+
+export type TransliterationScheme = 'iso9' | 'gost' | 'informal' | 'layout'
+
+export interface NewVocabularyWord {
+  word_ru: string
+  translation_en: string
+  example_sentence?: string
+  keep_separate?: boolean
+  group_with?: number
+}
+
+export interface VocabularyBatchResult {
+  vocabulary_ids: number[]
+  added: number
+  grouped: number
+  skipped: { word_ru: string; reason: string }[]
+}
+
+export interface LinkedWord {
+  vocabulary_id: number
+  word_ru: string
+}
+
+export interface UnknownWord {
+  word: string
+  forms: string[]
+  occurrences: number
+}
+
+export interface VocabularyExtraction {
+  linked: LinkedWord[]
+  unknown: UnknownWord[]
+}
+
+export interface LogDrillResponse {
+  drill_id: number
+  attempt_number: number
+  linked_words: LinkedWord[]
+  unknown_words: UnknownWord[]
+}
+
+export interface MaterialTerm {
+  id: number
+  material_name: string
+  term_ru: string
+  translation_en?: string
+  notes?: string
+  created_at: string
+  vocabulary_id: number | null
+  drill_count: number
+  last_seen: string | null
+}
+
+export interface MaterialReadiness {
+  material_name: string
+  name_ru?: string
+  problems_total: number
+  problems_solved: number
+  english_mastery: number
+  terms_total: number
+  terms_complete: number
+  glossary_completeness: number | null
+  incomplete_terms: string[]
+  terms_covered: number
+  term_coverage: number | null
+  missing_terms: string[]
+  ready: boolean
+  blockers: string[]
 }
 
 export interface DrillAttempt {
@@ -120,4 +391,340 @@ export interface DrillAttempt {
   errors_ru?: string
   resolution_ru?: string
   timestamp: string
+  rubric: DrillRubric | null
+}
+
+export interface RubricInput {
+  fluency: number
+  accuracy: number
+  completeness: number
+}
+
+export interface DrillRubric extends RubricInput {
+  terminology_coverage: number | null
+  terms_used: string[]
+  terms_missing: string[]
+  overall: number
+  scored_at: string
+}
+
+export type FormDrillKind = 'declension' | 'conjugation' | 'past' | 'imperative'
+
+export interface FormExercise {
+  id: number
+  position: number
+  vocabulary_id: number | null
+  word_ru: string
+  translation_en: string | null
+  kind: FormDrillKind
+  form_key: string
+  prompt: string
+  answer: string | null
+  correct: boolean | null
+}
+
+export interface FormDrill {
+  drill_id: number
+  material_name: string
+  attempt_number: number
+  exercises: FormExercise[]
+}
+
+export interface FormAnswerResult {
+  exercise_id: number
+  drill_id: number
+  correct: boolean
+  answer: string
+  expected: string
+  accepted: string[]
+  answered: number
+  correct_count: number
+  total: number
+  status: 'learning' | 'practicing' | 'mastered'
+}
+
+export type Grade = 'again' | 'hard' | 'good' | 'easy'
+
+export type SchedulerAlgorithm = 'sm2' | 'fsrs'
+
+export interface SchedulerSettings {
+  algorithm: SchedulerAlgorithm
+  desired_retention: number
+}
+
+export interface DueProblem {
+  problem_id: number
+  generated_id: string
+  title: string
+  material_name: string
+  subject_name?: string
+  is_solved: boolean
+  algorithm: SchedulerAlgorithm
+  stability: number
+  difficulty?: number
+  interval_days: number
+  lapses: number
+  last_reviewed_at: string
+  due_at: string
+  overdue_days: number
+  retrievability: number
+}
+
+export interface MasteryPolicy {
+  subject_name?: string  // omitted for the global default
+  required_successes: number
+  min_distinct_batches: number
+  min_distinct_days: number
+  max_time_minutes?: number
+  max_difficulty?: number
+  require_fresh_start_success: boolean
+  reset_on_failure: boolean
+}
+
+export interface RecomputeSummary {
+  evaluated: number
+  newly_solved: number
+  newly_unsolved: number
+}
+
+export type ErrorRuleKind = 'keyword' | 'regex'
+
+export interface ErrorRule {
+  id: number
+  kind: ErrorRuleKind
+  pattern: string
+}
+
+export interface ErrorCategory {
+  id: number
+  name: string
+  parent_id: number | null
+  description?: string
+  rules: ErrorRule[]
+}
+
+export interface ReclassifySummary {
+  evaluated: number
+  tagged: number
+  manual: number
+}
+
+export type TrendPeriod = 'day' | 'week' | 'month'
+
+export interface ErrorBreakdownFilter {
+  subject_name?: string
+  material_name?: string
+  since?: string  // YYYY-MM-DD, inclusive
+  until?: string
+  failed_only?: boolean
+  period?: TrendPeriod
+}
+
+export interface CategoryBreakdown {
+  category_id: number
+  name: string
+  parent_id: number | null
+  count: number
+  total: number  // including subcategories
+  share: number
+  last_seen: string | null
+  trend: { period: string; count: number }[]
+}
+
+export interface ErrorBreakdown {
+  attempts: number
+  categorized: number
+  uncategorized: number
+  categories: CategoryBreakdown[]
+}
+
+export interface SegmentationSettings {
+  gap_hours: number
+  max_batch_hours?: number
+  close_at_day_boundary: boolean
+  utc_offset_minutes?: number  // omitted to use the system timezone
+}
+
+export interface ResegmentSummary {
+  problem_id: number
+  batches_before: number
+  batches_after: number
+  attempts: number
+}
+
+export interface BackupFile {
+  path: string
+  size: number
+  sha256: string
+}
+
+export interface BackupManifest {
+  format_version: number
+  app_version: string
+  schema_version: number
+  created_at: string
+  table_counts: Record<string, number>
+  files: BackupFile[]
+}
+
+export interface BackupInfo {
+  id: string
+  path: string
+  created_at: string
+  size_bytes: number
+  schema_version?: number
+  automatic: boolean
+}
+
+export interface AutoBackupSettings {
+  enabled: boolean
+  interval_hours: number
+  keep_daily: number
+  keep_weekly: number
+}
+
+export type ImportMode = 'merge' | 'replace'
+
+export interface TableImportCounts {
+  inserted: number
+  matched: number
+}
+
+export interface ImportConflict {
+  table: string
+  key: string
+  message: string
+}
+
+export interface ImportSummary {
+  mode: ImportMode
+  tables: Record<string, TableImportCounts>
+  conflicts: ImportConflict[]
+  problems_rescheduled: number
+}
+
+export interface CsvColumnMapping {
+  subject: string
+  material: string
+  problem_title: string
+  timestamp: string
+  success: string
+  minutes?: string
+  difficulty?: string
+  errors?: string
+  resolution?: string
+  tags?: string
+  resources?: string
+}
+
+export interface CsvImportOptions {
+  mapping: CsvColumnMapping
+  delimiter?: string
+  timestamp_format?: string
+  utc_offset_minutes?: number
+  dry_run: boolean
+}
+
+export interface CsvRowError {
+  line: number
+  column?: string
+  message: string
+}
+
+export interface CsvImportSummary {
+  dry_run: boolean
+  rows: number
+  imported: number
+  skipped_duplicates: number
+  subjects_created: number
+  materials_created: number
+  problems_created: number
+  problems_affected: number
+  problems_renumbered: number
+  newly_solved: number
+  errors: CsvRowError[]
+}
+
+export interface AnkiExportFilter {
+  include_vocabulary?: boolean
+  include_problems?: boolean
+  material_name?: string
+  subject_name?: string
+  include_solved?: boolean
+  deck_name?: string
+}
+
+export interface AnkiExportSummary {
+  vocabulary_notes: number
+  problem_notes: number
+  media_files: number
+  missing_images: string[]
+}
+
+// Field/column name, or zero-based position
+export type FieldRef = string | number
+
+export interface VocabularyFieldMapping {
+  word_ru: FieldRef
+  translation_en: FieldRef
+  example_sentence?: FieldRef
+}
+
+export type DuplicatePolicy = 'skip' | 'overwrite' | 'append_translation'
+
+export interface VocabularyImportOptions {
+  mapping: VocabularyFieldMapping
+  material_name?: string
+  duplicate_policy: DuplicatePolicy
+  delimiter?: string
+  has_header?: boolean
+  import_reviews?: boolean
+}
+
+export interface VocabularyImportSummary {
+  rows: number
+  inserted: number
+  updated: number
+  grouped: number
+  skipped_duplicates: number
+  skipped_invalid: number
+  reviews_imported: number
+  warnings: string[]
+}
+
+export interface VaultExportSummary {
+  problems: number
+  subjects: number
+  materials: number
+  written: number
+  unchanged: number
+  images_copied: number
+  missing_images: string[]
+}
+
+export type SearchScope = 'problems' | 'attempts' | 'drills' | 'vocabulary'
+
+export interface HighlightRange {
+  start: number
+  end: number
+}
+
+export interface SearchHit {
+  scope: SearchScope
+  id: number
+  title: string
+  problem_id: number | null
+  generated_id: string | null
+  material_name: string | null
+  timestamp: string | null
+  field: string
+  snippet: string
+  highlights: HighlightRange[]
+  rank: number
+}
+
+export interface SearchResults {
+  query: string
+  total: number
+  hits: SearchHit[]
 }